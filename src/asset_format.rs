@@ -1,19 +1,23 @@
 #![allow(unused_imports)]
 
-use std::sync::LazyLock;
+use std::sync::{Arc, LazyLock, PoisonError, RwLock};
 
 use bevy::asset::{
     AssetLoader, LoadContext,
     io::{Reader, VecReader},
 };
+use bevy::audio::AudioLoader;
+use bevy::ecs::error::BevyError;
 use bevy::image::{
-    CompressedImageFormats, ImageFormatSetting, ImageLoader, ImageLoaderError, ImageLoaderSettings,
+    CompressedImageFormats, ImageFormat, ImageFormatSetting, ImageLoader, ImageLoaderError,
+    ImageLoaderSettings,
 };
 use bevy::prelude::*;
+use bevy::text::FontLoader;
 
 use chacha20poly1305::{
     ChaCha8Poly1305, Key,
-    aead::{Aead, AeadCore, KeyInit},
+    aead::{Aead, AeadCore, KeyInit, Payload},
 };
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
@@ -21,8 +25,26 @@ use thiserror::Error;
 
 pub(super) fn plugin(app: &mut App) {
     app.init_asset_loader::<EncryptedImageLoader>();
+    app.register_asset_loader(EncryptedLoader::new(AudioLoader, &["eogg", "eoga", "espx"]));
+    app.register_asset_loader(EncryptedLoader::new(FontLoader, &["ettf", "eotf"]));
 }
 
+/// Marks an asset encrypted with a header, so older assets without one can be told apart
+const MAGIC: &[u8; 4] = b"GHFA";
+
+/// Version of the header written by [`EncryptedAsset::to_bytes`]. Assets without a header are
+/// version 0.
+const FORMAT_VERSION: u8 = 1;
+
+/// The key new assets are encrypted with
+///
+/// To rotate keys, add the new key to [`key`] and point this at it. Older keys must be kept, so
+/// assets encrypted with them still load until they're encrypted again.
+const CURRENT_KEY_ID: u8 = 0;
+
+/// Where the hashes of every asset's plaintext are listed, relative to the assets folder
+pub const MANIFEST_PATH: &str = "asset_manifest.ron";
+
 static KEY: LazyLock<[u8; 32]> = LazyLock::new(|| {
     let mut hasher = Sha256::new();
     hasher.update(env!("CARGO_PKG_NAME").as_bytes());
@@ -34,6 +56,14 @@ along with a URL where you can purchase them for yourself."
     hasher.finalize().into()
 });
 
+/// Returns the key with the given id, or none if there isn't one
+fn key(key_id: u8) -> Option<Key> {
+    match key_id {
+        0 => Some((*KEY).into()),
+        _ => None,
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum AssetFormatError {
     #[error(transparent)]
@@ -46,6 +76,22 @@ pub enum AssetFormatError {
     DeserializeError(#[from] flexbuffers::DeserializationError),
     #[error(transparent)]
     SerializeError(#[from] flexbuffers::SerializationError),
+    #[error("Failed to load the decrypted asset: {}", .0)]
+    Inner(BevyError),
+    #[error("The asset's header is cut short")]
+    TruncatedHeader,
+    #[error("The asset is format version {0}, which is newer than this game supports")]
+    UnsupportedVersion(u8),
+    #[error("The asset is encrypted with key {0}, which this game doesn't have")]
+    UnknownKey(u8),
+    #[error("The asset has been tampered with or corrupted, so it can't be decrypted")]
+    Tampered,
+    #[error("{0} isn't in the asset manifest")]
+    NotInManifest(String),
+    #[error("{0} doesn't match the asset manifest, so it's stale or has been replaced")]
+    Stale(String),
+    #[error(transparent)]
+    Manifest(#[from] ron::error::SpannedError),
 }
 
 impl From<chacha20poly1305::Error> for AssetFormatError {
@@ -61,30 +107,264 @@ impl From<flexbuffers::ReaderError> for AssetFormatError {
     }
 }
 
-#[derive(Reflect, Serialize, Deserialize, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
-pub struct EncryptedAsset {
+/// An asset as encrypted before the header was added
+#[derive(Serialize, Deserialize)]
+struct LegacyEncryptedAsset {
     #[serde(with = "serde_bytes")]
     nonce: [u8; 12],
     #[serde(with = "serde_bytes")]
     ciphertext: Vec<u8>,
 }
 
+/// An encrypted asset, with the header it's stored with
+///
+/// The header is the magic bytes, format version, key id and the original extension. It's
+/// authenticated along with the ciphertext, so it can't be changed without decryption failing.
+#[derive(Reflect, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct EncryptedAsset {
+    version: u8,
+    key_id: u8,
+    /// The extension of the asset before it was encrypted, or empty for assets without a header
+    extension: String,
+    nonce: [u8; 12],
+    ciphertext: Vec<u8>,
+}
+
 impl EncryptedAsset {
+    /// Encrypts an asset with the current key
     #[cfg(feature = "dev_native")]
-    pub fn encrypt(key: Key, plaintext: &[u8]) -> Result<EncryptedAsset, AssetFormatError> {
+    pub fn encrypt(plaintext: &[u8], extension: &str) -> Result<EncryptedAsset, AssetFormatError> {
+        let mut asset = EncryptedAsset {
+            version: FORMAT_VERSION,
+            key_id: CURRENT_KEY_ID,
+            extension: extension.to_string(),
+            nonce: [0; 12],
+            ciphertext: vec![],
+        };
+        let key = key(CURRENT_KEY_ID).ok_or(AssetFormatError::UnknownKey(CURRENT_KEY_ID))?;
         let cipher = ChaCha8Poly1305::new(&key);
         let nonce =
             ChaCha8Poly1305::generate_nonce().map_err(|e| std::io::Error::other(e.to_string()))?;
-        let ciphertext = cipher.encrypt(&nonce, plaintext)?;
-        let nonce = *nonce.as_ref();
-        Ok(EncryptedAsset { ciphertext, nonce })
+        let header = asset.header();
+        asset.ciphertext = cipher.encrypt(
+            &nonce,
+            Payload {
+                msg: plaintext,
+                aad: &header,
+            },
+        )?;
+        asset.nonce = *nonce.as_ref();
+        Ok(asset)
     }
 
-    pub fn decrypt(&self, key: Key) -> Result<Vec<u8>, AssetFormatError> {
+    pub fn decrypt(&self) -> Result<Vec<u8>, AssetFormatError> {
+        let key = key(self.key_id).ok_or(AssetFormatError::UnknownKey(self.key_id))?;
         let cipher = ChaCha8Poly1305::new(&key);
         let nonce = (&(self.nonce)).into();
-        let decrypted = cipher.decrypt(nonce, self.ciphertext.as_ref())?;
-        Ok(decrypted)
+        // Assets without a header only authenticate their ciphertext
+        let header = if self.version == 0 {
+            vec![]
+        } else {
+            self.header()
+        };
+        let payload = Payload {
+            msg: &self.ciphertext,
+            aad: &header,
+        };
+        cipher
+            .decrypt(nonce, payload)
+            .map_err(|_| AssetFormatError::Tampered)
+    }
+
+    /// Returns true if the asset was encrypted in the current format with the current key
+    pub fn is_current(&self) -> bool {
+        self.version == FORMAT_VERSION && self.key_id == CURRENT_KEY_ID
+    }
+
+    /// The extension of the asset before it was encrypted, if it's known
+    pub fn extension(&self) -> Option<&str> {
+        (!self.extension.is_empty()).then_some(self.extension.as_str())
+    }
+
+    /// Reads an asset with a header, or one encrypted before headers were added
+    pub fn from_bytes(bytes: &[u8]) -> Result<EncryptedAsset, AssetFormatError> {
+        let Some(rest) = bytes.strip_prefix(MAGIC) else {
+            let flex_reader = flexbuffers::Reader::get_root(bytes)?;
+            let legacy = LegacyEncryptedAsset::deserialize(flex_reader)?;
+            return Ok(EncryptedAsset {
+                version: 0,
+                key_id: 0,
+                extension: String::new(),
+                nonce: legacy.nonce,
+                ciphertext: legacy.ciphertext,
+            });
+        };
+
+        let [version, key_id, extension_length, rest @ ..] = rest else {
+            return Err(AssetFormatError::TruncatedHeader);
+        };
+        if *version > FORMAT_VERSION {
+            return Err(AssetFormatError::UnsupportedVersion(*version));
+        }
+        let extension_length = *extension_length as usize;
+        if rest.len() < extension_length + 12 {
+            return Err(AssetFormatError::TruncatedHeader);
+        }
+        let (extension, rest) = rest.split_at(extension_length);
+        let (nonce, ciphertext) = rest.split_at(12);
+        Ok(EncryptedAsset {
+            version: *version,
+            key_id: *key_id,
+            extension: String::from_utf8_lossy(extension).into_owned(),
+            nonce: nonce.try_into().expect("the nonce is 12 bytes"),
+            ciphertext: ciphertext.to_vec(),
+        })
+    }
+
+    /// Writes the asset with its header
+    #[cfg(feature = "dev_native")]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.header();
+        bytes.extend_from_slice(&self.nonce);
+        bytes.extend_from_slice(&self.ciphertext);
+        bytes
+    }
+
+    fn header(&self) -> Vec<u8> {
+        let extension = &self.extension.as_bytes()[..self.extension.len().min(u8::MAX as usize)];
+        let mut header = MAGIC.to_vec();
+        header.extend([self.version, self.key_id, extension.len() as u8]);
+        header.extend_from_slice(extension);
+        header
+    }
+
+    /// Reads an encrypted asset and returns it decrypted, checked against the asset manifest
+    pub async fn read_decrypted(
+        reader: &mut dyn Reader,
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<(EncryptedAsset, Vec<u8>), AssetFormatError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let encrypted = EncryptedAsset::from_bytes(&bytes)?;
+        let decrypted = encrypted.decrypt()?;
+        check_manifest(load_context, &decrypted).await?;
+        Ok((encrypted, decrypted))
+    }
+}
+
+/// The SHA-256 hashes of every encrypted asset's plaintext, generated when assets are encrypted
+#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq, Eq)]
+pub struct AssetManifest {
+    /// Hex hashes, by the asset's path relative to the assets folder
+    pub hashes: std::collections::BTreeMap<String, String>,
+}
+
+/// Returns the hex SHA-256 hash of an asset's plaintext, as listed in the manifest
+pub fn plaintext_hash(plaintext: &[u8]) -> String {
+    Sha256::digest(plaintext)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// Returns the asset's path as it's listed in the manifest
+pub fn manifest_key(path: &std::path::Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+/// The manifest shipped with the game, read straight from the assets folder
+#[cfg(test)]
+pub fn bundled_manifest() -> AssetManifest {
+    ron::de::from_bytes(include_bytes!("../assets/asset_manifest.ron")).unwrap()
+}
+
+/// The asset manifest, read when the first encrypted asset loads, or none if there isn't one
+///
+/// Only the first asset read depends on the manifest, rather than every asset re-reading it.
+/// It's forgotten when assets are encrypted again, so their new hashes are read.
+static MANIFEST: RwLock<Option<Arc<Option<AssetManifest>>>> = RwLock::new(None);
+
+/// Returns the asset manifest, reading it if it hasn't been read yet
+pub async fn asset_manifest(
+    load_context: &mut LoadContext<'_>,
+) -> Result<Arc<Option<AssetManifest>>, AssetFormatError> {
+    if let Some(manifest) = MANIFEST
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+    {
+        return Ok(manifest);
+    }
+
+    let manifest = match load_context.read_asset_bytes(MANIFEST_PATH).await {
+        Ok(bytes) => Some(ron::de::from_bytes(&bytes)?),
+        Err(_) => None,
+    };
+    let manifest = Arc::new(manifest);
+    *MANIFEST.write().unwrap_or_else(PoisonError::into_inner) = Some(manifest.clone());
+    Ok(manifest)
+}
+
+/// Forgets the asset manifest, so it's read again by the next asset to load
+#[cfg(feature = "dev_native")]
+pub fn forget_asset_manifest() {
+    *MANIFEST.write().unwrap_or_else(PoisonError::into_inner) = None;
+}
+
+/// Checks a decrypted asset against the manifest
+///
+/// Assets aren't checked if the manifest hasn't been generated yet.
+async fn check_manifest(
+    load_context: &mut LoadContext<'_>,
+    plaintext: &[u8],
+) -> Result<(), AssetFormatError> {
+    let manifest = asset_manifest(load_context).await?;
+    let Some(manifest) = manifest.as_ref() else {
+        return Ok(());
+    };
+    let path = manifest_key(load_context.path());
+    match manifest.hashes.get(&path) {
+        Some(hash) if *hash == plaintext_hash(plaintext) => Ok(()),
+        Some(_) => Err(AssetFormatError::Stale(path)),
+        None => Err(AssetFormatError::NotInManifest(path)),
+    }
+}
+
+/// Loads encrypted assets by decrypting them and handing them to another loader
+///
+/// Encrypted files have an `e` before their original extension, so `.ogg` becomes `.eogg`.
+pub struct EncryptedLoader<L> {
+    inner: L,
+    extensions: &'static [&'static str],
+}
+
+impl<L: AssetLoader> EncryptedLoader<L> {
+    /// Wraps `inner` to load files with the given encrypted extensions
+    pub fn new(inner: L, extensions: &'static [&'static str]) -> Self {
+        Self { inner, extensions }
+    }
+}
+
+impl<L: AssetLoader> AssetLoader for EncryptedLoader<L> {
+    type Asset = L::Asset;
+    type Settings = L::Settings;
+    type Error = AssetFormatError;
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        settings: &L::Settings,
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<L::Asset, Self::Error> {
+        let (_, decrypted) = EncryptedAsset::read_decrypted(reader, load_context).await?;
+        let mut vec_reader = VecReader::new(decrypted);
+        self.inner
+            .load(&mut vec_reader, settings, load_context)
+            .await
+            .map_err(|error| AssetFormatError::Inner(error.into()))
+    }
+
+    fn extensions(&self) -> &[&str] {
+        self.extensions
     }
 }
 
@@ -101,15 +381,17 @@ impl AssetLoader for EncryptedImageLoader {
         settings: &ImageLoaderSettings,
         load_context: &mut LoadContext<'_>,
     ) -> Result<Image, Self::Error> {
-        let mut bytes = Vec::new();
-        reader.read_to_end(&mut bytes).await?;
-        let flex_reader = flexbuffers::Reader::get_root(bytes.as_slice())?;
-        let encrypted = EncryptedAsset::deserialize(flex_reader)?;
-        let decrypted = encrypted.decrypt((*KEY).into())?;
+        let (encrypted, decrypted) = EncryptedAsset::read_decrypted(reader, load_context).await?;
         let mut vec_reader = VecReader::new(decrypted);
+        // The extension is `.epng`, so the format comes from the header, or is guessed from the
+        // image's contents for assets without one
+        let format = match encrypted.extension().and_then(ImageFormat::from_extension) {
+            Some(format) => ImageFormatSetting::Format(format),
+            None => ImageFormatSetting::Guess,
+        };
         let image_loader = ImageLoader::new(CompressedImageFormats::NONE);
         let settings = ImageLoaderSettings {
-            format: ImageFormatSetting::Guess,
+            format,
             ..(settings.clone())
         };
         let asset = image_loader
@@ -123,90 +405,352 @@ impl AssetLoader for EncryptedImageLoader {
     }
 }
 
-/*
-#[derive(Default)]
-pub struct EncryptedAudioLoader;
-*/
-
 #[cfg(feature = "dev_native")]
 pub fn encrypt_raw_assets(_: On<Pointer<Click>>) {
-    if let Err(e) = asset_encryption::encrypt_assets() {
-        error!("Failed to encrypt raw assets: {}", e);
-    } else {
-        info!("Successfully encrypted raw assets");
+    match asset_encryption::encrypt_assets() {
+        Ok(report) if report.is_up_to_date() => info!("Raw assets are already encrypted"),
+        Ok(report) => {
+            // Assets that are reloaded are checked against their new hashes
+            forget_asset_manifest();
+            info!("Successfully encrypted raw assets: {}", report.summary());
+        }
+        Err(e) => error!("Failed to encrypt raw assets: {}", e),
     }
 }
 
+/// Encrypting `raw_assets/` into `assets/`, shared by the settings menu and the
+/// `encrypt_assets` binary
 #[cfg(feature = "dev_native")]
-mod asset_encryption {
+pub mod asset_encryption {
     use super::*;
     use std::fs;
-    use std::path::Path;
+    use std::path::{Path, PathBuf};
 
-    pub fn encrypt_assets() -> Result<(), AssetFormatError> {
-        let raw_assets_dir = Path::new("raw_assets");
-        let assets_dir = Path::new("assets");
-        process_directory(raw_assets_dir, assets_dir, raw_assets_dir)
+    /// What a sync changed, or would change for a dry run
+    #[derive(Debug, Default)]
+    pub struct SyncReport {
+        /// Outputs written because their source is new or has changed
+        pub encrypted: Vec<PathBuf>,
+        /// Outputs whose source hasn't changed
+        pub unchanged: usize,
+        /// Outputs removed because their source is gone
+        pub removed: Vec<PathBuf>,
+        /// Outputs whose source is gone, kept because the sync wasn't pruning
+        pub orphaned: Vec<PathBuf>,
+        /// Whether the asset manifest didn't match the sources
+        pub manifest_changed: bool,
     }
 
-    fn process_directory(
-        current_dir: &Path,
-        assets_base: &Path,
-        raw_assets_base: &Path,
-    ) -> Result<(), AssetFormatError> {
-        for entry in fs::read_dir(current_dir)? {
-            let entry = entry?;
-            let path = entry.path();
+    impl SyncReport {
+        /// Returns true if the outputs already matched their sources
+        pub fn is_up_to_date(&self) -> bool {
+            self.encrypted.is_empty() && self.removed.is_empty() && !self.manifest_changed
+        }
 
-            if path.is_dir() {
-                // Recursively process subdirectories
-                process_directory(&path, assets_base, raw_assets_base)?;
-            } else if path.is_file() {
-                encrypt_file(&path, assets_base, raw_assets_base)?;
-            }
+        pub fn summary(&self) -> String {
+            format!(
+                "{} encrypted, {} unchanged, {} removed, {} orphaned",
+                self.encrypted.len(),
+                self.unchanged,
+                self.removed.len(),
+                self.orphaned.len()
+            )
         }
+    }
 
-        Ok(())
+    /// How [`sync_assets`] treats the assets folder
+    #[derive(Debug, Clone, Copy, Default)]
+    pub struct SyncOptions {
+        /// Report what would change without writing or removing anything
+        pub dry_run: bool,
+        /// Remove outputs whose source is gone, rather than keeping them
+        pub prune: bool,
     }
 
-    fn encrypt_file(
-        source_path: &Path,
-        assets_base: &Path,
-        raw_assets_base: &Path,
-    ) -> Result<(), AssetFormatError> {
-        let relative_path = source_path
-            .strip_prefix(raw_assets_base)
-            .map_err(|e| std::io::Error::other(e.to_string()))?;
+    /// Encrypts `raw_assets/` into `assets/`, keeping any assets whose source is missing
+    pub fn encrypt_assets() -> Result<SyncReport, AssetFormatError> {
+        sync_assets(
+            Path::new("raw_assets"),
+            Path::new("assets"),
+            SyncOptions::default(),
+        )
+    }
+
+    /// Encrypts every file in `raw_assets_dir` into `assets_dir`, and lists their hashes in the
+    /// asset manifest
+    ///
+    /// Outputs are only written when their source has changed, or they were encrypted in an
+    /// older format or with an older key. Outputs listed in the previous manifest whose source
+    /// is gone are only removed when pruning, since a copy of `raw_assets_dir` may just be
+    /// missing some. Otherwise they're kept in the manifest, so they still load. Files that were
+    /// never listed, such as plain assets, are never touched.
+    pub fn sync_assets(
+        raw_assets_dir: &Path,
+        assets_dir: &Path,
+        options: SyncOptions,
+    ) -> Result<SyncReport, AssetFormatError> {
+        let mut report = SyncReport::default();
+        let manifest_path = assets_dir.join(MANIFEST_PATH);
+        let current_manifest = fs::read(&manifest_path)
+            .ok()
+            .and_then(|bytes| ron::de::from_bytes::<AssetManifest>(&bytes).ok());
+
+        let mut manifest = AssetManifest::default();
+        for source_path in files_in(raw_assets_dir)? {
+            let relative_path = source_path
+                .strip_prefix(raw_assets_dir)
+                .map_err(|e| std::io::Error::other(e.to_string()))?;
+            let relative_dest_path = encrypted_path(relative_path);
+            let dest_path = assets_dir.join(&relative_dest_path);
+            let plaintext = fs::read(&source_path)?;
+            manifest.hashes.insert(
+                manifest_key(&relative_dest_path),
+                plaintext_hash(&plaintext),
+            );
+
+            if is_current(&dest_path, &plaintext) {
+                report.unchanged += 1;
+                continue;
+            }
+            if !options.dry_run {
+                let extension = relative_path
+                    .extension()
+                    .map(|ext| ext.to_string_lossy())
+                    .unwrap_or_default();
+                encrypt_file(&plaintext, &extension, &dest_path)?;
+            }
+            report.encrypted.push(dest_path);
+        }
+
+        let previous_outputs = current_manifest
+            .iter()
+            .flat_map(|current| current.hashes.iter());
+        for (relative_path, hash) in previous_outputs {
+            let dest_path = assets_dir.join(relative_path);
+            if manifest.hashes.contains_key(relative_path) || !dest_path.is_file() {
+                continue;
+            }
+            if !options.prune {
+                manifest.hashes.insert(relative_path.clone(), hash.clone());
+                report.orphaned.push(dest_path);
+                continue;
+            }
+            if !options.dry_run {
+                fs::remove_file(&dest_path)?;
+            }
+            report.removed.push(dest_path);
+        }
 
-        let mut dest_path = assets_base.join(relative_path);
-        if let Some(ext) = dest_path.extension() {
-            let new_ext = format!("e{}", ext.to_string_lossy());
-            dest_path.set_extension(new_ext);
+        if current_manifest.as_ref() != Some(&manifest) {
+            if !options.dry_run {
+                let pretty = ron::ser::PrettyConfig::default();
+                let text = ron::ser::to_string_pretty(&manifest, pretty)
+                    .map_err(|e| std::io::Error::other(e.to_string()))?;
+                fs::write(&manifest_path, text)?;
+            }
+            report.manifest_changed = true;
         }
 
-        if dest_path.exists() {
-            info!("Skipping {} (already exists)", dest_path.display());
-            return Ok(());
+        Ok(report)
+    }
+
+    /// The path of a raw asset once encrypted, with an `e` before its extension
+    pub fn encrypted_path(source_path: &Path) -> PathBuf {
+        let mut dest_path = source_path.to_path_buf();
+        if let Some(ext) = source_path.extension() {
+            dest_path.set_extension(format!("e{}", ext.to_string_lossy()));
         }
+        dest_path
+    }
+
+    /// Returns true if `dest_path` is an encryption of `plaintext` in the current format
+    fn is_current(dest_path: &Path, plaintext: &[u8]) -> bool {
+        let Ok(encrypted) = fs::read(dest_path)
+            .map_err(AssetFormatError::from)
+            .and_then(|bytes| EncryptedAsset::from_bytes(&bytes))
+        else {
+            return false;
+        };
+        encrypted.is_current()
+            && encrypted
+                .decrypt()
+                .is_ok_and(|decrypted| plaintext_hash(&decrypted) == plaintext_hash(plaintext))
+    }
 
+    fn encrypt_file(
+        plaintext: &[u8],
+        extension: &str,
+        dest_path: &Path,
+    ) -> Result<(), AssetFormatError> {
         // Create parent directories if needed
         if let Some(parent) = dest_path.parent() {
             fs::create_dir_all(parent)?;
         }
 
-        let plaintext = fs::read(source_path)?;
-        let encrypted = EncryptedAsset::encrypt((*KEY).into(), &plaintext)?;
-        let mut serializer = flexbuffers::FlexbufferSerializer::new();
-        encrypted.serialize(&mut serializer)?;
-        let bytes = serializer.view();
-        fs::write(&dest_path, bytes)?;
+        let encrypted = EncryptedAsset::encrypt(plaintext, extension)?;
+        fs::write(dest_path, encrypted.to_bytes())?;
+        Ok(())
+    }
+
+    /// Every file under `dir`, recursively
+    fn files_in(dir: &Path) -> Result<Vec<PathBuf>, AssetFormatError> {
+        let mut files = vec![];
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                files.extend(files_in(&path)?);
+            } else if path.is_file() {
+                files.push(path);
+            }
+        }
+        Ok(files)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
 
-        info!(
-            "Encrypted {} -> {}",
-            source_path.display(),
-            dest_path.display()
-        );
+        fn decrypt_file(path: &Path) -> Vec<u8> {
+            EncryptedAsset::from_bytes(&fs::read(path).unwrap())
+                .unwrap()
+                .decrypt()
+                .unwrap()
+        }
 
-        Ok(())
+        #[test]
+        fn test_encrypted_paths() {
+            assert_eq!(
+                encrypted_path(Path::new("audio/sound_effects/button_click.ogg")),
+                Path::new("audio/sound_effects/button_click.eogg")
+            );
+            assert_eq!(
+                encrypted_path(Path::new("data/wardrobe.catalog.ron")),
+                Path::new("data/wardrobe.catalog.eron")
+            );
+        }
+
+        #[test]
+        fn test_header_round_trips() {
+            let encrypted = EncryptedAsset::encrypt(b"plaintext", "png").unwrap();
+            let read = EncryptedAsset::from_bytes(&encrypted.to_bytes()).unwrap();
+            assert_eq!(read, encrypted);
+            assert_eq!(read.extension(), Some("png"));
+            assert!(read.is_current());
+            assert_eq!(read.decrypt().unwrap(), b"plaintext");
+        }
+
+        #[test]
+        fn test_assets_without_a_header_still_decrypt() {
+            let cipher = ChaCha8Poly1305::new(&key(0).unwrap());
+            let nonce = [7; 12];
+            let legacy = LegacyEncryptedAsset {
+                nonce,
+                ciphertext: cipher.encrypt((&nonce).into(), &b"old"[..]).unwrap(),
+            };
+            let mut serializer = flexbuffers::FlexbufferSerializer::new();
+            legacy.serialize(&mut serializer).unwrap();
+
+            let read = EncryptedAsset::from_bytes(serializer.view()).unwrap();
+            assert_eq!(read.extension(), None);
+            assert!(!read.is_current());
+            assert_eq!(read.decrypt().unwrap(), b"old");
+        }
+
+        #[test]
+        fn test_bad_headers_have_clear_errors() {
+            let bytes = EncryptedAsset::encrypt(b"plaintext", "png")
+                .unwrap()
+                .to_bytes();
+            let header_length = MAGIC.len() + 3 + "png".len();
+
+            // The extension is authenticated, so changing it is caught
+            let mut tampered = bytes.clone();
+            tampered[header_length - 1] = b'x';
+            assert!(matches!(
+                EncryptedAsset::from_bytes(&tampered).unwrap().decrypt(),
+                Err(AssetFormatError::Tampered)
+            ));
+
+            let mut newer = bytes.clone();
+            newer[MAGIC.len()] = FORMAT_VERSION + 1;
+            assert!(matches!(
+                EncryptedAsset::from_bytes(&newer),
+                Err(AssetFormatError::UnsupportedVersion(_))
+            ));
+
+            let mut unknown_key = bytes.clone();
+            unknown_key[MAGIC.len() + 1] = 200;
+            assert!(matches!(
+                EncryptedAsset::from_bytes(&unknown_key).unwrap().decrypt(),
+                Err(AssetFormatError::UnknownKey(200))
+            ));
+
+            assert!(matches!(
+                EncryptedAsset::from_bytes(&bytes[..header_length + 4]),
+                Err(AssetFormatError::TruncatedHeader)
+            ));
+        }
+
+        #[test]
+        fn test_sync_only_touches_changed_and_orphaned_assets() {
+            let root = std::env::temp_dir().join(format!("encrypt-test-{}", std::process::id()));
+            let raw = root.join("raw_assets");
+            let assets = root.join("assets");
+            fs::create_dir_all(raw.join("images")).unwrap();
+            fs::create_dir_all(&assets).unwrap();
+            fs::write(raw.join("images/a.png"), b"first").unwrap();
+            fs::write(raw.join("b.ogg"), b"second").unwrap();
+            fs::write(assets.join("plain.png"), b"not encrypted").unwrap();
+            // Encrypted, but never synced from raw assets
+            fs::write(
+                assets.join("committed.epng"),
+                EncryptedAsset::encrypt(b"committed", "png")
+                    .unwrap()
+                    .to_bytes(),
+            )
+            .unwrap();
+
+            let sync = SyncOptions::default();
+            let prune = SyncOptions {
+                prune: true,
+                ..sync
+            };
+            let check = SyncOptions {
+                dry_run: true,
+                ..prune
+            };
+
+            let report = sync_assets(&raw, &assets, sync).unwrap();
+            assert_eq!(report.encrypted.len(), 2);
+            assert_eq!(decrypt_file(&assets.join("images/a.epng")), b"first");
+
+            fs::write(raw.join("images/a.png"), b"edited").unwrap();
+            fs::remove_file(raw.join("b.ogg")).unwrap();
+            assert!(!sync_assets(&raw, &assets, check).unwrap().is_up_to_date());
+
+            // Without pruning, the output of a missing source is kept and still listed
+            let report = sync_assets(&raw, &assets, sync).unwrap();
+            assert_eq!(report.encrypted, vec![assets.join("images/a.epng")]);
+            assert_eq!(report.orphaned, vec![assets.join("b.eogg")]);
+            assert!(assets.join("b.eogg").exists());
+
+            let report = sync_assets(&raw, &assets, prune).unwrap();
+            assert_eq!(report.removed, vec![assets.join("b.eogg")]);
+            assert!(!assets.join("b.eogg").exists());
+            assert_eq!(decrypt_file(&assets.join("images/a.epng")), b"edited");
+            assert!(assets.join("plain.png").exists());
+            assert!(assets.join("committed.epng").exists());
+            assert!(sync_assets(&raw, &assets, prune).unwrap().is_up_to_date());
+
+            let manifest: AssetManifest =
+                ron::de::from_bytes(&fs::read(assets.join(MANIFEST_PATH)).unwrap()).unwrap();
+            assert_eq!(
+                manifest.hashes.get("images/a.epng"),
+                Some(&plaintext_hash(b"edited"))
+            );
+            assert_eq!(manifest.hashes.len(), 1);
+
+            fs::remove_dir_all(root).unwrap();
+        }
     }
 }
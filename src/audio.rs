@@ -1,9 +1,25 @@
-use bevy::prelude::*;
+use bevy::{audio::Volume, ecs::component::Mutable, prelude::*};
+use bevy_pkv::prelude::*;
+use serde::{Deserialize, Serialize};
+
+mod music;
+
+pub use music::Fade;
 
 pub(super) fn plugin(app: &mut App) {
+    app.register_type::<CategoryVolume>();
+    app.register_type::<VolumeSettings>();
+    app.init_resource::<CategoryVolume>();
+    app.init_persistent_resource::<VolumeSettings>();
+    app.add_plugins(music::plugin);
     app.add_systems(
         Update,
-        apply_global_volume.run_if(resource_changed::<GlobalVolume>),
+        (
+            apply_volume_settings.run_if(resource_changed::<VolumeSettings>),
+            (apply_volume::<AudioSink>, apply_volume::<SpatialAudioSink>),
+        )
+            .chain()
+            .after(music::fade_music),
     );
 }
 
@@ -16,7 +32,6 @@ pub(super) fn plugin(app: &mut App) {
 pub struct Music;
 
 /// A music audio instance.
-#[expect(dead_code)]
 pub fn music(handle: Handle<AudioSource>) -> impl Bundle {
     (AudioPlayer(handle), PlaybackSettings::LOOP, Music)
 }
@@ -34,12 +49,151 @@ pub fn sound_effect(handle: Handle<AudioSource>) -> impl Bundle {
     (AudioPlayer(handle), PlaybackSettings::DESPAWN, SoundEffect)
 }
 
-/// [`GlobalVolume`] doesn't apply to already-running audio entities, so this system will update them.
-fn apply_global_volume(
+/// Resource holding the volume of each category of sound, applied on top of [`GlobalVolume`]
+#[derive(Resource, Reflect, Clone, Copy, Debug, PartialEq)]
+#[reflect(Resource)]
+pub struct CategoryVolume {
+    pub music: Volume,
+    pub sound_effects: Volume,
+}
+
+impl Default for CategoryVolume {
+    fn default() -> Self {
+        Self {
+            music: Volume::Linear(1.0),
+            sound_effects: Volume::Linear(1.0),
+        }
+    }
+}
+
+/// The lowest volume a setting can be turned down to
+pub const MIN_VOLUME: f32 = 0.0;
+
+/// The highest volume a setting can be turned up to
+pub const MAX_VOLUME: f32 = 3.0;
+
+/// A volume the player can set
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Reflect)]
+pub enum VolumeSetting {
+    Master,
+    Music,
+    SoundEffects,
+}
+
+/// The player's volume settings, which drive [`GlobalVolume`] and [`CategoryVolume`]
+#[derive(Resource, Reflect, Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[reflect(Resource)]
+pub struct VolumeSettings {
+    pub master: f32,
+    pub music: f32,
+    pub sound_effects: f32,
+    /// Silences everything without losing the volumes
+    pub muted: bool,
+}
+
+impl Default for VolumeSettings {
+    fn default() -> Self {
+        Self {
+            master: 1.0,
+            music: 1.0,
+            sound_effects: 1.0,
+            muted: false,
+        }
+    }
+}
+
+impl VolumeSettings {
+    /// Returns the linear volume of `setting`
+    pub fn get(&self, setting: VolumeSetting) -> f32 {
+        match setting {
+            VolumeSetting::Master => self.master,
+            VolumeSetting::Music => self.music,
+            VolumeSetting::SoundEffects => self.sound_effects,
+        }
+    }
+
+    /// Turns `setting` up or down by `step`, keeping it between [`MIN_VOLUME`] and [`MAX_VOLUME`]
+    pub fn adjust(&mut self, setting: VolumeSetting, step: f32) {
+        let volume = match setting {
+            VolumeSetting::Master => &mut self.master,
+            VolumeSetting::Music => &mut self.music,
+            VolumeSetting::SoundEffects => &mut self.sound_effects,
+        };
+        // Rounded so repeated steps don't drift away from whole percentages
+        *volume = ((*volume + step) * 100.0)
+            .round()
+            .clamp(MIN_VOLUME * 100.0, MAX_VOLUME * 100.0)
+            / 100.0;
+    }
+}
+
+/// System to apply the player's volume settings to the global and category volumes
+fn apply_volume_settings(
+    settings: Res<VolumeSettings>,
+    mut global_volume: ResMut<GlobalVolume>,
+    mut category_volume: ResMut<CategoryVolume>,
+) {
+    global_volume.volume = if settings.muted {
+        Volume::SILENT
+    } else {
+        Volume::Linear(settings.master)
+    };
+    category_volume.music = Volume::Linear(settings.music);
+    category_volume.sound_effects = Volume::Linear(settings.sound_effects);
+}
+
+/// [`GlobalVolume`] and [`CategoryVolume`] don't apply to already-running audio entities, and
+/// the category and fade aren't applied to new ones, so this system updates them.
+fn apply_volume<S: AudioSinkPlayback + Component<Mutability = Mutable>>(
     global_volume: Res<GlobalVolume>,
-    mut audio_query: Query<(&PlaybackSettings, &mut AudioSink)>,
+    category_volume: Res<CategoryVolume>,
+    mut audio_query: Query<(
+        &PlaybackSettings,
+        &mut S,
+        Has<Music>,
+        Has<SoundEffect>,
+        Option<Ref<Fade>>,
+    )>,
 ) {
-    for (playback, mut sink) in &mut audio_query {
-        sink.set_volume(global_volume.volume * playback.volume);
+    let volume_changed = global_volume.is_changed() || category_volume.is_changed();
+    for (playback, mut sink, is_music, is_sound_effect, fade) in &mut audio_query {
+        let fade_changed = fade.as_ref().is_some_and(|fade| fade.is_changed());
+        if !volume_changed && !fade_changed && !sink.is_added() {
+            continue;
+        }
+
+        let mut volume = global_volume.volume * playback.volume;
+        if is_music {
+            volume *= category_volume.music;
+        }
+        if is_sound_effect {
+            volume *= category_volume.sound_effects;
+        }
+        if let Some(fade) = fade {
+            volume *= Volume::Linear(fade.volume);
+        }
+        sink.set_volume(volume);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_volume_stays_in_range() {
+        let mut settings = VolumeSettings::default();
+        for _ in 0..50 {
+            settings.adjust(VolumeSetting::Music, 0.1);
+        }
+        assert_eq!(settings.get(VolumeSetting::Music), MAX_VOLUME);
+        for _ in 0..50 {
+            settings.adjust(VolumeSetting::Music, -0.1);
+        }
+        assert_eq!(settings.get(VolumeSetting::Music), MIN_VOLUME);
+        assert_eq!(settings.get(VolumeSetting::Master), 1.0);
+
+        settings.adjust(VolumeSetting::SoundEffects, -0.3);
+        assert_eq!(settings.get(VolumeSetting::SoundEffects), 0.7);
     }
 }
@@ -0,0 +1,206 @@
+//! Background music: a track for each screen and season, crossfaded whenever it changes.
+//!
+//! Music is ducked while the game is paused, rather than stopped, so it picks up where it left
+//! off.
+
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+
+use super::{Music, music};
+use crate::{
+    Pause,
+    game::{Season, replay::PlayedLevel},
+    screens::Screen,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<Fade>();
+    app.init_resource::<MusicAssets>();
+    app.add_systems(
+        Update,
+        (
+            play_music.run_if(state_changed::<Screen>.or(resource_changed::<Season>)),
+            fade_music,
+        )
+            .chain(),
+    );
+}
+
+/// How long it takes to fade a track fully in or out, in seconds
+const CROSSFADE_SECS: f32 = 2.0;
+
+/// How loud the music is while the game is paused
+const DUCKED_VOLUME: f32 = 0.3;
+
+/// Component fading a music track in, out, or down while paused
+#[derive(Component, Reflect, Debug)]
+#[reflect(Component)]
+pub struct Fade {
+    /// The current volume, from 0 to 1, applied on top of the music volume
+    pub volume: f32,
+    /// Whether the track is fading out, to be despawned once it's silent
+    fading_out: bool,
+}
+
+impl Fade {
+    fn fade_in() -> Self {
+        Self {
+            volume: 0.0,
+            fading_out: false,
+        }
+    }
+}
+
+/// Component holding the path of the track a music entity plays
+#[derive(Component, Debug)]
+struct MusicTrack(&'static str);
+
+/// Every music track, by path
+const TRACKS: [&str; 6] = [
+    "audio/music/title.eogg",
+    "audio/music/summer.eogg",
+    "audio/music/autumn.eogg",
+    "audio/music/winter.eogg",
+    "audio/music/spring.eogg",
+    "audio/music/victory.eogg",
+];
+
+/// Resource holding every music track, so they stay loaded between screens
+///
+/// Tracks aren't waited for on the loading screen, since the game works without them.
+#[derive(Resource, Debug)]
+struct MusicAssets {
+    tracks: HashMap<&'static str, Handle<AudioSource>>,
+}
+
+impl FromWorld for MusicAssets {
+    fn from_world(world: &mut World) -> Self {
+        let assets = world.resource::<AssetServer>();
+        Self {
+            tracks: TRACKS
+                .into_iter()
+                .map(|track| (track, assets.load(track)))
+                .collect(),
+        }
+    }
+}
+
+/// Returns the track to play on `screen` during `season`, if any
+fn track_for(screen: Screen, season: Season) -> Option<&'static str> {
+    match screen {
+        Screen::Splash => None,
+        Screen::Title | Screen::Loading => Some(TRACKS[0]),
+        Screen::Gameplay => Some(match season {
+            Season::Summer => TRACKS[1],
+            Season::Autumn => TRACKS[2],
+            Season::Winter => TRACKS[3],
+            Season::Spring => TRACKS[4],
+        }),
+        Screen::Victory => Some(TRACKS[5]),
+    }
+}
+
+/// System to crossfade to the track for the current screen and season
+fn play_music(
+    mut commands: Commands,
+    music_assets: Res<MusicAssets>,
+    screen: Res<State<Screen>>,
+    played: PlayedLevel,
+    mut music_query: Query<(&MusicTrack, &mut Fade), With<Music>>,
+) {
+    let track = track_for(*screen.get(), played.season())
+        .and_then(|track| Some((track, music_assets.tracks.get(track)?.clone())));
+
+    let mut already_playing = false;
+    for (playing, mut fade) in &mut music_query {
+        if track.as_ref().is_some_and(|(track, _)| playing.0 == *track) && !fade.fading_out {
+            already_playing = true;
+        } else {
+            fade.fading_out = true;
+        }
+    }
+
+    if let Some((track, handle)) = track
+        && !already_playing
+    {
+        commands.spawn((
+            Name::new("Music"),
+            music(handle),
+            MusicTrack(track),
+            Fade::fade_in(),
+        ));
+    }
+}
+
+/// System to move each track's volume towards its target, despawning tracks that have faded out
+pub(super) fn fade_music(
+    mut commands: Commands,
+    time: Res<Time>,
+    pause: Res<State<Pause>>,
+    mut fade_query: Query<(Entity, &mut Fade)>,
+) {
+    let playing_volume = if pause.get().0 { DUCKED_VOLUME } else { 1.0 };
+    let step = time.delta_secs() / CROSSFADE_SECS;
+
+    for (entity, mut fade) in &mut fade_query {
+        let target = if fade.fading_out { 0.0 } else { playing_volume };
+        if fade.volume == target {
+            continue;
+        }
+
+        fade.volume = if fade.volume < target {
+            (fade.volume + step).min(target)
+        } else {
+            (fade.volume - step).max(target)
+        };
+        if fade.fading_out && fade.volume <= 0.0 {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::asset_format::bundled_manifest;
+
+    #[test]
+    fn test_each_season_has_its_own_gameplay_track() {
+        let seasons = [
+            Season::Summer,
+            Season::Autumn,
+            Season::Winter,
+            Season::Spring,
+        ];
+        let mut tracks: Vec<_> = seasons
+            .iter()
+            .map(|season| track_for(Screen::Gameplay, *season))
+            .collect();
+        tracks.dedup();
+        assert_eq!(tracks.len(), seasons.len());
+        assert!(tracks.iter().flatten().all(|track| TRACKS.contains(track)));
+        assert!(track_for(Screen::Splash, Season::Summer).is_none());
+        // The title track doesn't change with the season
+        assert_eq!(
+            track_for(Screen::Title, Season::Summer),
+            track_for(Screen::Title, Season::Winter)
+        );
+    }
+
+    #[test]
+    fn test_bundled_tracks_exist() {
+        let manifest = bundled_manifest();
+        for track in TRACKS {
+            assert!(
+                Path::new("assets").join(track).exists(),
+                "{track} is missing"
+            );
+            assert!(
+                manifest.hashes.contains_key(track),
+                "{track} isn't in the manifest"
+            );
+        }
+    }
+}
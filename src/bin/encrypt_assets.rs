@@ -0,0 +1,132 @@
+//! Encrypts `raw_assets/` into `assets/` without starting the game, for CI and build scripts.
+//!
+//! ```text
+//! encrypt_assets [--check] [--prune] [RAW_ASSETS_DIR ASSETS_DIR]
+//! encrypt_assets decrypt FILE [OUTPUT]
+//! ```
+//!
+//! Only assets whose source has changed, or that were encrypted in an older format or with an
+//! older key, are encrypted again, and the manifest of plaintext hashes the game checks assets
+//! against is updated. Encrypted assets whose source is gone are kept unless `--prune` is given.
+//!
+//! `--check` changes nothing, and exits with status 2 if anything is stale. `decrypt` writes a
+//! decrypted copy of an asset for inspection, or prints its size if no output is given.
+
+use std::path::Path;
+use std::process::ExitCode;
+
+// The game's asset format, shared so the binary can't drift from what the game loads
+#[allow(dead_code)]
+#[path = "../asset_format.rs"]
+mod asset_format;
+
+use asset_format::asset_encryption::{SyncOptions, sync_assets};
+use asset_format::{AssetFormatError, EncryptedAsset, MANIFEST_PATH};
+
+const USAGE: &str = "usage: encrypt_assets [--check] [--prune] [RAW_ASSETS_DIR ASSETS_DIR]
+       encrypt_assets decrypt FILE [OUTPUT]";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    match args.as_slice() {
+        ["decrypt", file] => decrypt(file, None),
+        ["decrypt", file, output] => decrypt(file, Some(output)),
+        ["--help" | "-h"] => {
+            println!("{USAGE}");
+            ExitCode::SUCCESS
+        }
+        args => {
+            let (flags, dirs): (Vec<&str>, Vec<&str>) =
+                args.iter().copied().partition(|arg| arg.starts_with("--"));
+            let mut options = SyncOptions::default();
+            for flag in flags {
+                match flag {
+                    "--check" => options.dry_run = true,
+                    "--prune" => options.prune = true,
+                    _ => {
+                        eprintln!("{USAGE}");
+                        return ExitCode::FAILURE;
+                    }
+                }
+            }
+            sync(&dirs, options)
+        }
+    }
+}
+
+fn sync(dirs: &[&str], options: SyncOptions) -> ExitCode {
+    let check = options.dry_run;
+    let (raw_assets_dir, assets_dir) = match dirs {
+        [] => ("raw_assets", "assets"),
+        [raw_assets_dir, assets_dir] => (*raw_assets_dir, *assets_dir),
+        _ => {
+            eprintln!("{USAGE}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let report = match sync_assets(Path::new(raw_assets_dir), Path::new(assets_dir), options) {
+        Ok(report) => report,
+        Err(error) => {
+            eprintln!("Failed to encrypt {raw_assets_dir}: {error}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let (encrypted, removed, manifest) = if check {
+        ("Stale", "Orphaned", "Stale")
+    } else {
+        ("Encrypted", "Removed", "Updated")
+    };
+    for path in &report.encrypted {
+        println!("{encrypted} {}", path.display());
+    }
+    for path in &report.removed {
+        println!("{removed} {}", path.display());
+    }
+    for path in &report.orphaned {
+        println!(
+            "No source for {} (use --prune to remove it)",
+            path.display()
+        );
+    }
+    if report.manifest_changed {
+        println!("{manifest} {assets_dir}/{MANIFEST_PATH}");
+    }
+    println!("{}", report.summary());
+
+    if check && !report.is_up_to_date() {
+        eprintln!("Assets are out of date, run encrypt_assets to update them");
+        return ExitCode::from(2);
+    }
+    ExitCode::SUCCESS
+}
+
+/// Returns the decrypted contents of an encrypted asset
+fn decrypt_file(path: &Path) -> Result<Vec<u8>, AssetFormatError> {
+    EncryptedAsset::from_bytes(&std::fs::read(path)?)?.decrypt()
+}
+
+fn decrypt(file: &str, output: Option<&str>) -> ExitCode {
+    let plaintext = match decrypt_file(Path::new(file)) {
+        Ok(plaintext) => plaintext,
+        Err(error) => {
+            eprintln!("Failed to decrypt {file}: {error}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match output {
+        Some(output) => {
+            if let Err(error) = std::fs::write(output, &plaintext) {
+                eprintln!("Failed to write {output}: {error}");
+                return ExitCode::FAILURE;
+            }
+            println!("Decrypted {file} -> {output}");
+        }
+        None => println!("{file} decrypts to {} bytes", plaintext.len()),
+    }
+    ExitCode::SUCCESS
+}
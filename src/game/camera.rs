@@ -0,0 +1,196 @@
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::*;
+
+use super::character::{Character, Direction};
+use super::controls::Action;
+use super::level::LevelBounds;
+use crate::PausableSystems;
+use crate::pixel_camera::{PixelCamera, PixelCanvas};
+use crate::screens::Screen;
+
+pub fn plugin(app: &mut App) {
+    app.init_resource::<CameraRig>();
+
+    app.add_systems(OnEnter(Screen::Gameplay), start_framing_pan);
+    app.add_systems(OnExit(Screen::Gameplay), stop_framing_pan);
+
+    app.add_systems(
+        Update,
+        (tick_framing_pan, camera_follow_player)
+            .chain()
+            .after(super::physics::respawn_on_fall)
+            .in_set(PausableSystems)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+}
+
+/// Higher = faster following
+const FOLLOW_SMOOTHNESS: f32 = 10.0;
+/// How fast the look-ahead offset swings when the character turns around
+const LOOK_AHEAD_SMOOTHNESS: f32 = 3.0;
+/// How far ahead of the character the camera looks in the facing direction
+const LOOK_AHEAD_DISTANCE: f32 = 64.0;
+/// Vertical offset so the character sits slightly below the center of the view
+const Y_OFFSET: f32 = 32.0;
+/// Half height of the vertical band the character can move in without the camera following
+const DEAD_ZONE_HALF_HEIGHT: f32 = 24.0;
+/// Duration of the framing pan at level start, in seconds
+const FRAMING_PAN_SECS: f32 = 2.5;
+
+/// Resource tracking the camera's follow state between frames
+#[derive(Resource, Debug, Default)]
+pub struct CameraRig {
+    /// Current horizontal look-ahead offset
+    look_ahead: f32,
+    /// The height the camera is currently focused on
+    focus_y: Option<f32>,
+}
+
+/// Resource present while the camera is panning over the level at level start
+#[derive(Resource, Debug)]
+pub struct FramingPan {
+    timer: Timer,
+}
+
+/// Starts the framing pan and resets the follow state for a new level
+fn start_framing_pan(mut commands: Commands, mut rig: ResMut<CameraRig>) {
+    *rig = CameraRig::default();
+    commands.insert_resource(FramingPan {
+        timer: Timer::from_seconds(FRAMING_PAN_SECS, TimerMode::Once),
+    });
+}
+
+fn stop_framing_pan(mut commands: Commands) {
+    commands.remove_resource::<FramingPan>();
+}
+
+/// Ticks the framing pan, ending it early if the player jumps or uses something
+fn tick_framing_pan(
+    mut commands: Commands,
+    time: Res<Time>,
+    pan: Option<ResMut<FramingPan>>,
+    action_query: Query<&ActionState<Action>>,
+) {
+    let Some(mut pan) = pan else {
+        return;
+    };
+
+    let skipped = action_query.single().is_ok_and(|action_state| {
+        action_state.just_pressed(&Action::Jump) || action_state.just_pressed(&Action::Use)
+    });
+
+    pan.timer.tick(time.delta());
+    if pan.timer.is_finished() || skipped {
+        commands.remove_resource::<FramingPan>();
+    }
+}
+
+/// System to follow the player character with the camera, kept within the level bounds
+fn camera_follow_player(
+    time: Res<Time>,
+    level_bounds: Res<LevelBounds>,
+    pan: Option<Res<FramingPan>>,
+    mut rig: ResMut<CameraRig>,
+    canvas: Res<PixelCanvas>,
+    character_query: Query<(&Transform, &Direction), (With<Character>, Without<PixelCamera>)>,
+    mut camera_query: Query<&mut Transform, With<PixelCamera>>,
+) {
+    let Ok((character_transform, direction)) = character_query.single() else {
+        return;
+    };
+
+    let Ok(mut camera_transform) = camera_query.single_mut() else {
+        return;
+    };
+
+    let dt = time.delta_secs();
+    let half_view = Vec2::new(canvas.width as f32, canvas.height as f32) / 2.0;
+
+    // Ease the look-ahead towards the facing direction
+    let look_ahead_target = match direction {
+        Direction::Left => -LOOK_AHEAD_DISTANCE,
+        Direction::Right => LOOK_AHEAD_DISTANCE,
+    };
+    rig.look_ahead += (look_ahead_target - rig.look_ahead) * (LOOK_AHEAD_SMOOTHNESS * dt).min(1.0);
+
+    // Only refocus vertically once the character leaves the dead zone
+    let character_y = character_transform.translation.y + Y_OFFSET;
+    let focus_y = rig.focus_y.unwrap_or(character_y).clamp(
+        character_y - DEAD_ZONE_HALF_HEIGHT,
+        character_y + DEAD_ZONE_HALF_HEIGHT,
+    );
+    rig.focus_y = Some(focus_y);
+
+    let follow_target = clamp_to_bounds(
+        Vec2::new(character_transform.translation.x + rig.look_ahead, focus_y),
+        half_view,
+        &level_bounds,
+    );
+
+    if let Some(pan) = pan {
+        // Sweep from the goal end of the level back to the character
+        let overview = clamp_to_bounds(
+            Vec2::new(level_bounds.max.x, follow_target.y),
+            half_view,
+            &level_bounds,
+        );
+        let t = pan.timer.fraction();
+        let eased = t * t * (3.0 - 2.0 * t);
+        let position = overview.lerp(follow_target, eased);
+        camera_transform.translation.x = position.x;
+        camera_transform.translation.y = position.y;
+        return;
+    }
+
+    let current = camera_transform.translation.truncate();
+    let position = current + (follow_target - current) * (FOLLOW_SMOOTHNESS * dt).min(1.0);
+    let position = clamp_to_bounds(position, half_view, &level_bounds);
+    camera_transform.translation.x = position.x;
+    camera_transform.translation.y = position.y;
+}
+
+/// Clamps a camera center so that a view of `half_view` extents stays within the level bounds
+///
+/// Levels narrower than the view are centered horizontally. Only the bottom edge is
+/// clamped vertically, so tall jumps can still show the sky above the level.
+pub fn clamp_to_bounds(center: Vec2, half_view: Vec2, bounds: &LevelBounds) -> Vec2 {
+    let min_x = bounds.min.x + half_view.x;
+    let max_x = bounds.max.x - half_view.x;
+    let x = if min_x > max_x {
+        bounds.center().x
+    } else {
+        center.x.clamp(min_x, max_x)
+    };
+    let y = center.y.max(bounds.min.y + half_view.y);
+    Vec2::new(x, y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bounds() -> LevelBounds {
+        LevelBounds {
+            min: Vec2::new(0.0, -64.0),
+            max: Vec2::new(2000.0, 400.0),
+        }
+    }
+
+    #[test]
+    fn test_clamp_to_bounds_edges() {
+        let half_view = Vec2::new(300.0, 160.0);
+
+        let clamped = clamp_to_bounds(Vec2::new(-500.0, -500.0), half_view, &bounds());
+        assert_eq!(clamped, Vec2::new(300.0, 96.0));
+
+        let clamped = clamp_to_bounds(Vec2::new(5000.0, 1000.0), half_view, &bounds());
+        assert_eq!(clamped, Vec2::new(1700.0, 1000.0));
+    }
+
+    #[test]
+    fn test_clamp_to_bounds_narrow_level() {
+        let half_view = Vec2::new(1500.0, 160.0);
+        let clamped = clamp_to_bounds(Vec2::new(100.0, 200.0), half_view, &bounds());
+        assert_eq!(clamped.x, 1000.0);
+    }
+}
@@ -1,14 +1,14 @@
 use crate::{PausableSystems, screens::Screen};
 use bevy::prelude::*;
 use bevy::sprite::Anchor;
-use bevy_pkv::prelude::*;
 use serde::{Deserialize, Serialize};
-use std::sync::LazyLock;
 use std::time::Duration;
 use strum::Display;
 
+use super::composite::Compositor;
 use super::level::PlayerSpawnPoint;
 use super::physics::{CharacterController, Velocity};
+use super::replay::PlayedLevel;
 
 /// Component for character facing direction
 #[derive(Component, Debug, Default, Reflect, Clone, Copy, PartialEq)]
@@ -22,11 +22,6 @@ pub enum Direction {
 pub static ROWS: u32 = 7;
 pub static COLUMNS: u32 = 10;
 
-pub static HAIR_COLOURS: LazyLock<Vec<&str>> =
-    LazyLock::new(|| vec!["blonde", "red", "light", "dark", "black"]);
-pub static CLOTHING_COLOURS: LazyLock<Vec<&str>> =
-    LazyLock::new(|| vec!["blue", "green", "orange", "purple", "red"]);
-
 pub fn plugin(app: &mut App) {
     app.register_type::<CharacterLayers>();
     app.register_type::<CharacterAnimation>();
@@ -34,7 +29,7 @@ pub fn plugin(app: &mut App) {
     app.register_type::<Direction>();
     app.register_type::<OneShotAnimation>();
 
-    app.init_persistent_resource::<CharacterLayers>();
+    app.init_resource::<CharacterLayers>();
 
     app.add_systems(OnEnter(Screen::Gameplay), spawn_character);
 
@@ -127,25 +122,27 @@ impl Default for CharacterLayers {
     }
 }
 
-#[derive(Clone, Copy, Debug, Reflect, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Reflect, Serialize, Deserialize)]
 pub enum LayerVariant {
     HairColour(u8),
     ClothingColour(u8),
     Variant(u8),
+    /// Any colour, as sRGB. The item's greyscale `tint` image is recoloured to it.
+    Tint(u8, u8, u8),
 }
 
 impl LayerVariant {
-    pub fn to_path_string(self) -> String {
+    /// The colour of a tinted layer
+    pub fn tint(self) -> Option<Color> {
         match self {
-            LayerVariant::HairColour(index) => (*HAIR_COLOURS)[index as usize].to_string(),
-            LayerVariant::ClothingColour(index) => (*CLOTHING_COLOURS)[index as usize].to_string(),
-            LayerVariant::Variant(number) => number.to_string(),
+            LayerVariant::Tint(red, green, blue) => Some(Color::srgb_u8(red, green, blue)),
+            _ => None,
         }
     }
 }
 
 /// Represents a single sprite layer in the character
-#[derive(Component, Clone, Debug, Reflect, Serialize, Deserialize)]
+#[derive(Component, Clone, Debug, PartialEq, Eq, Reflect, Serialize, Deserialize)]
 #[reflect(Component)]
 pub struct CharacterLayer {
     /// Type of layer
@@ -156,29 +153,6 @@ pub struct CharacterLayer {
     pub variant: Option<LayerVariant>,
 }
 
-impl CharacterLayer {
-    pub fn texture_path(&self) -> Result<String, ()> {
-        if self.item_name.is_none() && self.variant.is_none() {
-            return Err(());
-        }
-        let mut path = format!(
-            "images/character/{}",
-            self.layer_type.to_string().to_lowercase()
-        );
-        if let Some(item_name) = self.item_name.clone() {
-            path.push('/');
-            path.push_str(&item_name);
-        }
-        if let Some(variant) = self.variant {
-            path.push('/');
-            let variant_string = variant.to_path_string();
-            path.push_str(&variant_string);
-        }
-        path.push_str(".epng");
-        Ok(path)
-    }
-}
-
 /// The type of layer. Variants are in bottom-to-top order
 #[derive(
     Clone, Copy, Debug, Display, Reflect, PartialEq, Eq, Ord, PartialOrd, Serialize, Deserialize,
@@ -195,146 +169,17 @@ pub enum LayerType {
 }
 
 impl LayerType {
-    pub fn available_items(&self) -> Vec<CharacterLayer> {
-        let mut items = vec![];
-        match self {
-            LayerType::Body => {
-                for index in 1..=5 {
-                    items.push(CharacterLayer {
-                        layer_type: *self,
-                        item_name: None,
-                        variant: Some(LayerVariant::Variant(index)),
-                    });
-                }
-            }
-            LayerType::Cape | LayerType::Gloves => {
-                for index in 0..=4 {
-                    items.push(CharacterLayer {
-                        layer_type: *self,
-                        item_name: None,
-                        variant: Some(LayerVariant::ClothingColour(index)),
-                    });
-                }
-            }
-            LayerType::Hair => {
-                let item_names = vec!["1", "2", "3", "4", "5", "6"];
-                for name in item_names {
-                    for index in 0..=4 {
-                        items.push(CharacterLayer {
-                            layer_type: *self,
-                            item_name: Some(name.to_string()),
-                            variant: Some(LayerVariant::HairColour(index)),
-                        });
-                    }
-                }
-            }
-            LayerType::Underclothes => {
-                items.push(CharacterLayer {
-                    layer_type: *self,
-                    item_name: Some("armored-corset".to_string()),
-                    variant: None,
-                });
-                let item_names = vec![
-                    "bodice1",
-                    "bodice2",
-                    "bodice3",
-                    "corset1",
-                    "corset2",
-                    "corset3",
-                    "corset4",
-                    "bikini",
-                    "underwear",
-                ];
-                for name in item_names {
-                    for index in 0..=4 {
-                        items.push(CharacterLayer {
-                            layer_type: *self,
-                            item_name: Some(name.to_string()),
-                            variant: Some(LayerVariant::ClothingColour(index)),
-                        });
-                    }
-                }
-            }
-            LayerType::Footwear => {
-                items.push(CharacterLayer {
-                    layer_type: *self,
-                    item_name: Some("boots".to_string()),
-                    variant: None,
-                });
-                for index in 0..=4 {
-                    items.push(CharacterLayer {
-                        layer_type: *self,
-                        item_name: Some("socks".to_string()),
-                        variant: Some(LayerVariant::ClothingColour(index)),
-                    });
-                }
-                for index in 1..=5 {
-                    items.push(CharacterLayer {
-                        layer_type: *self,
-                        item_name: Some("thighhighs".to_string()),
-                        variant: Some(LayerVariant::Variant(index)),
-                    });
-                }
-            }
-            LayerType::Clothes => {
-                for index in 0..=4 {
-                    items.push(CharacterLayer {
-                        layer_type: *self,
-                        item_name: Some("dress".to_string()),
-                        variant: Some(LayerVariant::ClothingColour(index)),
-                    });
-                }
-                let item_names = vec![
-                    "sleeve-dress",
-                    "fancy-dress",
-                    "queen-dress",
-                    "skirt",
-                    "short-skirt",
-                ];
-                for name in item_names {
-                    items.push(CharacterLayer {
-                        layer_type: *self,
-                        item_name: Some(name.to_string()),
-                        variant: None,
-                    });
-                }
-            }
-            LayerType::Headwear => {
-                for index in 0..=4 {
-                    items.push(CharacterLayer {
-                        layer_type: *self,
-                        item_name: Some("hat".to_string()),
-                        variant: Some(LayerVariant::ClothingColour(index)),
-                    });
-                }
-                for index in 0..=4 {
-                    items.push(CharacterLayer {
-                        layer_type: *self,
-                        item_name: Some("cap".to_string()),
-                        variant: Some(LayerVariant::ClothingColour(index)),
-                    });
-                }
-
-                let item_names = vec!["farming-hat", "mining-helmet", "witch-hat", "santa-hat"];
-                for name in item_names {
-                    items.push(CharacterLayer {
-                        layer_type: *self,
-                        item_name: Some(name.to_string()),
-                        variant: None,
-                    });
-                }
-
-                for index in 1..=5 {
-                    items.push(CharacterLayer {
-                        layer_type: *self,
-                        item_name: Some("bunnyears".to_string()),
-                        variant: Some(LayerVariant::Variant(index)),
-                    });
-                }
-            }
-        };
-        items
-    }
+    /// Every layer type, bottom to top
+    pub const ALL: [LayerType; 8] = [
+        LayerType::Cape,
+        LayerType::Body,
+        LayerType::Hair,
+        LayerType::Underclothes,
+        LayerType::Footwear,
+        LayerType::Clothes,
+        LayerType::Gloves,
+        LayerType::Headwear,
+    ];
 }
 
 /// Tracks animation state for a layered character
@@ -346,7 +191,7 @@ pub struct CharacterAnimation {
     state: AnimationState,
 }
 
-#[derive(Reflect, PartialEq, Clone, Copy, Debug, Display)]
+#[derive(Reflect, PartialEq, Clone, Copy, Debug, Display, Serialize, Deserialize)]
 pub enum AnimationState {
     Idle,
     Walk,
@@ -466,14 +311,17 @@ fn create_bucket_sprite(
     )
 }
 
-/// Create a character layer sprite
-fn create_layer_sprite(
-    layer: &CharacterLayer,
+/// Marker component for the sprite drawing a character's baked layers
+#[derive(Component)]
+pub struct CharacterSprite;
+
+/// Create the sprite for a character's baked layers
+pub(super) fn create_character_sprite(
+    sheet: Handle<Image>,
     asset_server: &AssetServer,
     atlas_index: usize,
     flip_x: bool,
 ) -> impl Bundle {
-    let texture = asset_server.load(layer.texture_path().unwrap());
     let layout = TextureAtlasLayout::from_grid(
         UVec2::new(80, 64),
         COLUMNS,
@@ -483,9 +331,10 @@ fn create_layer_sprite(
     );
 
     (
-        Name::new(format!("{:?} Layer", layer.layer_type)),
+        Name::new("Character Sprite"),
+        CharacterSprite,
         Sprite {
-            image: texture,
+            image: sheet,
             texture_atlas: Some(TextureAtlas {
                 layout: asset_server.add(layout),
                 index: atlas_index,
@@ -494,16 +343,16 @@ fn create_layer_sprite(
             ..default()
         },
         Anchor::CENTER,
-        layer.clone(),
         Transform::default(),
     )
 }
 
-/// Spawns a layered character with all its sprite layers as children
+/// Spawns a character, drawn from its layers baked into one sheet
 pub fn spawn_character(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
-    character_layers: Res<CharacterLayers>,
+    mut compositor: Compositor,
+    played: PlayedLevel,
     spawn_point: Res<PlayerSpawnPoint>,
 ) {
     let animation = CharacterAnimation::new(AnimationState::Idle);
@@ -525,14 +374,11 @@ pub fn spawn_character(
         ))
         .id();
 
-    // Spawn each layer as a child sprite
-    for layer in character_layers.layers.iter() {
-        let layer_entity = commands
-            .spawn(create_layer_sprite(layer, &asset_server.clone(), 0, true))
-            .id();
-
-        commands.entity(character_id).add_child(layer_entity);
-    }
+    let sheet = compositor.composite(played.character_layers());
+    let sprite_entity = commands
+        .spawn(create_character_sprite(sheet, &asset_server, 0, true))
+        .id();
+    commands.entity(character_id).add_child(sprite_entity);
 
     let bucket_entity = commands
         .spawn(create_bucket_sprite(&asset_server.clone(), 0, true))
@@ -550,10 +396,10 @@ pub fn update_character_animation_timer(
     }
 }
 
-/// System that syncs all layer sprites to the character's current animation frame and direction
+/// System that syncs the character's sprites to its current animation frame and direction
 pub fn sync_layer_animations(
-    character_query: Query<(&CharacterAnimation, &Direction, &Children), With<Character>>,
-    mut layer_query: Query<&mut Sprite, Or<(With<CharacterLayer>, With<Bucket>)>>,
+    character_query: Query<(&CharacterAnimation, &Direction, &Children)>,
+    mut sprite_query: Query<&mut Sprite, Or<(With<CharacterSprite>, With<Bucket>)>>,
 ) {
     for (animation, direction, children) in &character_query {
         if !animation.just_changed() {
@@ -567,7 +413,7 @@ pub fn sync_layer_animations(
         let should_flip = *direction == Direction::Right;
 
         for child in children.iter() {
-            if let Ok(mut sprite) = layer_query.get_mut(child) {
+            if let Ok(mut sprite) = sprite_query.get_mut(child) {
                 if let Some(atlas) = sprite.texture_atlas.as_mut() {
                     atlas.index = atlas_index;
                 }
@@ -577,41 +423,21 @@ pub fn sync_layer_animations(
     }
 }
 
-/// System that updates the character entity when the CharacterLayers resource changes
+/// System that bakes the character again when the CharacterLayers resource changes
 pub fn update_character(
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    character_layers: Res<CharacterLayers>,
-    character_query: Query<(Entity, &Children, &CharacterAnimation, &Direction), With<Character>>,
-    layer_query: Query<Entity, With<CharacterLayer>>,
+    mut compositor: Compositor,
+    played: PlayedLevel,
+    character_query: Query<&Children, With<Character>>,
+    mut sprite_query: Query<&mut Sprite, With<CharacterSprite>>,
 ) {
-    if !character_layers.is_changed() {
+    if !played.is_changed() {
         return;
     }
 
-    for (character_entity, children, animation, direction) in &character_query {
-        // Remove all existing layer children
-        for child in children.iter() {
-            if layer_query.contains(child) {
-                commands.entity(child).despawn();
-            }
-        }
-
-        let (row, _, _, _) = animation.state().get_animation_config();
-        let atlas_index = row * (COLUMNS as usize) + animation.current_frame();
-        let should_flip = *direction == Direction::Right;
-
-        for layer in character_layers.layers.iter() {
-            let layer_entity = commands
-                .spawn(create_layer_sprite(
-                    layer,
-                    &asset_server.clone(),
-                    atlas_index,
-                    should_flip,
-                ))
-                .id();
-
-            commands.entity(character_entity).add_child(layer_entity);
+    for children in &character_query {
+        let mut sprites = sprite_query.iter_many_mut(children);
+        while let Some(mut sprite) = sprites.fetch_next() {
+            sprite.image = compositor.composite(played.character_layers());
         }
     }
 }
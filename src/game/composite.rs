@@ -0,0 +1,316 @@
+//! Baking a character's layers into a single sprite sheet.
+//!
+//! The character, the ghost and the character select preview each draw one sprite from the
+//! baked sheet instead of one per layer. Sheets are baked once every layer's image has
+//! loaded, recolouring tinted layers as they go, and the first frame can be saved as an avatar.
+
+use bevy::{
+    asset::RenderAssetUsages,
+    ecs::system::SystemParam,
+    image::IntoDynamicImageError,
+    prelude::*,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+};
+use thiserror::Error;
+
+use super::character::{CharacterLayers, LayerVariant};
+use super::tint::recolour;
+use super::wardrobe::Wardrobe;
+
+pub fn plugin(app: &mut App) {
+    app.init_resource::<CompositeQueue>();
+    app.add_systems(Update, bake_composites);
+}
+
+/// Size of a frame in the character sprite sheets
+pub const FRAME_SIZE: UVec2 = UVec2::new(80, 64);
+
+/// Offset of the first frame in the character sprite sheets
+pub const SHEET_OFFSET: UVec2 = UVec2::new(0, 1);
+
+/// How much the avatar is scaled up when saved
+const AVATAR_SCALE: u32 = 4;
+
+/// Resource holding the sheets waiting for their layers' images to load
+#[derive(Resource, Default)]
+pub struct CompositeQueue(Vec<PendingComposite>);
+
+struct PendingComposite {
+    sheet: Handle<Image>,
+    /// Each layer's image, bottom to top, and its tint if it has one
+    layers: Vec<(Handle<Image>, Option<Color>)>,
+}
+
+/// System parameter for baking characters' layers into sheets
+#[derive(SystemParam)]
+pub struct Compositor<'w> {
+    asset_server: Res<'w, AssetServer>,
+    wardrobe: Wardrobe<'w>,
+    images: Res<'w, Assets<Image>>,
+    queue: ResMut<'w, CompositeQueue>,
+}
+
+impl Compositor<'_> {
+    /// Returns the sheet for `layers`, which is filled in once their images have loaded
+    ///
+    /// Layers that aren't in the wardrobe are left out.
+    pub fn composite(&mut self, layers: &CharacterLayers) -> Handle<Image> {
+        let mut sorted = layers.layers.clone();
+        sorted.sort_by_key(|layer| layer.layer_type);
+
+        let layers = sorted
+            .iter()
+            .filter_map(|layer| {
+                let Some(path) = self.wardrobe.texture_path(layer) else {
+                    warn!("Leaving {layer:?} out of the character, as it isn't in the wardrobe");
+                    return None;
+                };
+                let tint = layer.variant.and_then(LayerVariant::tint);
+                Some((self.asset_server.load(path), tint))
+            })
+            .collect();
+
+        let sheet = self.images.reserve_handle();
+        self.queue.0.push(PendingComposite {
+            sheet: sheet.clone(),
+            layers,
+        });
+        sheet
+    }
+}
+
+/// System to bake each queued sheet once its layers have loaded
+fn bake_composites(
+    mut queue: ResMut<CompositeQueue>,
+    asset_server: Res<AssetServer>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    for pending in std::mem::take(&mut queue.0) {
+        // Layers that failed to load are left out rather than waited on forever
+        let waiting = pending.layers.iter().any(|(layer, _)| {
+            !images.contains(layer) && !asset_server.load_state(layer).is_failed()
+        });
+        if waiting {
+            queue.0.push(pending);
+            continue;
+        }
+
+        let layers: Vec<_> = pending
+            .layers
+            .iter()
+            .filter_map(|(layer, tint)| Some((images.get(layer)?, *tint)))
+            .collect();
+        let Some(sheet) = composite(&layers) else {
+            warn!("Failed to bake the character's layers");
+            continue;
+        };
+        if let Err(error) = images.insert(&pending.sheet, sheet) {
+            warn!("Failed to store the character's sheet: {error}");
+        }
+    }
+}
+
+/// Draws each layer over the ones below it, recolouring tinted layers
+///
+/// Layers must be 8-bit RGBA and the same size as the first one, and any that aren't are
+/// left out. Returns none if there are no layers to draw.
+pub fn composite(layers: &[(&Image, Option<Color>)]) -> Option<Image> {
+    let (first, _) = layers.first()?;
+    let size = first.texture_descriptor.size;
+    let mut data = vec![0; (size.width * size.height * 4) as usize];
+
+    for (layer, tint) in layers {
+        let Some(pixels) = rgba_pixels(layer) else {
+            warn!("Leaving a layer out of the character, as it isn't 8-bit RGBA");
+            continue;
+        };
+        if layer.texture_descriptor.size != size {
+            warn!("Leaving a layer out of the character, as it's a different size");
+            continue;
+        }
+
+        let tint = tint.map(LinearRgba::from);
+        for (output, pixel) in data.chunks_exact_mut(4).zip(pixels.chunks_exact(4)) {
+            let pixel = match tint {
+                Some(tint) => {
+                    let base = Srgba::rgba_u8(pixel[0], pixel[1], pixel[2], pixel[3]);
+                    recolour(base, tint).to_u8_array()
+                }
+                None => [pixel[0], pixel[1], pixel[2], pixel[3]],
+            };
+            blend_over(output, pixel);
+        }
+    }
+
+    Some(Image::new(
+        size,
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    ))
+}
+
+/// Returns the image's pixels if it's 8-bit RGBA
+fn rgba_pixels(image: &Image) -> Option<&[u8]> {
+    match image.texture_descriptor.format {
+        TextureFormat::Rgba8UnormSrgb | TextureFormat::Rgba8Unorm => image.data.as_deref(),
+        _ => None,
+    }
+}
+
+/// Draws a pixel over another, with straight alpha
+fn blend_over(destination: &mut [u8], source: [u8; 4]) {
+    let source_alpha = source[3] as f32 / 255.0;
+    let destination_alpha = destination[3] as f32 / 255.0;
+    let alpha = source_alpha + destination_alpha * (1.0 - source_alpha);
+    if alpha <= 0.0 {
+        return;
+    }
+
+    for channel in 0..3 {
+        let blended = (source[channel] as f32 * source_alpha
+            + destination[channel] as f32 * destination_alpha * (1.0 - source_alpha))
+            / alpha;
+        destination[channel] = blended.round() as u8;
+    }
+    destination[3] = (alpha * 255.0).round() as u8;
+}
+
+#[derive(Error, Debug)]
+pub enum AvatarError {
+    #[error("The character hasn't finished baking")]
+    NotReady,
+    #[error(transparent)]
+    Convert(#[from] IntoDynamicImageError),
+    #[error("Failed to write the avatar: {0}")]
+    Write(String),
+}
+
+/// Crops the first frame of a baked sheet, scaled up for saving as an avatar
+pub fn avatar(sheet: &Image) -> Result<Image, AvatarError> {
+    let pixels = rgba_pixels(sheet).ok_or(AvatarError::NotReady)?;
+    let sheet_width = sheet.width();
+    if sheet_width < SHEET_OFFSET.x + FRAME_SIZE.x || sheet.height() < SHEET_OFFSET.y + FRAME_SIZE.y
+    {
+        return Err(AvatarError::NotReady);
+    }
+
+    let size = FRAME_SIZE * AVATAR_SCALE;
+    let mut data = Vec::with_capacity((size.x * size.y * 4) as usize);
+    for y in 0..size.y {
+        for x in 0..size.x {
+            let sheet_x = SHEET_OFFSET.x + x / AVATAR_SCALE;
+            let sheet_y = SHEET_OFFSET.y + y / AVATAR_SCALE;
+            let index = ((sheet_y * sheet_width + sheet_x) * 4) as usize;
+            data.extend_from_slice(&pixels[index..index + 4]);
+        }
+    }
+
+    Ok(Image::new(
+        Extent3d {
+            width: size.x,
+            height: size.y,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    ))
+}
+
+/// Saves the first frame of a baked sheet as a PNG
+#[cfg(not(target_family = "wasm"))]
+pub fn save_avatar(sheet: &Image, path: &std::path::Path) -> Result<(), AvatarError> {
+    avatar(sheet)?
+        .try_into_dynamic()?
+        .save(path)
+        .map_err(|error| AvatarError::Write(error.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solid(size: UVec2, colour: [u8; 4]) -> Image {
+        Image::new_fill(
+            Extent3d {
+                width: size.x,
+                height: size.y,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            &colour,
+            TextureFormat::Rgba8UnormSrgb,
+            RenderAssetUsages::default(),
+        )
+    }
+
+    fn pixel(image: &Image, x: u32, y: u32) -> [u8; 4] {
+        let index = ((y * image.width() + x) * 4) as usize;
+        image.data.as_ref().unwrap()[index..index + 4]
+            .try_into()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_upper_layers_are_drawn_over_lower_ones() {
+        let bottom = solid(UVec2::splat(2), [255, 0, 0, 255]);
+        let top = solid(UVec2::splat(2), [0, 0, 255, 255]);
+        let clear = solid(UVec2::splat(2), [0, 255, 0, 0]);
+
+        let sheet = composite(&[(&bottom, None), (&top, None), (&clear, None)]).unwrap();
+        assert_eq!(pixel(&sheet, 1, 1), [0, 0, 255, 255]);
+    }
+
+    #[test]
+    fn test_translucent_layers_blend() {
+        let bottom = solid(UVec2::splat(1), [0, 0, 0, 255]);
+        let top = solid(UVec2::splat(1), [255, 255, 255, 128]);
+        let sheet = composite(&[(&bottom, None), (&top, None)]).unwrap();
+        assert_eq!(pixel(&sheet, 0, 0), [128, 128, 128, 255]);
+    }
+
+    #[test]
+    fn test_tinted_layers_are_recoloured() {
+        let white = solid(UVec2::splat(1), [255, 255, 255, 255]);
+        let black = solid(UVec2::splat(1), [0, 0, 0, 255]);
+        let tint = Some(Color::srgb(1.0, 0.0, 0.0));
+        assert_eq!(
+            pixel(&composite(&[(&white, tint)]).unwrap(), 0, 0),
+            [255, 255, 255, 255]
+        );
+        assert_eq!(
+            pixel(&composite(&[(&black, tint)]).unwrap(), 0, 0),
+            [0, 0, 0, 255]
+        );
+    }
+
+    #[test]
+    fn test_mismatched_layers_are_left_out() {
+        let bottom = solid(UVec2::splat(2), [255, 0, 0, 255]);
+        let other_size = solid(UVec2::splat(3), [0, 0, 255, 255]);
+        let sheet = composite(&[(&bottom, None), (&other_size, None)]).unwrap();
+        assert_eq!(pixel(&sheet, 0, 0), [255, 0, 0, 255]);
+        assert!(composite(&[]).is_none());
+    }
+
+    #[test]
+    fn test_avatar_is_the_first_frame_scaled_up() {
+        let sheet_size = SHEET_OFFSET + FRAME_SIZE * UVec2::new(2, 1);
+        let mut sheet = solid(sheet_size, [0, 0, 0, 0]);
+        // Mark the first frame's top left pixel
+        let index = ((SHEET_OFFSET.y * sheet_size.x + SHEET_OFFSET.x) * 4) as usize;
+        sheet.data.as_mut().unwrap()[index..index + 4].copy_from_slice(&[9, 8, 7, 255]);
+
+        let avatar = avatar(&sheet).unwrap();
+        assert_eq!(avatar.width(), FRAME_SIZE.x * AVATAR_SCALE);
+        assert_eq!(avatar.height(), FRAME_SIZE.y * AVATAR_SCALE);
+        assert_eq!(
+            pixel(&avatar, AVATAR_SCALE - 1, AVATAR_SCALE - 1),
+            [9, 8, 7, 255]
+        );
+        assert_eq!(pixel(&avatar, AVATAR_SCALE, 0), [0, 0, 0, 0]);
+    }
+}
@@ -1,7 +1,12 @@
 use bevy::prelude::*;
+use bevy_pkv::prelude::*;
 use leafwing_input_manager::prelude::*;
+use serde::{Deserialize, Serialize};
+use strum::Display;
 
-#[derive(Actionlike, PartialEq, Eq, Hash, Clone, Copy, Debug, Reflect)]
+#[derive(
+    Actionlike, PartialEq, Eq, Hash, Clone, Copy, Debug, Display, Reflect, Serialize, Deserialize,
+)]
 pub enum Action {
     #[actionlike(DualAxis)]
     Run,
@@ -9,14 +14,63 @@ pub enum Action {
     Use,
     Menu,
     Select,
+    Rewind,
+    /// Moves focus between menu buttons
+    #[actionlike(DualAxis)]
+    Navigate,
+}
+
+/// Actions the player can rebind, in the order they are listed in the controls menu
+pub const REBINDABLE_ACTIONS: [Action; 5] = [
+    Action::Jump,
+    Action::Use,
+    Action::Rewind,
+    Action::Menu,
+    Action::Select,
+];
+
+/// The key the player's bindings are stored under in the [`PkvStore`]
+const INPUT_MAP_KEY: &str = "input_map";
+
+/// The kind of device the player last used, for showing matching button prompts
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum InputDevice {
+    #[default]
+    Keyboard,
+    Gamepad,
+    Touch,
 }
 
 pub fn plugin(app: &mut App) {
     app.add_plugins(InputManagerPlugin::<Action>::default());
+    app.init_resource::<InputDevice>();
     app.add_systems(Startup, setup_input);
+    app.add_systems(Update, detect_input_device);
 }
 
-fn setup_input(mut commands: Commands) {
+fn setup_input(mut commands: Commands, pkv: Res<PkvStore>) {
+    let input_map = match pkv.get::<InputMap<Action>>(INPUT_MAP_KEY) {
+        Ok(mut input_map) => {
+            // Bindings saved before menu navigation existed won't have it
+            if input_map.get_dual_axislike(&Action::Navigate).is_none() {
+                insert_navigation_bindings(&mut input_map);
+            }
+            input_map
+        }
+        Err(_) => default_input_map(),
+    };
+    commands.spawn(input_map);
+}
+
+/// Persists the player's bindings
+pub fn save_input_map(pkv: &mut PkvStore, input_map: &InputMap<Action>) {
+    if let Err(error) = pkv.set(INPUT_MAP_KEY, input_map) {
+        warn!("Failed to save controls: {error}");
+    }
+}
+
+/// The bindings used before the player changes anything
+pub fn default_input_map() -> InputMap<Action> {
     let mut input_map = InputMap::default();
 
     input_map.insert_dual_axis(Action::Run, VirtualDPad::wasd());
@@ -32,10 +86,291 @@ fn setup_input(mut commands: Commands) {
     input_map.insert(Action::Use, GamepadButton::West);
 
     input_map.insert(Action::Menu, KeyCode::Escape);
+    input_map.insert(Action::Menu, KeyCode::KeyP);
     input_map.insert(Action::Menu, GamepadButton::Start);
 
     input_map.insert(Action::Select, KeyCode::Enter);
+    input_map.insert(Action::Select, KeyCode::Space);
     input_map.insert(Action::Select, GamepadButton::South);
 
-    commands.spawn(input_map);
+    input_map.insert(Action::Rewind, KeyCode::KeyR);
+    input_map.insert(Action::Rewind, GamepadButton::LeftTrigger);
+
+    insert_navigation_bindings(&mut input_map);
+
+    input_map
+}
+
+fn insert_navigation_bindings(input_map: &mut InputMap<Action>) {
+    input_map.insert_dual_axis(Action::Navigate, VirtualDPad::arrow_keys());
+    input_map.insert_dual_axis(Action::Navigate, VirtualDPad::dpad());
+    input_map.insert_dual_axis(Action::Navigate, GamepadStick::LEFT);
+}
+
+/// A single key or gamepad button bound to an action
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Binding {
+    Key(KeyCode),
+    Gamepad(GamepadButton),
+}
+
+impl Binding {
+    pub fn device(&self) -> InputDevice {
+        match self {
+            Binding::Key(_) => InputDevice::Keyboard,
+            Binding::Gamepad(_) => InputDevice::Gamepad,
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            Binding::Key(key) => key_label(*key),
+            Binding::Gamepad(button) => gamepad_button_label(*button),
+        }
+    }
+}
+
+/// Returns the keys and buttons bound to a buttonlike action
+pub fn bindings(input_map: &InputMap<Action>, action: Action) -> Vec<Binding> {
+    let Some(bindings) = input_map.get_buttonlike(&action) else {
+        return Vec::new();
+    };
+    bindings
+        .iter()
+        .filter_map(|binding| as_binding(binding.as_ref()))
+        .collect()
+}
+
+/// Returns the keys and buttons making up the directions of a dual-axis action's d-pads
+fn dpad_bindings(input_map: &InputMap<Action>, action: Action) -> Vec<Binding> {
+    let Some(inputs) = input_map.get_dual_axislike(&action) else {
+        return Vec::new();
+    };
+    inputs
+        .iter()
+        .filter_map(|input| input.as_ref().as_reflect().downcast_ref::<VirtualDPad>())
+        .flat_map(|dpad| [&dpad.up, &dpad.down, &dpad.left, &dpad.right])
+        .filter_map(|direction| as_binding(direction.as_ref()))
+        .collect()
+}
+
+fn as_binding(input: &dyn Buttonlike) -> Option<Binding> {
+    let input = input.as_reflect();
+    input
+        .downcast_ref::<KeyCode>()
+        .map(|key| Binding::Key(*key))
+        .or_else(|| {
+            input
+                .downcast_ref::<GamepadButton>()
+                .map(|button| Binding::Gamepad(*button))
+        })
+}
+
+/// Returns true if an action is only read in menus
+fn is_menu_only(action: Action) -> bool {
+    matches!(action, Action::Select | Action::Navigate)
+}
+
+/// Returns true if two actions can be triggered at the same time, so can't share a binding
+///
+/// Menu-only actions may share a binding with gameplay actions, and `Menu` is read everywhere.
+fn shares_context(a: Action, b: Action) -> bool {
+    a == Action::Menu || b == Action::Menu || is_menu_only(a) == is_menu_only(b)
+}
+
+/// Replaces an action's bindings for the new binding's device
+///
+/// Returns the conflicting action instead if the binding is already in use.
+pub fn rebind(
+    input_map: &mut InputMap<Action>,
+    action: Action,
+    binding: Binding,
+) -> Result<(), Action> {
+    // Movement is bound as d-pads, so their directions are checked too
+    if let Some(conflict) = REBINDABLE_ACTIONS
+        .into_iter()
+        .chain([Action::Run, Action::Navigate])
+        .find(|other| {
+            *other != action
+                && shares_context(action, *other)
+                && (bindings(input_map, *other).contains(&binding)
+                    || dpad_bindings(input_map, *other).contains(&binding))
+        })
+    {
+        return Err(conflict);
+    }
+
+    let kept: Vec<_> = bindings(input_map, action)
+        .into_iter()
+        .filter(|existing| existing.device() != binding.device())
+        .chain(std::iter::once(binding))
+        .collect();
+
+    input_map.clear_action(&action);
+    for binding in kept {
+        match binding {
+            Binding::Key(key) => input_map.insert(action, key),
+            Binding::Gamepad(button) => input_map.insert(action, button),
+        };
+    }
+    Ok(())
+}
+
+/// Run condition that is true on the frame `action` is pressed
+pub fn action_just_pressed(action: Action) -> impl FnMut(Query<&ActionState<Action>>) -> bool {
+    move |action_query: Query<&ActionState<Action>>| {
+        action_query
+            .single()
+            .is_ok_and(|action_state| action_state.just_pressed(&action))
+    }
+}
+
+/// System to track whether the keyboard, a gamepad or a touchscreen was used most recently
+fn detect_input_device(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    touches: Res<Touches>,
+    mut device: ResMut<InputDevice>,
+) {
+    if touches.any_just_pressed() {
+        device.set_if_neq(InputDevice::Touch);
+    } else if keyboard.get_just_pressed().len() > 0 {
+        device.set_if_neq(InputDevice::Keyboard);
+    } else if gamepads.iter().any(|gamepad| {
+        gamepad.get_just_pressed().next().is_some() || gamepad.left_stick().length() > 0.5
+    }) {
+        device.set_if_neq(InputDevice::Gamepad);
+    }
+}
+
+/// Returns a short label for the first binding of `action` on the given device
+pub fn binding_label(
+    input_map: &InputMap<Action>,
+    action: Action,
+    device: InputDevice,
+) -> Option<String> {
+    bindings(input_map, action)
+        .into_iter()
+        .find(|binding| binding.device() == device)
+        .map(|binding| binding.label())
+}
+
+/// Returns a short, human-readable label for a key
+pub fn key_label(key: KeyCode) -> String {
+    let name = format!("{:?}", key);
+    match key {
+        KeyCode::ArrowUp => "Up".to_string(),
+        KeyCode::ArrowDown => "Down".to_string(),
+        KeyCode::ArrowLeft => "Left".to_string(),
+        KeyCode::ArrowRight => "Right".to_string(),
+        KeyCode::Escape => "Esc".to_string(),
+        _ => name
+            .strip_prefix("Key")
+            .or_else(|| name.strip_prefix("Digit"))
+            .unwrap_or(&name)
+            .to_string(),
+    }
+}
+
+/// Returns a short label for a gamepad button, using Xbox-style face button names
+pub fn gamepad_button_label(button: GamepadButton) -> String {
+    match button {
+        GamepadButton::South => "A".to_string(),
+        GamepadButton::East => "B".to_string(),
+        GamepadButton::West => "X".to_string(),
+        GamepadButton::North => "Y".to_string(),
+        GamepadButton::LeftTrigger => "LB".to_string(),
+        GamepadButton::RightTrigger => "RB".to_string(),
+        GamepadButton::LeftTrigger2 => "LT".to_string(),
+        GamepadButton::RightTrigger2 => "RT".to_string(),
+        GamepadButton::DPadUp => "D-Pad Up".to_string(),
+        GamepadButton::DPadDown => "D-Pad Down".to_string(),
+        GamepadButton::DPadLeft => "D-Pad Left".to_string(),
+        GamepadButton::DPadRight => "D-Pad Right".to_string(),
+        other => format!("{:?}", other),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rebind_replaces_device_bindings() {
+        let mut input_map = default_input_map();
+        rebind(&mut input_map, Action::Jump, Binding::Key(KeyCode::KeyK)).unwrap();
+
+        let jump = bindings(&input_map, Action::Jump);
+        assert!(jump.contains(&Binding::Key(KeyCode::KeyK)));
+        assert!(!jump.contains(&Binding::Key(KeyCode::Space)));
+        // Gamepad bindings are untouched
+        assert!(jump.contains(&Binding::Gamepad(GamepadButton::South)));
+    }
+
+    #[test]
+    fn test_rebind_detects_conflicts() {
+        let mut input_map = default_input_map();
+        assert_eq!(
+            rebind(&mut input_map, Action::Jump, Binding::Key(KeyCode::KeyE)),
+            Err(Action::Use)
+        );
+        assert!(bindings(&input_map, Action::Jump).contains(&Binding::Key(KeyCode::Space)));
+
+        // Select is only used in menus, so it can share with Use
+        assert_eq!(
+            rebind(&mut input_map, Action::Select, Binding::Key(KeyCode::KeyE)),
+            Ok(())
+        );
+        // ...but not with Menu
+        assert_eq!(
+            rebind(
+                &mut input_map,
+                Action::Select,
+                Binding::Key(KeyCode::Escape)
+            ),
+            Err(Action::Menu)
+        );
+    }
+
+    #[test]
+    fn test_rebind_detects_movement_conflicts() {
+        let mut input_map = default_input_map();
+        assert_eq!(
+            rebind(&mut input_map, Action::Use, Binding::Key(KeyCode::KeyA)),
+            Err(Action::Run)
+        );
+        assert_eq!(
+            rebind(
+                &mut input_map,
+                Action::Rewind,
+                Binding::Key(KeyCode::ArrowLeft)
+            ),
+            Err(Action::Run)
+        );
+        assert_eq!(
+            rebind(
+                &mut input_map,
+                Action::Select,
+                Binding::Gamepad(GamepadButton::DPadUp)
+            ),
+            Err(Action::Navigate)
+        );
+        assert_eq!(
+            rebind(
+                &mut input_map,
+                Action::Menu,
+                Binding::Gamepad(GamepadButton::DPadDown)
+            ),
+            Err(Action::Navigate)
+        );
+        // Gameplay actions may share with menu navigation
+        assert_eq!(
+            rebind(
+                &mut input_map,
+                Action::Use,
+                Binding::Gamepad(GamepadButton::DPadUp)
+            ),
+            Ok(())
+        );
+    }
 }
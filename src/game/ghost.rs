@@ -0,0 +1,391 @@
+//! Ghost racing against the player's best completion of a level.
+
+use bevy::prelude::*;
+use bevy_pkv::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::character::{
+    AnimationState, Character, CharacterAnimation, CharacterLayers, Direction,
+    create_character_sprite,
+};
+use super::composite::Compositor;
+use super::interactions::LevelCompleteMessage;
+use super::level::{LevelSeed, spawn_level};
+use super::replay::{PlayedLevel, ReplayPlayback};
+use super::save::SaveSlots;
+use super::{GameLevel, Season};
+use crate::{PausableSystems, screens::Screen};
+
+pub fn plugin(app: &mut App) {
+    app.register_type::<GhostSettings>();
+    app.init_persistent_resource::<GhostSettings>();
+    app.init_persistent_resource::<StoredGhosts>();
+    app.init_resource::<GhostRecorder>();
+
+    app.add_systems(
+        OnEnter(Screen::Gameplay),
+        (start_ghost_recording, spawn_ghost).after(spawn_level),
+    );
+    app.add_systems(
+        Update,
+        (
+            record_ghost_frame,
+            save_best_ghost,
+            update_ghost,
+            fade_ghost_sprite,
+            toggle_ghost_visibility,
+        )
+            .chain()
+            .in_set(PausableSystems)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+}
+
+/// How opaque the ghost's sprites are
+const GHOST_ALPHA: f32 = 0.4;
+
+/// How often the character's pose is recorded, in seconds
+///
+/// Positions are interpolated between frames, so this only needs to catch changes of direction
+/// and animation.
+const GHOST_SAMPLE_INTERVAL: f32 = 1.0 / 15.0;
+
+/// How many best runs are kept in storage, across every slot
+const MAX_STORED_GHOSTS: usize = 10;
+
+/// Whether to show a ghost of the player's best run
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Reflect, Resource, Serialize, Deserialize)]
+pub struct GhostSettings {
+    pub enabled: bool,
+}
+
+impl Default for GhostSettings {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+/// The character's pose at a point in a run
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GhostFrame {
+    /// Seconds since the level started
+    pub time: f32,
+    pub position: (f32, f32),
+    pub facing_right: bool,
+    pub animation: AnimationState,
+}
+
+/// A completed run of a level, drawn as a ghost on later attempts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GhostRun {
+    /// Completion time in seconds
+    pub time: f32,
+    pub character_layers: CharacterLayers,
+    pub frames: Vec<GhostFrame>,
+}
+
+impl GhostRun {
+    /// Returns the latest frame at or before `time`
+    pub fn sample(&self, time: f32) -> Option<&GhostFrame> {
+        let index = self.frames.partition_point(|frame| frame.time <= time);
+        self.frames.get(index.checked_sub(1)?)
+    }
+
+    /// Returns the position at `time`, interpolated between the frames either side of it
+    pub fn position(&self, time: f32) -> Option<Vec2> {
+        let index = self.frames.partition_point(|frame| frame.time <= time);
+        let before = self.frames.get(index.checked_sub(1)?)?;
+        let before_position = Vec2::new(before.position.0, before.position.1);
+        let Some(after) = self.frames.get(index) else {
+            return Some(before_position);
+        };
+        let t = (time - before.time) / (after.time - before.time);
+        Some(before_position.lerp(Vec2::new(after.position.0, after.position.1), t))
+    }
+
+    /// Returns true if this run beats `best`, or there is no best yet
+    pub fn beats(&self, best: Option<&GhostRun>) -> bool {
+        best.is_none_or(|best| self.time < best.time)
+    }
+}
+
+/// The key a level's best run in the save slot `slot` is stored under
+pub fn ghost_key(slot: &str, season: Season, game_level: GameLevel, seed: u64) -> String {
+    format!("ghost-{}-{}-{}-{}", slot, season, game_level.0, seed)
+}
+
+/// Resource listing the keys of the best runs in storage, least recently saved first
+///
+/// Levels are generated from a new seed each time, so old runs are rarely raced again. Only the
+/// most recent are kept, so they can't fill up storage and stop save slots being written.
+#[derive(Resource, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StoredGhosts {
+    keys: Vec<String>,
+}
+
+impl StoredGhosts {
+    /// Marks `key` as the most recently saved run, returning the keys of runs to remove
+    pub fn record(&mut self, key: &str) -> Vec<String> {
+        self.keys.retain(|stored| stored != key);
+        self.keys.push(key.to_string());
+        let excess = self.keys.len().saturating_sub(MAX_STORED_GHOSTS);
+        self.keys.drain(..excess).collect()
+    }
+}
+
+/// Resource recording the current attempt so it can become the new best
+#[derive(Resource, Debug, Default)]
+pub struct GhostRecorder {
+    key: String,
+    run: Option<GhostRun>,
+}
+
+/// Component for the ghost character, replaying a best run
+#[derive(Component)]
+pub struct Ghost {
+    run: GhostRun,
+    elapsed: f32,
+}
+
+/// Marker component for the ghost's sprite
+#[derive(Component)]
+struct GhostSprite;
+
+fn start_ghost_recording(
+    mut recorder: ResMut<GhostRecorder>,
+    playback: Option<Res<ReplayPlayback>>,
+    slots: Res<SaveSlots>,
+    played: PlayedLevel,
+    level_seed: Res<LevelSeed>,
+) {
+    let slot = slots.active.as_deref().unwrap_or_default();
+    recorder.key = ghost_key(slot, played.season(), played.game_level(), level_seed.0);
+    // Watching a replay shouldn't set a personal best
+    recorder.run = playback.is_none().then(|| GhostRun {
+        time: 0.0,
+        character_layers: played.character_layers().clone(),
+        frames: Vec::new(),
+    });
+}
+
+/// Spawns a ghost of the best run of this level, if there is one
+fn spawn_ghost(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut compositor: Compositor,
+    pkv: Res<PkvStore>,
+    settings: Res<GhostSettings>,
+    recorder: Res<GhostRecorder>,
+) {
+    let Ok(run) = pkv.get::<GhostRun>(&recorder.key) else {
+        return;
+    };
+    let Some(first) = run.frames.first() else {
+        return;
+    };
+
+    let sheet = compositor.composite(&run.character_layers);
+    let ghost = commands
+        .spawn((
+            Name::new("Ghost"),
+            Transform::from_xyz(first.position.0, first.position.1, -0.5),
+            if settings.enabled {
+                Visibility::Inherited
+            } else {
+                Visibility::Hidden
+            },
+            CharacterAnimation::new(first.animation),
+            Direction::default(),
+            Ghost { run, elapsed: 0.0 },
+            DespawnOnExit(Screen::Gameplay),
+        ))
+        .id();
+
+    commands.entity(ghost).with_child((
+        create_character_sprite(sheet, &asset_server, 0, true),
+        GhostSprite,
+    ));
+}
+
+/// System to record the character's pose every [`GHOST_SAMPLE_INTERVAL`]
+fn record_ghost_frame(
+    time: Res<Time>,
+    mut recorder: ResMut<GhostRecorder>,
+    character_query: Query<(&Transform, &Direction, &CharacterAnimation), With<Character>>,
+) {
+    let Some(run) = &mut recorder.run else {
+        return;
+    };
+    let Ok((transform, direction, animation)) = character_query.single() else {
+        return;
+    };
+
+    run.time += time.delta_secs();
+    if run
+        .frames
+        .last()
+        .is_some_and(|last| run.time - last.time < GHOST_SAMPLE_INTERVAL)
+    {
+        return;
+    }
+    run.frames.push(GhostFrame {
+        time: run.time,
+        position: (transform.translation.x, transform.translation.y),
+        facing_right: *direction == Direction::Right,
+        animation: animation.state(),
+    });
+}
+
+/// System to store the run as the new best when the level is completed faster
+fn save_best_ghost(
+    mut level_complete_reader: MessageReader<LevelCompleteMessage>,
+    mut recorder: ResMut<GhostRecorder>,
+    mut stored: ResMut<StoredGhosts>,
+    mut pkv: ResMut<PkvStore>,
+) {
+    if level_complete_reader.read().last().is_none() {
+        return;
+    }
+    let Some(run) = recorder.run.take() else {
+        return;
+    };
+
+    let best = pkv.get::<GhostRun>(&recorder.key).ok();
+    if !run.beats(best.as_ref()) {
+        return;
+    }
+
+    for key in stored.record(&recorder.key) {
+        if let Err(error) = pkv.remove(&key) {
+            warn!("Failed to remove old best run {key}: {error}");
+        }
+    }
+    match pkv.set(&recorder.key, &run) {
+        Ok(()) => info!("New best time: {:.2}s", run.time),
+        Err(error) => warn!("Failed to save best run: {error}"),
+    }
+}
+
+/// System to move the ghost along its recorded path
+fn update_ghost(
+    time: Res<Time>,
+    mut ghost_query: Query<(
+        &mut Ghost,
+        &mut Transform,
+        &mut Direction,
+        &mut CharacterAnimation,
+    )>,
+) {
+    for (mut ghost, mut transform, mut direction, mut animation) in &mut ghost_query {
+        ghost.elapsed += time.delta_secs();
+        let (Some(frame), Some(position)) = (
+            ghost.run.sample(ghost.elapsed).copied(),
+            ghost.run.position(ghost.elapsed),
+        ) else {
+            continue;
+        };
+
+        transform.translation.x = position.x;
+        transform.translation.y = position.y;
+        direction.set_if_neq(if frame.facing_right {
+            Direction::Right
+        } else {
+            Direction::Left
+        });
+        animation.set_state(frame.animation);
+    }
+}
+
+/// System to make the newly spawned ghost translucent
+fn fade_ghost_sprite(mut sprite_query: Query<&mut Sprite, Added<GhostSprite>>) {
+    for mut sprite in &mut sprite_query {
+        sprite.color = sprite.color.with_alpha(GHOST_ALPHA);
+    }
+}
+
+/// System to show or hide the ghost when the setting changes
+fn toggle_ghost_visibility(
+    settings: Res<GhostSettings>,
+    mut ghost_query: Query<&mut Visibility, With<Ghost>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+
+    for mut visibility in &mut ghost_query {
+        *visibility = if settings.enabled {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(time: f32) -> GhostRun {
+        GhostRun {
+            time,
+            character_layers: CharacterLayers::default(),
+            frames: (1..=3)
+                .map(|i| GhostFrame {
+                    time: i as f32,
+                    position: (i as f32 * 10.0, 0.0),
+                    facing_right: true,
+                    animation: AnimationState::Walk,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_sample_picks_latest_frame() {
+        let run = run(3.0);
+        assert!(run.sample(0.5).is_none());
+        assert_eq!(run.sample(1.0).unwrap().position.0, 10.0);
+        assert_eq!(run.sample(2.5).unwrap().position.0, 20.0);
+        assert_eq!(run.sample(100.0).unwrap().position.0, 30.0);
+    }
+
+    #[test]
+    fn test_position_is_interpolated() {
+        let run = run(3.0);
+        assert!(run.position(0.5).is_none());
+        assert_eq!(run.position(1.5), Some(Vec2::new(15.0, 0.0)));
+        assert_eq!(run.position(100.0), Some(Vec2::new(30.0, 0.0)));
+    }
+
+    #[test]
+    fn test_only_recent_ghosts_are_kept() {
+        let mut stored = StoredGhosts::default();
+        for i in 0..MAX_STORED_GHOSTS {
+            assert!(stored.record(&format!("ghost-{i}")).is_empty());
+        }
+
+        // Saving an existing run again makes it the most recent
+        assert!(stored.record("ghost-0").is_empty());
+        assert_eq!(stored.record("ghost-new"), vec!["ghost-1".to_string()]);
+        assert_eq!(stored.keys.len(), MAX_STORED_GHOSTS);
+    }
+
+    #[test]
+    fn test_beats_best() {
+        assert!(run(10.0).beats(None));
+        assert!(run(9.0).beats(Some(&run(10.0))));
+        assert!(!run(10.0).beats(Some(&run(10.0))));
+    }
+
+    #[test]
+    fn test_ghost_key_includes_slot_and_seed() {
+        assert_ne!(
+            ghost_key("Save 1", Season::Winter, GameLevel(2), 1),
+            ghost_key("Save 1", Season::Winter, GameLevel(2), 2)
+        );
+        assert_ne!(
+            ghost_key("Save 1", Season::Winter, GameLevel(2), 1),
+            ghost_key("Save 2", Season::Winter, GameLevel(2), 1)
+        );
+    }
+}
@@ -1,11 +1,10 @@
 use bevy::prelude::*;
 use leafwing_input_manager::prelude::*;
 
-use super::character::Character;
+use super::character::{Character, Direction};
 use super::controls::Action;
-use super::level::BucketContent;
-use super::level::PlayerSpawnPoint;
 use super::level::objects::{Container, Fire, Snow, Water};
+use super::level::{BucketContent, DeathCause, PlayerDeathMessage};
 use crate::PausableSystems;
 use crate::screens::Screen;
 
@@ -14,14 +13,13 @@ use crate::screens::Screen;
 pub struct LevelCompleteMessage;
 
 pub fn plugin(app: &mut App) {
+    app.register_type::<Interactable>();
     app.add_message::<LevelCompleteMessage>();
+    app.init_resource::<InteractionTarget>();
     app.add_systems(
         Update,
         (
-            interact_with_water,
-            interact_with_container,
-            interact_with_fire,
-            interact_with_snow,
+            (find_interaction_target, dispatch_interaction).chain(),
             touch_active_fire,
         )
             .in_set(PausableSystems)
@@ -31,196 +29,154 @@ pub fn plugin(app: &mut App) {
 
 const INTERACTION_RANGE: f32 = 64.0; // How close player needs to be to interact
 
-/// System to handle picking up water
-fn interact_with_water(
-    action_query: Query<&ActionState<Action>>,
-    character_query: Query<&Transform, With<Character>>,
-    water_query: Query<&Transform, With<Water>>,
-    mut bucket_content: ResMut<BucketContent>,
-) {
-    let Ok(action_state) = action_query.single() else {
-        return;
-    };
+/// Horizontal distance within which a target counts as in front of the character
+/// regardless of which way they are facing
+const FACING_TOLERANCE: f32 = 16.0;
 
-    // Only check when Use is just pressed
-    if !action_state.just_pressed(&Action::Use) {
-        return;
-    }
-
-    let Ok(character_transform) = character_query.single() else {
-        return;
-    };
-
-    let character_pos = character_transform.translation;
-
-    // Check if there's a water source nearby
-    for water_transform in &water_query {
-        let water_pos = water_transform.translation;
-        let distance = character_pos.distance(water_pos);
+/// Component for objects the character can interact with using [`Action::Use`]
+#[derive(Component, Debug, Clone, Copy, Reflect)]
+#[reflect(Component)]
+pub struct Interactable {
+    /// Higher priority targets are chosen over nearer, lower priority ones
+    pub priority: u8,
+}
 
-        if distance <= INTERACTION_RANGE {
-            // Fill bucket with water
-            *bucket_content = BucketContent::Water;
-            info!("Picked up water!");
-            return; // Only interact with one water source at a time
-        }
+impl Interactable {
+    pub fn new(priority: u8) -> Self {
+        Self { priority }
     }
 }
 
-/// System to handle pouring water into containers
-fn interact_with_container(
-    action_query: Query<&ActionState<Action>>,
-    character_query: Query<&Transform, With<Character>>,
-    mut container_query: Query<(&Transform, &mut Container)>,
-    mut bucket_content: ResMut<BucketContent>,
-    mut level_complete_writer: MessageWriter<LevelCompleteMessage>,
-) {
-    let Ok(action_state) = action_query.single() else {
-        return;
-    };
+/// The kinds of interaction the character can perform on an [`Interactable`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+pub enum InteractionKind {
+    /// Fill the bucket from a water source
+    FillBucket,
+    /// Scoop snow into an empty bucket
+    ScoopSnow,
+    /// Pour water from the bucket into a container
+    Pour,
+    /// Melt snow in the bucket at an active fire
+    MeltSnow,
+    /// Put out an active fire with water from the bucket
+    Extinguish,
+}
 
-    // Only check when Use is just pressed
-    if !action_state.just_pressed(&Action::Use) {
-        return;
-    };
+/// Event triggered on an [`Interactable`] when the character interacts with it
+#[derive(EntityEvent, Debug, Clone, Copy)]
+pub struct Interact {
+    pub entity: Entity,
+    pub kind: InteractionKind,
+}
 
-    // Can only pour if bucket has water
-    if *bucket_content != BucketContent::Water {
-        return;
+/// Resource holding the best interaction available to the character this frame
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq)]
+pub struct InteractionTarget(pub Option<(Entity, InteractionKind)>);
+
+/// Returns the interaction an object offers for the given bucket content, if any
+pub fn available_interaction(
+    bucket_content: BucketContent,
+    is_water: bool,
+    is_snow: bool,
+    fire: Option<&Fire>,
+    container: Option<&Container>,
+) -> Option<InteractionKind> {
+    if let Some(container) = container {
+        return (bucket_content == BucketContent::Water && !container.is_full())
+            .then_some(InteractionKind::Pour);
     }
-
-    let Ok(character_transform) = character_query.single() else {
-        return;
-    };
-
-    let character_pos = character_transform.translation;
-
-    // Check if there's a container nearby
-    for (container_transform, mut container) in &mut container_query {
-        let container_pos = container_transform.translation;
-        let distance = character_pos.distance(container_pos);
-
-        if distance <= INTERACTION_RANGE {
-            // Pour water into container
-            container.fill();
-            *bucket_content = BucketContent::Empty;
-            info!(
-                "Poured water into container! Container is now {:?}",
-                container.state
-            );
-
-            // Check if container is full (level complete!)
-            if container.is_full() {
-                info!("Container is full! Level complete!");
-                level_complete_writer.write(LevelCompleteMessage);
-            }
-
-            return; // Only interact with one container at a time
+    if let Some(fire) = fire {
+        if !fire.is_active() {
+            return None;
         }
+        return match bucket_content {
+            BucketContent::Water => Some(InteractionKind::Extinguish),
+            BucketContent::Snow => Some(InteractionKind::MeltSnow),
+            BucketContent::Empty => None,
+        };
+    }
+    if is_water {
+        return (bucket_content != BucketContent::Water).then_some(InteractionKind::FillBucket);
     }
+    if is_snow {
+        return (bucket_content == BucketContent::Empty).then_some(InteractionKind::ScoopSnow);
+    }
+    None
 }
 
-/// System to handle interacting with fires (extinguish or melt snow)
-fn interact_with_fire(
-    action_query: Query<&ActionState<Action>>,
-    character_query: Query<&Transform, With<Character>>,
-    mut fire_query: Query<(&Transform, &mut Fire)>,
-    mut bucket_content: ResMut<BucketContent>,
+/// System to pick the best interaction target in front of the character
+fn find_interaction_target(
+    character_query: Query<(&Transform, &Direction), With<Character>>,
+    interactable_query: Query<(
+        Entity,
+        &Transform,
+        &Interactable,
+        Has<Water>,
+        Has<Snow>,
+        Option<&Fire>,
+        Option<&Container>,
+    )>,
+    bucket_content: Res<BucketContent>,
+    mut target: ResMut<InteractionTarget>,
 ) {
-    let Ok(action_state) = action_query.single() else {
-        return;
-    };
-
-    // Only check when Use is just pressed
-    if !action_state.just_pressed(&Action::Use) {
-        return;
-    }
-
-    let Ok(character_transform) = character_query.single() else {
+    let Ok((character_transform, direction)) = character_query.single() else {
+        target.set_if_neq(InteractionTarget(None));
         return;
     };
 
     let character_pos = character_transform.translation;
+    let facing = match direction {
+        Direction::Left => -1.0,
+        Direction::Right => 1.0,
+    };
 
-    // Check if there's a fire nearby
-    for (fire_transform, mut fire) in &mut fire_query {
-        let fire_pos = fire_transform.translation;
-        let distance = character_pos.distance(fire_pos);
-
-        if distance <= INTERACTION_RANGE {
-            // Handle different bucket contents
-            match *bucket_content {
-                BucketContent::Water => {
-                    // Extinguish fire with water
-                    if fire.is_active() {
-                        fire.extinguish();
-                        *bucket_content = BucketContent::Empty;
-                        info!("Extinguished fire!");
-                        return; // Only interact with one fire at a time
-                    }
+    let best = interactable_query
+        .iter()
+        .filter_map(
+            |(entity, transform, interactable, is_water, is_snow, fire, container)| {
+                let offset = transform.translation - character_pos;
+                let distance = offset.length();
+                if distance > INTERACTION_RANGE || offset.x * facing < -FACING_TOLERANCE {
+                    return None;
                 }
-                BucketContent::Snow => {
-                    // Melt snow to water at fire (only if fire is active)
-                    if fire.is_active() {
-                        *bucket_content = BucketContent::Water;
-                        info!("Melted snow into water!");
-                        return; // Only interact with one fire at a time
-                    }
-                }
-                BucketContent::Empty => {
-                    // Can't do anything with empty bucket
-                }
-            }
-        }
-    }
+                let kind =
+                    available_interaction(*bucket_content, is_water, is_snow, fire, container)?;
+                Some((entity, kind, interactable.priority, distance))
+            },
+        )
+        .max_by(|a, b| a.2.cmp(&b.2).then(b.3.total_cmp(&a.3)))
+        .map(|(entity, kind, _, _)| (entity, kind));
+
+    target.set_if_neq(InteractionTarget(best));
 }
 
-/// System to handle picking up snow
-fn interact_with_snow(
+/// System to interact with the current target when Use is pressed
+fn dispatch_interaction(
+    mut commands: Commands,
     action_query: Query<&ActionState<Action>>,
-    character_query: Query<&Transform, With<Character>>,
-    snow_query: Query<&Transform, With<Snow>>,
-    mut bucket_content: ResMut<BucketContent>,
+    target: Res<InteractionTarget>,
 ) {
     let Ok(action_state) = action_query.single() else {
         return;
     };
 
-    // Only check when Use is just pressed
+    // Only interact when Use is just pressed
     if !action_state.just_pressed(&Action::Use) {
         return;
     }
 
-    let Ok(character_transform) = character_query.single() else {
-        return;
-    };
-
-    let character_pos = character_transform.translation;
-
-    // Check if there's snow nearby
-    for snow_transform in &snow_query {
-        let snow_pos = snow_transform.translation;
-        let distance = character_pos.distance(snow_pos);
-
-        if distance <= INTERACTION_RANGE {
-            // Fill bucket with snow
-            if *bucket_content != BucketContent::Water {
-                *bucket_content = BucketContent::Snow;
-                info!("Picked up snow!");
-            }
-            return; // Only interact with one snow source at a time
-        }
+    if let Some((entity, kind)) = target.0 {
+        commands.trigger(Interact { entity, kind });
     }
 }
 
-/// System to handle player touching active fire (reset to spawn point)
-fn touch_active_fire(
-    mut fire_query: Query<(&Transform, &mut Fire), Without<Character>>,
-    spawn_point: Res<PlayerSpawnPoint>,
-    mut character_transform_query: Query<&mut Transform, With<Character>>,
-    mut bucket_content: ResMut<BucketContent>,
+/// System to handle player touching active fire
+pub(super) fn touch_active_fire(
+    fire_query: Query<(&Transform, &Fire), Without<Character>>,
+    character_query: Query<&Transform, With<Character>>,
+    mut death_writer: MessageWriter<PlayerDeathMessage>,
 ) {
-    let Ok(character_transform) = character_transform_query.single() else {
+    let Ok(character_transform) = character_query.single() else {
         return;
     };
 
@@ -239,21 +195,68 @@ fn touch_active_fire(
         const TOUCH_DISTANCE: f32 = 20.0;
 
         if distance <= TOUCH_DISTANCE {
-            // Reset player to spawn point
-            if let Ok(mut transform) = character_transform_query.single_mut() {
-                transform.translation = spawn_point.position;
-
-                // Reset all fires to Active state
-                for (_, mut fire) in &mut fire_query {
-                    fire.ignite();
-                }
-
-                // Reset bucket contents
-                *bucket_content = BucketContent::Empty;
-
-                info!("Player touched fire! Resetting to spawn point and level state.");
-            }
+            death_writer.write(PlayerDeathMessage {
+                cause: DeathCause::Fire,
+            });
             return; // Only handle one fire collision per frame
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::level::objects::{ContainerState, FireState};
+
+    #[test]
+    fn test_fire_interactions() {
+        let active = Fire::new(FireState::Active);
+        let extinguished = Fire::new(FireState::Extinguished);
+
+        assert_eq!(
+            available_interaction(BucketContent::Water, false, false, Some(&active), None),
+            Some(InteractionKind::Extinguish)
+        );
+        assert_eq!(
+            available_interaction(BucketContent::Snow, false, false, Some(&active), None),
+            Some(InteractionKind::MeltSnow)
+        );
+        assert_eq!(
+            available_interaction(BucketContent::Empty, false, false, Some(&active), None),
+            None
+        );
+        assert_eq!(
+            available_interaction(
+                BucketContent::Water,
+                false,
+                false,
+                Some(&extinguished),
+                None
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_container_and_source_interactions() {
+        let half_full = Container::new(ContainerState::HalfFull);
+        let full = Container::new(ContainerState::Full);
+
+        assert_eq!(
+            available_interaction(BucketContent::Water, false, false, None, Some(&half_full)),
+            Some(InteractionKind::Pour)
+        );
+        assert_eq!(
+            available_interaction(BucketContent::Water, false, false, None, Some(&full)),
+            None
+        );
+        assert_eq!(
+            available_interaction(BucketContent::Snow, true, false, None, None),
+            Some(InteractionKind::FillBucket)
+        );
+        assert_eq!(
+            available_interaction(BucketContent::Snow, false, true, None, None),
+            None
+        );
+    }
+}
@@ -0,0 +1,274 @@
+use bevy::prelude::*;
+
+use super::causality::BucketContent;
+use super::objects::{Checkpoint, Container, ContainerState, Fire, FireState, Snow, Water};
+use super::spawn::PlayerSpawnPoint;
+use crate::game::character::Character;
+use crate::game::physics::Velocity;
+use crate::{PausableSystems, screens::Screen};
+
+pub fn plugin(app: &mut App) {
+    app.add_message::<PlayerDeathMessage>();
+    app.init_resource::<ActiveCheckpoint>();
+    app.add_systems(OnEnter(Screen::Gameplay), clear_checkpoint);
+    app.add_systems(
+        Update,
+        (activate_checkpoints, respawn_player)
+            .chain()
+            .after(crate::game::physics::respawn_on_fall)
+            .after(crate::game::interactions::touch_active_fire)
+            .in_set(PausableSystems)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+}
+
+/// How close the character needs to be to activate a checkpoint
+const CHECKPOINT_RANGE: f32 = 32.0;
+
+/// What killed the player
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeathCause {
+    Fell,
+    Fire,
+}
+
+/// Message written when the player dies and needs to respawn
+#[derive(Message, Debug, Clone, Copy)]
+pub struct PlayerDeathMessage {
+    pub cause: DeathCause,
+}
+
+/// Puzzle state saved when a checkpoint is activated
+#[derive(Debug, Clone)]
+pub struct CheckpointSnapshot {
+    /// Where the player respawns
+    pub position: Vec3,
+    pub bucket_content: BucketContent,
+    pub fires: Vec<(Entity, FireState)>,
+    pub containers: Vec<(Entity, ContainerState)>,
+}
+
+/// Resource holding the most recently activated checkpoint, if any
+#[derive(Resource, Debug, Default)]
+pub struct ActiveCheckpoint(pub Option<CheckpointSnapshot>);
+
+/// The resources left in a level that can still produce water
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PuzzleResources {
+    pub has_water_source: bool,
+    pub has_snow_source: bool,
+    pub active_fires: usize,
+}
+
+/// Returns true if the goal can still be reached from a puzzle state
+///
+/// Water and snow sources are infinite, so the only way to get stuck is to run out
+/// of fires to melt snow at when there is no water source.
+pub fn is_winnable(
+    bucket_content: BucketContent,
+    containers: &[ContainerState],
+    resources: PuzzleResources,
+) -> bool {
+    if containers.contains(&ContainerState::Full) {
+        return true;
+    }
+
+    let can_get_snow = resources.has_snow_source || bucket_content == BucketContent::Snow;
+    bucket_content == BucketContent::Water
+        || resources.has_water_source
+        || (can_get_snow && resources.active_fires > 0)
+}
+
+impl CheckpointSnapshot {
+    /// Returns true if restoring this snapshot leaves the level winnable
+    pub fn is_consistent(&self, has_water_source: bool, has_snow_source: bool) -> bool {
+        let resources = PuzzleResources {
+            has_water_source,
+            has_snow_source,
+            active_fires: self
+                .fires
+                .iter()
+                .filter(|(_, state)| *state == FireState::Active)
+                .count(),
+        };
+        let containers: Vec<_> = self.containers.iter().map(|(_, state)| *state).collect();
+        is_winnable(self.bucket_content, &containers, resources)
+    }
+}
+
+fn clear_checkpoint(mut active_checkpoint: ResMut<ActiveCheckpoint>) {
+    active_checkpoint.0 = None;
+}
+
+/// System to activate checkpoints the character touches, saving the puzzle state
+///
+/// `refused` is the checkpoint that last couldn't be saved, so it's only logged once each
+/// time the character reaches it.
+fn activate_checkpoints(
+    character_query: Query<&Transform, With<Character>>,
+    mut checkpoint_query: Query<(Entity, &Transform, &mut Checkpoint), Without<Character>>,
+    fire_query: Query<(Entity, &Fire)>,
+    container_query: Query<(Entity, &Container)>,
+    water_query: Query<(), With<Water>>,
+    snow_query: Query<(), With<Snow>>,
+    bucket_content: Res<BucketContent>,
+    mut active_checkpoint: ResMut<ActiveCheckpoint>,
+    mut refused: Local<Option<Entity>>,
+) {
+    let Ok(character_transform) = character_query.single() else {
+        return;
+    };
+
+    for (entity, checkpoint_transform, mut checkpoint) in &mut checkpoint_query {
+        let in_range = character_transform
+            .translation
+            .truncate()
+            .distance(checkpoint_transform.translation.truncate())
+            <= CHECKPOINT_RANGE;
+        if !in_range && *refused == Some(entity) {
+            *refused = None;
+        }
+        if checkpoint.activated || !in_range {
+            continue;
+        }
+
+        let snapshot = CheckpointSnapshot {
+            position: character_transform.translation,
+            bucket_content: *bucket_content,
+            fires: fire_query
+                .iter()
+                .map(|(entity, fire)| (entity, fire.state))
+                .collect(),
+            containers: container_query
+                .iter()
+                .map(|(entity, container)| (entity, container.state))
+                .collect(),
+        };
+
+        if !snapshot.is_consistent(!water_query.is_empty(), !snow_query.is_empty()) {
+            if *refused != Some(entity) {
+                info!("Checkpoint not saved: the puzzle can't be finished from here");
+                *refused = Some(entity);
+            }
+            continue;
+        }
+
+        checkpoint.activated = true;
+        active_checkpoint.0 = Some(snapshot);
+        info!("Checkpoint activated!");
+    }
+}
+
+/// System to respawn the player at the last checkpoint, or the spawn point if there is none
+pub fn respawn_player(
+    mut death_reader: MessageReader<PlayerDeathMessage>,
+    active_checkpoint: Res<ActiveCheckpoint>,
+    spawn_point: Res<PlayerSpawnPoint>,
+    mut character_query: Query<(&mut Transform, &mut Velocity), With<Character>>,
+    mut fire_query: Query<(Entity, &mut Fire)>,
+    mut container_query: Query<(Entity, &mut Container)>,
+    water_query: Query<(), With<Water>>,
+    snow_query: Query<(), With<Snow>>,
+    mut bucket_content: ResMut<BucketContent>,
+) {
+    let Some(death) = death_reader.read().last() else {
+        return;
+    };
+
+    let Ok((mut transform, mut velocity)) = character_query.single_mut() else {
+        return;
+    };
+
+    // Reset velocity to prevent continued falling
+    velocity.x = 0.0;
+    velocity.y = 0.0;
+
+    let snapshot = active_checkpoint
+        .0
+        .as_ref()
+        .filter(|snapshot| snapshot.is_consistent(!water_query.is_empty(), !snow_query.is_empty()));
+
+    if let Some(snapshot) = snapshot {
+        transform.translation = snapshot.position;
+
+        for (entity, mut fire) in &mut fire_query {
+            if let Some((_, state)) = snapshot.fires.iter().find(|(e, _)| *e == entity)
+                && fire.state != *state
+            {
+                fire.state = *state;
+            }
+        }
+        for (entity, mut container) in &mut container_query {
+            if let Some((_, state)) = snapshot.containers.iter().find(|(e, _)| *e == entity)
+                && container.state != *state
+            {
+                container.state = *state;
+            }
+        }
+        *bucket_content = snapshot.bucket_content;
+
+        info!("Player died ({:?})! Restored last checkpoint.", death.cause);
+    } else {
+        // Reset to spawn position
+        transform.translation = spawn_point.position;
+
+        // Reset all fires to Active state
+        for (_, mut fire) in &mut fire_query {
+            fire.ignite();
+        }
+
+        // Reset bucket contents
+        *bucket_content = BucketContent::Empty;
+
+        info!(
+            "Player died ({:?})! Respawned and reset level state.",
+            death.cause
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_winnable_with_water_source() {
+        let resources = PuzzleResources {
+            has_water_source: true,
+            ..default()
+        };
+        assert!(is_winnable(
+            BucketContent::Empty,
+            &[ContainerState::Empty],
+            resources
+        ));
+    }
+
+    #[test]
+    fn test_unwinnable_without_fire_to_melt_snow() {
+        let resources = PuzzleResources {
+            has_snow_source: true,
+            active_fires: 0,
+            ..default()
+        };
+        assert!(!is_winnable(
+            BucketContent::Snow,
+            &[ContainerState::HalfFull],
+            resources
+        ));
+
+        // Water already in the bucket can still finish a half full container...
+        assert!(is_winnable(
+            BucketContent::Water,
+            &[ContainerState::HalfFull],
+            resources
+        ));
+
+        // ...and a full container is always fine
+        assert!(is_winnable(
+            BucketContent::Empty,
+            &[ContainerState::Full],
+            resources
+        ));
+    }
+}
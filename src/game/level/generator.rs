@@ -315,8 +315,56 @@ impl CausalityGenerator {
             }
         }
 
+        for node_id in self.checkpoint_locations(chain, graph) {
+            let node = graph
+                .get_node_mut(node_id)
+                .ok_or_else(|| format!("Node {:?} not found in graph", node_id))?;
+            if !node.terrain_objects.contains(&SmartTerrain::Checkpoint) {
+                node.add_terrain(SmartTerrain::Checkpoint);
+            }
+        }
+
         Ok(())
     }
+
+    /// Returns the locations of puzzle milestones that should get a checkpoint
+    ///
+    /// Milestones are the first fill of the goal container and extinguishing a
+    /// blocking fire. A blocking fire's checkpoint goes on the first platform past it
+    /// that isn't the start or goal, so it can't be touched until the fire is out.
+    /// Easy levels are short enough not to need checkpoints.
+    pub fn checkpoint_locations(
+        &self,
+        chain: &CausalityChain,
+        graph: &PlatformGraph,
+    ) -> Vec<NodeId> {
+        if self.config.difficulty == Difficulty::Easy {
+            return vec![];
+        }
+
+        let mut locations = vec![];
+        for node in chain.forward_order() {
+            let location = if matches!(node.terrain, SmartTerrain::BlockingFire { .. }) {
+                // Platforms the player can reach before the fire is out
+                let player_side = graph.reachable_without(graph.start, node.location);
+                graph.get_node(node.location).and_then(|fire_node| {
+                    fire_node.edges.iter().map(|edge| edge.to).find(|to| {
+                        *to != graph.start && *to != graph.goal && !player_side.contains(to)
+                    })
+                })
+            } else if matches!(node.effect, Effect::ContainerFilled(0)) {
+                Some(node.location)
+            } else {
+                None
+            };
+            if let Some(location) = location
+                && !locations.contains(&location)
+            {
+                locations.push(location);
+            }
+        }
+        locations
+    }
 }
 
 #[cfg(test)]
@@ -389,5 +437,88 @@ mod tests {
         // Check that terrain was placed
         let has_terrain = graph.nodes.iter().any(|n| !n.terrain_objects.is_empty());
         assert!(has_terrain, "No terrain objects were placed");
+
+        // Easy levels don't get checkpoints
+        let has_checkpoint = graph
+            .nodes
+            .iter()
+            .any(|n| n.terrain_objects.contains(&SmartTerrain::Checkpoint));
+        assert!(!has_checkpoint, "Easy level should not have checkpoints");
+    }
+
+    #[test]
+    fn test_checkpoint_after_first_fill() {
+        let mut graph = create_simple_graph();
+        let config = GeneratorConfig {
+            difficulty: Difficulty::Hard,
+            seed: 42,
+            season: Season::Autumn,
+            completed_year: false,
+        };
+
+        let mut generator = CausalityGenerator::new(config);
+        let chain = generator.generate_chain(&graph).unwrap();
+        generator.apply_chain_to_graph(&chain, &mut graph).unwrap();
+
+        let goal = graph.get_node(graph.goal).unwrap();
+        let checkpoints = goal
+            .terrain_objects
+            .iter()
+            .filter(|t| **t == SmartTerrain::Checkpoint)
+            .count();
+        assert_eq!(checkpoints, 1, "Goal should have exactly one checkpoint");
+    }
+
+    #[test]
+    fn test_checkpoint_past_blocking_fire() {
+        // The fire is between the start and goal, with a platform on each side of it. The
+        // fire's edges back towards the player come first.
+        let mut graph = PlatformGraph::new(NodeId(0), NodeId(3));
+        let ids: Vec<_> = (0..5)
+            .map(|_| graph.add_node(PlatformNode::new()))
+            .collect();
+        let jump = ConnectionType::Jump {
+            direction: LayoutDirection::Right,
+        };
+        for (from, to) in [(0, 4), (0, 1), (4, 1), (1, 2), (2, 3)] {
+            graph
+                .get_node_mut(ids[from])
+                .unwrap()
+                .add_edge(ids[to], jump);
+            graph
+                .get_node_mut(ids[to])
+                .unwrap()
+                .add_edge(ids[from], jump);
+        }
+        let config = GeneratorConfig {
+            difficulty: Difficulty::Medium,
+            seed: 42,
+            season: Season::Autumn,
+            completed_year: false,
+        };
+
+        let mut chain = CausalityChain::new(Effect::ContainerFilled(1));
+        chain.add_node(CausalityNode {
+            effect: Effect::WaterBucket,
+            cause: Cause::BucketAt {
+                content: BucketContent::Water,
+                location: NodeId(1),
+            },
+            terrain: SmartTerrain::BlockingFire {
+                extinguished: false,
+            },
+            location: NodeId(1),
+        });
+
+        let generator = CausalityGenerator::new(config);
+        assert_eq!(
+            generator.checkpoint_locations(&chain, &graph),
+            vec![NodeId(2)],
+            "Checkpoint should be on the platform past the fire"
+        );
+
+        // With nothing between the fire and the goal, there's nowhere for the checkpoint
+        graph.goal = NodeId(2);
+        assert!(generator.checkpoint_locations(&chain, &graph).is_empty());
     }
 }
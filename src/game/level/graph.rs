@@ -222,6 +222,8 @@ pub enum SmartTerrain {
     Switch { activated: bool, activates: NodeId },
     /// A moving platform
     MovingPlatform { active: bool },
+    /// A checkpoint that saves puzzle progress when touched
+    Checkpoint,
 }
 
 /// The complete platform graph for a level
@@ -257,12 +259,21 @@ impl PlatformGraph {
 
     /// Returns all nodes reachable from the given node
     pub fn reachable_from(&self, node: NodeId) -> Vec<NodeId> {
+        self.reachable(node, None)
+    }
+
+    /// Returns all nodes reachable from the given node without passing through `blocked`
+    pub fn reachable_without(&self, node: NodeId, blocked: NodeId) -> Vec<NodeId> {
+        self.reachable(node, Some(blocked))
+    }
+
+    fn reachable(&self, node: NodeId, blocked: Option<NodeId>) -> Vec<NodeId> {
         let mut reachable = Vec::new();
         let mut visited = vec![false; self.nodes.len()];
         let mut stack = vec![node];
 
         while let Some(current) = stack.pop() {
-            if visited[current.0] {
+            if visited[current.0] || blocked == Some(current) {
                 continue;
             }
             visited[current.0] = true;
@@ -3,15 +3,18 @@
 use bevy::prelude::*;
 
 use super::interactions::LevelCompleteMessage;
-use super::parallax::{parallax_background, scroll_parallax};
+use super::parallax::{parallax_background, resize_parallax, scroll_parallax};
+use super::replay::{PlayedLevel, ReplayPlayback};
 use super::tiles::{GridPosition, TerrainTile};
 use crate::{
     PausableSystems,
     game::{CompletedYear, GameLevel, Season},
+    pixel_camera::PixelCanvas,
     screens::Screen,
 };
 
 mod causality;
+mod checkpoint;
 mod example;
 mod generator;
 mod graph;
@@ -20,13 +23,17 @@ mod spawn;
 mod templates;
 
 pub use causality::{BucketContent, CausalityChain, CausalityNode, Cause, Effect};
+pub use checkpoint::{ActiveCheckpoint, DeathCause, PlayerDeathMessage, respawn_player};
 pub use example::generate_example_level;
 pub use generator::{CausalityGenerator, Difficulty, GeneratorConfig};
 pub use graph::{
     ConnectionType, Edge, GROUND_LEVEL, NodeId, PlatformGraph, PlatformLayout, PlatformNode,
     PlatformType, SmartTerrain, WALL_HEIGHT,
 };
-pub use spawn::{PlayerSpawnPoint, spawn_level_from_graph, update_player_spawn_point};
+pub use spawn::{
+    LevelBounds, PlayerSpawnPoint, spawn_level_from_graph, update_level_bounds,
+    update_player_spawn_point,
+};
 pub use templates::{
     create_branching_template, create_cul_de_sac_template, create_ground_and_floating_template,
     create_linear_template, create_zigzag_template, merge_graphs,
@@ -34,50 +41,69 @@ pub use templates::{
 
 pub fn plugin(app: &mut App) {
     app.init_resource::<PlayerSpawnPoint>();
+    app.init_resource::<LevelBounds>();
     app.init_resource::<BucketContent>();
-    app.add_plugins(objects::plugin);
+    app.init_resource::<LevelSeed>();
+    app.add_plugins((objects::plugin, checkpoint::plugin));
     app.add_systems(OnEnter(Screen::Gameplay), spawn_level);
     app.add_systems(
         Update,
-        (scroll_parallax, handle_level_complete)
+        (scroll_parallax, resize_parallax, handle_level_complete)
             .in_set(PausableSystems)
             .run_if(in_state(Screen::Gameplay)),
     );
 }
 
+/// Resource holding the seed the current level was generated from
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct LevelSeed(pub u64);
+
 pub fn spawn_level(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
-    season: Res<Season>,
-    completed_year: Res<CompletedYear>,
-    game_level: Res<GameLevel>,
+    canvas: Res<PixelCanvas>,
+    played: PlayedLevel,
     mut spawn_point: ResMut<PlayerSpawnPoint>,
+    mut level_bounds: ResMut<LevelBounds>,
+    mut level_seed: ResMut<LevelSeed>,
 ) {
+    let season = played.season();
+    let completed_year = played.completed_year();
+    let game_level = played.game_level();
+
     commands.spawn((
         Name::new("Level"),
         Transform::default(),
         Visibility::default(),
         DespawnOnExit(Screen::Gameplay),
-        children![parallax_background(*season, asset_server.clone())],
+        children![parallax_background(
+            season,
+            canvas.width,
+            asset_server.clone()
+        )],
     ));
 
     // Use season and level to create unique seed
     // If year is completed, add randomness for variety on subsequent playthroughs
-    let seed = if completed_year.0 {
+    let seed = if let Some(seed) = played.replay_seed() {
+        seed
+    } else if completed_year.0 {
         use rand::Rng;
-        let base_seed = (*season as u64) * 100 + game_level.0 as u64;
+        let base_seed = (season as u64) * 100 + game_level.0 as u64;
         let random_offset = rand::rng().random_range(0..1000000);
         base_seed.wrapping_add(random_offset)
     } else {
-        (*season as u64) * 1100 + game_level.0 as u64
+        (season as u64) * 1100 + game_level.0 as u64
     };
 
+    level_seed.0 = seed;
+
     // Calculate difficulty based on season and completion
     let difficulty = if completed_year.0 {
         // After completing a year, stay on Hard
         Difficulty::Hard
     } else {
-        match *season {
+        match season {
             Season::Summer => Difficulty::Easy,
             Season::Autumn => Difficulty::Medium,
             Season::Winter => Difficulty::Medium,
@@ -98,7 +124,7 @@ pub fn spawn_level(
             Difficulty::Easy => {
                 // First level (Summer level 1) always uses linear template
                 // All other easy levels use varied templates
-                let is_first_level = *season == Season::Summer && game_level.0 == 1;
+                let is_first_level = season == Season::Summer && game_level.0 == 1;
 
                 if is_first_level {
                     create_linear_template(Some(attempt_seed))
@@ -161,7 +187,7 @@ pub fn spawn_level(
         let config = GeneratorConfig {
             difficulty,
             seed: attempt_seed,
-            season: *season,
+            season,
             completed_year: completed_year.0,
         };
 
@@ -176,9 +202,10 @@ pub fn spawn_level(
 
             // Update spawn point based on generated level
             update_player_spawn_point(&graph, &layouts, &mut spawn_point);
+            update_level_bounds(&layouts, &mut level_bounds);
 
             // Spawn entire level in one pass
-            spawn_level_from_graph(&mut commands, &asset_server, &graph, &layouts, *season);
+            spawn_level_from_graph(&mut commands, &asset_server, &graph, &layouts, season);
 
             info!(
                 "Successfully generated procedural level (attempt {})",
@@ -203,15 +230,24 @@ pub fn spawn_level(
     let graph = create_linear_template(Some(seed));
     let layouts = graph.generate_layout(seed);
     update_player_spawn_point(&graph, &layouts, &mut spawn_point);
-    spawn_level_from_graph(&mut commands, &asset_server, &graph, &layouts, *season);
+    update_level_bounds(&layouts, &mut level_bounds);
+    spawn_level_from_graph(&mut commands, &asset_server, &graph, &layouts, season);
 }
 
 /// System to handle level completion and transition to victory screen
 fn handle_level_complete(
     mut level_complete_reader: MessageReader<LevelCompleteMessage>,
+    playback: Option<Res<ReplayPlayback>>,
     mut next_state: ResMut<NextState<Screen>>,
 ) {
     for _ in level_complete_reader.read() {
+        if playback.is_some() {
+            // Replays don't count towards progress
+            info!("Replay finished the level! Returning to title");
+            next_state.set(Screen::Title);
+            continue;
+        }
+
         info!("Level complete! Showing victory screen");
 
         // Transition to Victory screen
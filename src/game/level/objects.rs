@@ -2,9 +2,18 @@
 
 use bevy::{math::VectorSpace, prelude::*};
 
+use super::super::interactions::{Interact, Interactable, InteractionKind, LevelCompleteMessage};
+use super::super::sound::{GameSound, SoundEffectMessage};
 use super::super::tiles::GridPosition;
+use super::causality::BucketContent;
 use crate::{PausableSystems, screens::Screen};
 
+/// Interaction priorities, so the most specific object wins when several are in range
+const CONTAINER_PRIORITY: u8 = 3;
+const FIRE_PRIORITY: u8 = 2;
+const SNOW_PRIORITY: u8 = 1;
+const WATER_PRIORITY: u8 = 0;
+
 pub fn plugin(app: &mut App) {
     app.register_type::<Fire>();
     app.register_type::<FireState>();
@@ -16,6 +25,12 @@ pub fn plugin(app: &mut App) {
     app.register_type::<WaterAnimation>();
     app.register_type::<Container>();
     app.register_type::<ContainerState>();
+    app.register_type::<Checkpoint>();
+
+    app.add_observer(interact_with_water);
+    app.add_observer(interact_with_snow);
+    app.add_observer(interact_with_fire);
+    app.add_observer(interact_with_container);
 
     app.add_systems(
         Update,
@@ -26,6 +41,7 @@ pub fn plugin(app: &mut App) {
             update_water_animation,
             sync_water_animation,
             update_container_state,
+            update_checkpoint_state,
         )
             .in_set(PausableSystems)
             .run_if(in_state(Screen::Gameplay)),
@@ -130,6 +146,7 @@ pub fn spawn_fire(
     let mut entity_commands = commands.spawn((
         Name::new("Fire"),
         Fire::new(state),
+        Interactable::new(FIRE_PRIORITY),
         grid_pos,
         Sprite {
             image: texture,
@@ -204,6 +221,37 @@ pub fn update_fire_state(
     }
 }
 
+/// Observer to extinguish fires or melt snow at them
+fn interact_with_fire(
+    interact: On<Interact>,
+    mut fire_query: Query<&mut Fire>,
+    mut bucket_content: ResMut<BucketContent>,
+    mut sound_writer: MessageWriter<SoundEffectMessage>,
+) {
+    let Ok(mut fire) = fire_query.get_mut(interact.entity) else {
+        return;
+    };
+
+    if !fire.is_active() {
+        return;
+    }
+
+    match interact.kind {
+        InteractionKind::Extinguish => {
+            fire.extinguish();
+            *bucket_content = BucketContent::Empty;
+            sound_writer.write(SoundEffectMessage::new(GameSound::Extinguish));
+            info!("Extinguished fire!");
+        }
+        InteractionKind::MeltSnow => {
+            *bucket_content = BucketContent::Water;
+            sound_writer.write(SoundEffectMessage::new(GameSound::Melt));
+            info!("Melted snow into water!");
+        }
+        _ => {}
+    }
+}
+
 /// Marker component for snow objects
 #[derive(Component, Debug, Reflect)]
 #[reflect(Component)]
@@ -219,6 +267,7 @@ pub fn spawn_snow(
         .spawn((
             Name::new("Snow"),
             Snow,
+            Interactable::new(SNOW_PRIORITY),
             grid_pos,
             Sprite {
                 image: asset_server.load("images/objects/snow.epng"),
@@ -232,6 +281,20 @@ pub fn spawn_snow(
         .id()
 }
 
+/// Observer to scoop snow into the bucket
+fn interact_with_snow(
+    interact: On<Interact>,
+    snow_query: Query<(), With<Snow>>,
+    mut bucket_content: ResMut<BucketContent>,
+    mut sound_writer: MessageWriter<SoundEffectMessage>,
+) {
+    if interact.kind == InteractionKind::ScoopSnow && snow_query.contains(interact.entity) {
+        *bucket_content = BucketContent::Snow;
+        sound_writer.write(SoundEffectMessage::new(GameSound::SnowScoop));
+        info!("Picked up snow!");
+    }
+}
+
 /// Marker component for platform objects
 #[derive(Component, Debug, Reflect)]
 #[reflect(Component)]
@@ -357,6 +420,7 @@ pub fn spawn_water(
             Name::new(format!("Water {:?}", water_type)),
             Water::new(water_type),
             WaterAnimation::new(water_type),
+            Interactable::new(WATER_PRIORITY),
             grid_pos,
             Sprite {
                 image: texture,
@@ -392,6 +456,20 @@ pub fn sync_water_animation(mut query: Query<(&WaterAnimation, &mut Sprite), Wit
     }
 }
 
+/// Observer to fill the bucket from a water source
+fn interact_with_water(
+    interact: On<Interact>,
+    water_query: Query<(), With<Water>>,
+    mut bucket_content: ResMut<BucketContent>,
+    mut sound_writer: MessageWriter<SoundEffectMessage>,
+) {
+    if interact.kind == InteractionKind::FillBucket && water_query.contains(interact.entity) {
+        *bucket_content = BucketContent::Water;
+        sound_writer.write(SoundEffectMessage::new(GameSound::BucketFill));
+        info!("Picked up water!");
+    }
+}
+
 /// Component representing a container with different fill states
 #[derive(Component, Debug, Reflect)]
 #[reflect(Component)]
@@ -468,6 +546,7 @@ pub fn spawn_container(
         .spawn((
             Name::new(format!("Container {:?}", state)),
             Container::new(state),
+            Interactable::new(CONTAINER_PRIORITY),
             grid_pos,
             Sprite {
                 image: texture,
@@ -499,3 +578,89 @@ pub fn update_container_state(
         });
     }
 }
+
+/// Observer to pour water from the bucket into a container
+fn interact_with_container(
+    interact: On<Interact>,
+    mut container_query: Query<&mut Container>,
+    mut bucket_content: ResMut<BucketContent>,
+    mut level_complete_writer: MessageWriter<LevelCompleteMessage>,
+    mut sound_writer: MessageWriter<SoundEffectMessage>,
+) {
+    if interact.kind != InteractionKind::Pour {
+        return;
+    }
+
+    let Ok(mut container) = container_query.get_mut(interact.entity) else {
+        return;
+    };
+
+    container.fill();
+    *bucket_content = BucketContent::Empty;
+    sound_writer.write(SoundEffectMessage::new(GameSound::Pour));
+    info!(
+        "Poured water into container! Container is now {:?}",
+        container.state
+    );
+
+    // Check if container is full (level complete!)
+    if container.is_full() {
+        info!("Container is full! Level complete!");
+        sound_writer.write(SoundEffectMessage::new(GameSound::ContainerFull));
+        level_complete_writer.write(LevelCompleteMessage);
+    }
+}
+
+/// Component representing a checkpoint that saves puzzle progress when touched
+#[derive(Component, Debug, Default, Reflect)]
+#[reflect(Component)]
+pub struct Checkpoint {
+    pub activated: bool,
+}
+
+/// Marker component for the flag of a checkpoint
+#[derive(Component, Debug, Reflect)]
+#[reflect(Component)]
+pub struct CheckpointFlag;
+
+const CHECKPOINT_FLAG_INACTIVE: Color = Color::srgb(0.55, 0.55, 0.55);
+const CHECKPOINT_FLAG_ACTIVE: Color = Color::srgb(0.3, 0.6, 0.95);
+
+/// Spawns a checkpoint flag at the specified position
+pub fn spawn_checkpoint(commands: &mut Commands, grid_pos: GridPosition) -> Entity {
+    commands
+        .spawn((
+            Name::new("Checkpoint"),
+            Checkpoint::default(),
+            grid_pos,
+            Sprite::from_color(Color::srgb(0.35, 0.25, 0.2), Vec2::new(2.0, 32.0)),
+            Transform::default(),
+            Visibility::default(),
+            DespawnOnExit(Screen::Gameplay),
+            children![(
+                Name::new("Checkpoint Flag"),
+                CheckpointFlag,
+                Sprite::from_color(CHECKPOINT_FLAG_INACTIVE, Vec2::new(12.0, 8.0)),
+                Transform::from_xyz(7.0, 12.0, 0.1),
+            )],
+        ))
+        .id()
+}
+
+/// System to recolour checkpoint flags when they are activated
+pub fn update_checkpoint_state(
+    checkpoint_query: Query<(&Checkpoint, &Children), Changed<Checkpoint>>,
+    mut flag_query: Query<&mut Sprite, With<CheckpointFlag>>,
+) {
+    for (checkpoint, children) in &checkpoint_query {
+        for child in children.iter() {
+            if let Ok(mut sprite) = flag_query.get_mut(child) {
+                sprite.color = if checkpoint.activated {
+                    CHECKPOINT_FLAG_ACTIVE
+                } else {
+                    CHECKPOINT_FLAG_INACTIVE
+                };
+            }
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use super::graph::{NodeId, PlatformGraph, PlatformLayout, PlatformNode, SmartTerrain};
 use super::objects::{
-    ContainerState, FireState, WaterType, spawn_container, spawn_fire, spawn_snow, spawn_water,
+    ContainerState, FireState, WaterType, spawn_checkpoint, spawn_container, spawn_fire,
+    spawn_snow, spawn_water,
 };
 use crate::game::tiles::{GridPosition, TILE_SIZE, TerrainTile};
 use crate::screens::Screen;
@@ -21,6 +22,31 @@ impl Default for PlayerSpawnPoint {
     }
 }
 
+/// Resource holding the world-space bounding box of the spawned platform layouts
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct LevelBounds {
+    /// Bottom-left corner in world coordinates
+    pub min: Vec2,
+    /// Top-right corner in world coordinates
+    pub max: Vec2,
+}
+
+impl Default for LevelBounds {
+    fn default() -> Self {
+        Self {
+            min: Vec2::new(0.0, super::graph::GROUND_LEVEL),
+            max: Vec2::new(TILE_SIZE * 20.0, TILE_SIZE * 10.0),
+        }
+    }
+}
+
+impl LevelBounds {
+    /// Get the center of the bounds in world coordinates
+    pub fn center(&self) -> Vec2 {
+        (self.min + self.max) / 2.0
+    }
+}
+
 /// Spawns a complete level from a platform graph and layout map
 pub fn spawn_level_from_graph(
     commands: &mut Commands,
@@ -273,6 +299,9 @@ fn spawn_other_terrain_objects(
                 // TODO: Implement moving platform
                 info!("Moving platform not yet implemented at grid {:?}", grid_pos);
             }
+            SmartTerrain::Checkpoint => {
+                spawn_checkpoint(commands, checkpoint_grid_pos(layout));
+            }
         }
     }
 }
@@ -433,7 +462,7 @@ fn spawn_start_platform(
 fn spawn_goal_platform(
     commands: &mut Commands,
     asset_server: &AssetServer,
-    _node: &PlatformNode,
+    node: &PlatformNode,
     layout: &PlatformLayout,
 ) {
     use super::graph::{GROUND_LEVEL, WALL_HEIGHT};
@@ -474,6 +503,11 @@ fn spawn_goal_platform(
         ContainerState::Empty,
     );
 
+    // Spawn checkpoint near the left edge, away from the container
+    if node.terrain_objects.contains(&SmartTerrain::Checkpoint) {
+        spawn_checkpoint(commands, checkpoint_grid_pos(layout));
+    }
+
     // Spawn ground support (dirt to GROUND_LEVEL)
     let ground_y_tiles = (GROUND_LEVEL / TILE_SIZE) as i32;
     let platform_bottom_y = platform_y_tiles;
@@ -507,6 +541,11 @@ fn spawn_goal_platform(
     }
 }
 
+/// Grid position for a checkpoint, near the left edge of a platform
+fn checkpoint_grid_pos(layout: &PlatformLayout) -> GridPosition {
+    GridPosition::primary(layout.grid_x, layout.grid_y + layout.height_tiles + 1)
+}
+
 /// Spawns a 3x5 blocking wall above a fire
 fn spawn_fire_wall(commands: &mut Commands, fire_grid_pos: GridPosition) {
     // Spawn 2-tile wide, 5-tile high wall above the fire
@@ -536,3 +575,31 @@ pub fn update_player_spawn_point(
         spawn_point.position = Vec3::new(spawn_x, start_layout.top_world() + TILE_SIZE * 4.0, 0.0);
     }
 }
+
+/// Updates the level bounds to enclose every platform layout
+///
+/// The bottom edge never rises above `GROUND_LEVEL`, since grounded platforms
+/// extend their dirt down to it. The top edge leaves room for the boundary walls.
+pub fn update_level_bounds(
+    layouts: &HashMap<NodeId, PlatformLayout>,
+    level_bounds: &mut LevelBounds,
+) {
+    use super::graph::{GROUND_LEVEL, WALL_HEIGHT};
+
+    let mut layouts = layouts.values();
+    let Some(first) = layouts.next() else {
+        return;
+    };
+
+    let mut min = Vec2::new(first.left_edge_world(), first.bottom_world());
+    let mut max = Vec2::new(first.right_edge_world(), first.top_world());
+    for layout in layouts {
+        min = min.min(Vec2::new(layout.left_edge_world(), layout.bottom_world()));
+        max = max.max(Vec2::new(layout.right_edge_world(), layout.top_world()));
+    }
+
+    min.y = min.y.min(GROUND_LEVEL);
+    max.y += WALL_HEIGHT as f32 * TILE_SIZE;
+
+    *level_bounds = LevelBounds { min, max };
+}
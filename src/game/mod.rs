@@ -1,16 +1,28 @@
 use bevy::prelude::*;
-use bevy_pkv::prelude::*;
 use serde::{Deserialize, Serialize};
 use strum::Display;
 
+mod camera;
 pub mod character;
+pub mod composite;
 pub mod controls;
+pub mod ghost;
 mod interactions;
 pub mod level;
+pub mod outfits;
 mod parallax;
 mod physics;
+pub mod replay;
+pub mod rewind;
+pub mod save;
+pub mod share_code;
+pub mod sound;
 mod tiles;
+mod tint;
+mod touch;
 mod ui;
+pub mod unlocks;
+pub mod wardrobe;
 
 #[derive(
     Clone,
@@ -105,16 +117,26 @@ impl Season {
 }
 
 pub fn plugin(app: &mut App) {
-    app.insert_resource(PkvStore::new("tmstorey", "glass-half-full"));
-    app.init_persistent_resource::<PlayerLevel>();
-    app.init_persistent_resource::<GameLevel>();
-    app.init_persistent_resource::<CompletedYear>();
-    app.init_persistent_resource::<Season>();
+    app.init_resource::<PlayerLevel>();
+    app.init_resource::<GameLevel>();
+    app.init_resource::<CompletedYear>();
+    app.init_resource::<Season>();
     app.add_plugins(tiles::plugin);
     app.add_plugins(character::plugin);
+    app.add_plugins(composite::plugin);
     app.add_plugins(controls::plugin);
     app.add_plugins(physics::plugin);
+    app.add_plugins(camera::plugin);
     app.add_plugins(level::plugin);
     app.add_plugins(ui::plugin);
     app.add_plugins(interactions::plugin);
+    app.add_plugins(sound::plugin);
+    app.add_plugins(rewind::plugin);
+    app.add_plugins(replay::plugin);
+    app.add_plugins(ghost::plugin);
+    app.add_plugins(touch::plugin);
+    app.add_plugins(save::plugin);
+    app.add_plugins(unlocks::plugin);
+    app.add_plugins(wardrobe::plugin);
+    app.add_plugins(outfits::plugin);
 }
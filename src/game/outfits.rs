@@ -0,0 +1,291 @@
+//! Outfits: presets the player saves from character select, random outfits, and presets worn
+//! automatically when the season changes.
+//!
+//! Presets are shared by every save slot, so an outfit is only worn if the active slot has
+//! unlocked all of its items.
+
+use bevy::prelude::*;
+use bevy_pkv::prelude::*;
+use rand::Rng;
+use rand::seq::IndexedRandom;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::Season;
+use super::character::{CharacterLayer, CharacterLayers, LayerType};
+use super::replay::ReplayPlayback;
+use super::unlocks::UnlockedItems;
+use super::wardrobe::{Wardrobe, WardrobeCatalog};
+
+pub fn plugin(app: &mut App) {
+    app.init_persistent_resource::<OutfitPresets>();
+    app.add_systems(
+        Update,
+        wear_seasonal_outfit
+            .run_if(resource_changed::<Season>)
+            .run_if(not(resource_exists::<ReplayPlayback>)),
+    );
+}
+
+/// Layer types every outfit has an item for
+pub const REQUIRED_LAYER_TYPES: [LayerType; 2] = [LayerType::Body, LayerType::Underclothes];
+
+/// The longest name a preset can be given, in characters
+pub const MAX_PRESET_NAME_CHARS: usize = 24;
+
+/// Why a preset couldn't be renamed
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum RenameError {
+    #[error("An outfit needs a name")]
+    Empty,
+    #[error("There's already an outfit called {0}")]
+    Taken(String),
+    #[error("There's no outfit called {0}")]
+    Missing(String),
+}
+
+/// Resource holding the player's saved outfits, in the order they were saved
+#[derive(Resource, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OutfitPresets {
+    pub presets: Vec<OutfitPreset>,
+}
+
+/// A saved outfit
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutfitPreset {
+    pub name: String,
+    pub layers: CharacterLayers,
+    /// The season the outfit is worn in automatically, if any
+    #[serde(default)]
+    pub season: Option<Season>,
+}
+
+impl OutfitPreset {
+    /// Returns true if the active slot has unlocked every item in the outfit
+    pub fn is_wearable(&self, catalog: &WardrobeCatalog, unlocked: &UnlockedItems) -> bool {
+        self.layers
+            .layers
+            .iter()
+            .all(|layer| unlocked.is_unlocked(catalog, layer))
+    }
+}
+
+impl OutfitPresets {
+    pub fn get(&self, name: &str) -> Option<&OutfitPreset> {
+        self.presets.iter().find(|preset| preset.name == name)
+    }
+
+    /// Saves `layers` as a new preset, returning its name
+    pub fn save(&mut self, layers: &CharacterLayers) -> String {
+        let names: Vec<_> = self
+            .presets
+            .iter()
+            .map(|preset| preset.name.clone())
+            .collect();
+        let name = next_preset_name(&names);
+        let mut layers = layers.clone();
+        layers.layers.sort_by_key(|layer| layer.layer_type);
+        self.presets.push(OutfitPreset {
+            name: name.clone(),
+            layers,
+            season: None,
+        });
+        name
+    }
+
+    /// Renames the preset `name`, returning its new name without surrounding whitespace
+    pub fn rename(&mut self, name: &str, new_name: &str) -> Result<String, RenameError> {
+        let new_name = new_name.trim();
+        if new_name.is_empty() {
+            return Err(RenameError::Empty);
+        }
+        if new_name != name && self.get(new_name).is_some() {
+            return Err(RenameError::Taken(new_name.to_string()));
+        }
+        let preset = self
+            .presets
+            .iter_mut()
+            .find(|preset| preset.name == name)
+            .ok_or_else(|| RenameError::Missing(name.to_string()))?;
+        preset.name = new_name.to_string();
+        Ok(preset.name.clone())
+    }
+
+    pub fn delete(&mut self, name: &str) {
+        self.presets.retain(|preset| preset.name != name);
+    }
+
+    /// Moves the preset on to being worn in the next season, or no season after Spring
+    ///
+    /// A season only has one outfit, so any other preset worn in the new season is cleared.
+    pub fn cycle_season(&mut self, name: &str) -> Option<Season> {
+        let preset = self.presets.iter_mut().find(|preset| preset.name == name)?;
+        let season = match preset.season {
+            None => Some(Season::Summer),
+            Some(Season::Spring) => None,
+            Some(season) => Some(season.next()),
+        };
+        preset.season = season;
+
+        if let Some(season) = season {
+            for other in &mut self.presets {
+                if other.name != name && other.season == Some(season) {
+                    other.season = None;
+                }
+            }
+        }
+        season
+    }
+
+    /// Returns the preset worn in `season`, if any
+    pub fn for_season(&self, season: Season) -> Option<&OutfitPreset> {
+        self.presets
+            .iter()
+            .find(|preset| preset.season == Some(season))
+    }
+}
+
+/// Returns the first "Outfit N" name that isn't taken
+pub fn next_preset_name(names: &[String]) -> String {
+    (1..)
+        .map(|i| format!("Outfit {i}"))
+        .find(|name| !names.contains(name))
+        .unwrap()
+}
+
+/// Picks a random unlocked item for each layer type
+///
+/// Body and underclothes always get an item, and every other layer type may be left empty.
+pub fn random_outfit(
+    catalog: &WardrobeCatalog,
+    unlocked: &UnlockedItems,
+    rng: &mut impl Rng,
+) -> CharacterLayers {
+    let mut layers = vec![];
+    for layer_type in LayerType::ALL {
+        let mut options: Vec<Option<CharacterLayer>> = catalog
+            .layers_of(layer_type)
+            .into_iter()
+            .filter(|layer| unlocked.is_unlocked(catalog, layer))
+            .map(Some)
+            .collect();
+        if !REQUIRED_LAYER_TYPES.contains(&layer_type) {
+            options.push(None);
+        }
+        if let Some(Some(layer)) = options.choose(rng) {
+            layers.push(layer.clone());
+        }
+    }
+    CharacterLayers { layers }
+}
+
+/// System to wear the current season's outfit, if one is set
+fn wear_seasonal_outfit(
+    season: Res<Season>,
+    presets: Res<OutfitPresets>,
+    wardrobe: Wardrobe,
+    unlocked: Res<UnlockedItems>,
+    mut character_layers: ResMut<CharacterLayers>,
+) {
+    let Some(preset) = presets.for_season(*season) else {
+        return;
+    };
+    let Some(catalog) = wardrobe.catalog() else {
+        return;
+    };
+    if !preset.is_wearable(catalog, &unlocked) {
+        info!(
+            "Not wearing {} for {}, as it isn't unlocked",
+            preset.name, *season
+        );
+        return;
+    }
+    if character_layers.layers != preset.layers.layers {
+        character_layers.layers = preset.layers.layers.clone();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::game::wardrobe::bundled_catalog;
+
+    #[test]
+    fn test_next_preset_name_fills_gaps() {
+        assert_eq!(next_preset_name(&[]), "Outfit 1");
+        let names = vec!["Outfit 1".to_string(), "Outfit 3".to_string()];
+        assert_eq!(next_preset_name(&names), "Outfit 2");
+    }
+
+    #[test]
+    fn test_rename_keeps_names_unique() {
+        let mut presets = OutfitPresets::default();
+        let first = presets.save(&CharacterLayers::default());
+        let second = presets.save(&CharacterLayers::default());
+
+        assert_eq!(
+            presets.rename(&first, "  Winter coat "),
+            Ok("Winter coat".to_string())
+        );
+        assert!(presets.get("Winter coat").is_some());
+        assert!(presets.get(&first).is_none());
+
+        assert_eq!(
+            presets.rename(&second, "Winter coat"),
+            Err(RenameError::Taken("Winter coat".to_string()))
+        );
+        assert_eq!(presets.rename(&second, "   "), Err(RenameError::Empty));
+        assert_eq!(
+            presets.rename(&first, "Anything"),
+            Err(RenameError::Missing(first.clone()))
+        );
+        // Renaming to the same name is allowed
+        assert_eq!(presets.rename(&second, &second), Ok(second.clone()));
+    }
+
+    #[test]
+    fn test_each_season_has_one_outfit() {
+        let mut presets = OutfitPresets::default();
+        let first = presets.save(&CharacterLayers::default());
+        let second = presets.save(&CharacterLayers::default());
+
+        assert_eq!(presets.cycle_season(&first), Some(Season::Summer));
+        assert_eq!(presets.cycle_season(&second), Some(Season::Summer));
+        assert_eq!(presets.get(&first).unwrap().season, None);
+        assert_eq!(presets.for_season(Season::Summer).unwrap().name, second);
+
+        for _ in 0..3 {
+            presets.cycle_season(&second);
+        }
+        assert_eq!(presets.cycle_season(&second), None);
+        assert!(presets.for_season(Season::Summer).is_none());
+    }
+
+    #[test]
+    fn test_random_outfit_follows_layer_rules() {
+        let catalog = bundled_catalog();
+        let unlocked = UnlockedItems::default();
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+
+        for _ in 0..50 {
+            let outfit = random_outfit(&catalog, &unlocked, &mut rng);
+            for layer_type in REQUIRED_LAYER_TYPES {
+                assert!(outfit.layers.iter().any(|l| l.layer_type == layer_type));
+            }
+            for layer_type in LayerType::ALL {
+                let count = outfit
+                    .layers
+                    .iter()
+                    .filter(|l| l.layer_type == layer_type)
+                    .count();
+                assert!(count <= 1, "{count} {layer_type} items");
+            }
+            for layer in &outfit.layers {
+                assert!(unlocked.is_unlocked(&catalog, layer), "{layer:?} is locked");
+                assert!(catalog.texture_path(layer).is_some());
+            }
+        }
+    }
+}
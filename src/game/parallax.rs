@@ -2,7 +2,10 @@ use bevy::image::{ImageLoaderSettings, ImageSampler};
 use bevy::prelude::*;
 
 use super::Season;
-use crate::pixel_camera::PixelCamera;
+use crate::pixel_camera::{PixelCamera, PixelCanvas};
+
+/// Width of one repetition of the background images
+const TILE_WIDTH: f32 = 1024.;
 
 #[derive(Component, Debug, Default, PartialEq, PartialOrd, Reflect)]
 #[reflect(Component)]
@@ -13,7 +16,19 @@ pub struct ParallaxLayer {
     pub scroll_factor: f32,
 }
 
-pub fn parallax_background(season: Season, asset_server: AssetServer) -> impl Bundle {
+/// Width of a parallax layer that covers a canvas of the given width at any scroll offset
+pub fn parallax_width(canvas_width: u32) -> f32 {
+    // The layer's center can trail the camera by up to one tile on either side.
+    // Keep the width a multiple of two tiles, so tiles line up with the layer's center
+    let tiles = (canvas_width as f32 / TILE_WIDTH).ceil() + 2.;
+    (tiles / 2.).ceil() * 2. * TILE_WIDTH
+}
+
+pub fn parallax_background(
+    season: Season,
+    canvas_width: u32,
+    asset_server: AssetServer,
+) -> impl Bundle {
     let mut children = vec![];
     let scroll_factors = [0.3, 0.5, 0.7, 0.95, 1.0];
     for layer in 1..=5 {
@@ -38,7 +53,7 @@ pub fn parallax_background(season: Season, asset_server: AssetServer) -> impl Bu
             Name::new(name),
             Sprite {
                 image,
-                custom_size: Some(Vec2::new(16_384., 346.)),
+                custom_size: Some(Vec2::new(parallax_width(canvas_width), 346.)),
                 image_mode: SpriteImageMode::Tiled {
                     tile_x: true,
                     tile_y: false,
@@ -66,8 +81,28 @@ pub fn scroll_parallax(
     };
 
     for (layer, mut transform) in &mut parallax_query {
-        transform.translation.x = camera_transform.translation.x * layer.scroll_factor;
-        transform.translation.x %= 1024.;
+        // Stay within one tile of the camera, offset so the layer appears to scroll
+        // at `scroll_factor` relative to the world
+        let camera_x = camera_transform.translation.x;
+        transform.translation.x =
+            camera_x - (camera_x * (1. - layer.scroll_factor)).rem_euclid(TILE_WIDTH);
         transform.translation.y = camera_transform.translation.y * layer.scroll_factor;
     }
 }
+
+/// Resizes parallax layers when the pixel canvas changes size
+pub fn resize_parallax(
+    canvas: Res<PixelCanvas>,
+    mut parallax_query: Query<&mut Sprite, With<ParallaxLayer>>,
+) {
+    if !canvas.is_changed() {
+        return;
+    }
+
+    let width = parallax_width(canvas.width);
+    for mut sprite in &mut parallax_query {
+        if let Some(size) = sprite.custom_size.as_mut() {
+            size.x = width;
+        }
+    }
+}
@@ -5,10 +5,10 @@ use super::character::{
     AnimationState, Character, CharacterAnimation, Direction, OneShotAnimation,
 };
 use super::controls::Action;
-use super::level::PlayerSpawnPoint;
+use super::level::{DeathCause, PlayerDeathMessage};
+use super::sound::{GameSound, SoundEffectMessage};
 use super::tiles::{GridPosition, TILE_SIZE, TerrainTile};
 use crate::PausableSystems;
-use crate::pixel_camera::PixelCamera;
 use crate::screens::Screen;
 
 pub fn plugin(app: &mut App) {
@@ -24,7 +24,6 @@ pub fn plugin(app: &mut App) {
             character_collision,
             decrement_oneshot_animation,
             update_character_animation,
-            camera_follow_player,
             respawn_on_fall,
         )
             .in_set(PausableSystems)
@@ -89,6 +88,7 @@ fn character_movement(
         (&mut Velocity, &mut Direction, &CharacterController),
         With<Character>,
     >,
+    mut sound_writer: MessageWriter<SoundEffectMessage>,
 ) {
     let Ok(action_state) = action_query.single() else {
         return;
@@ -126,6 +126,7 @@ fn character_movement(
         // Handle jumping
         if controller.is_grounded && action_state.just_pressed(&Action::Jump) {
             velocity.y = controller.jump_strength;
+            sound_writer.write(SoundEffectMessage::new(GameSound::Jump));
         }
     }
 }
@@ -157,6 +158,7 @@ fn character_collision(
         With<Character>,
     >,
     terrain_query: Query<&GridPosition, With<TerrainTile>>,
+    mut sound_writer: MessageWriter<SoundEffectMessage>,
 ) {
     for (mut transform, mut velocity, mut controller) in &mut character_query {
         let char_x = transform.translation.x;
@@ -170,6 +172,7 @@ fn character_collision(
         let char_bottom = char_y - half_height;
         let char_top = char_y + half_height - 50.;
 
+        let was_grounded = controller.is_grounded;
         controller.is_grounded = false;
 
         // Check collision with each terrain tile
@@ -219,6 +222,10 @@ fn character_collision(
                 }
             }
         }
+
+        if controller.is_grounded && !was_grounded {
+            sound_writer.write(SoundEffectMessage::new(GameSound::Land));
+        }
     }
 }
 
@@ -282,62 +289,20 @@ fn update_character_animation(
     }
 }
 
-/// System to smoothly follow the player character with the camera
-fn camera_follow_player(
-    time: Res<Time>,
-    character_query: Query<&Transform, (With<Character>, Without<PixelCamera>)>,
-    mut camera_query: Query<&mut Transform, With<PixelCamera>>,
+/// System to report the character falling off the level
+pub(super) fn respawn_on_fall(
+    character_query: Query<&Transform, With<Character>>,
+    mut death_writer: MessageWriter<PlayerDeathMessage>,
 ) {
-    let Ok(character_transform) = character_query.single() else {
-        return;
-    };
-
-    let Ok(mut camera_transform) = camera_query.single_mut() else {
-        return;
-    };
-
-    let smoothness = 10.0; // Higher = faster following
-    let y_offset = 32.0;
-    let dt = time.delta_secs();
-
-    camera_transform.translation.x = camera_transform.translation.x
-        + (character_transform.translation.x - camera_transform.translation.x) * smoothness * dt;
-    camera_transform.translation.y = camera_transform.translation.y
-        + (character_transform.translation.y + y_offset - camera_transform.translation.y)
-            * smoothness
-            * dt;
-    camera_transform.translation.y = camera_transform.translation.y.max(-12.);
-}
-
-/// System to respawn the character if they fall off the level
-fn respawn_on_fall(
-    spawn_point: Res<PlayerSpawnPoint>,
-    mut character_query: Query<(&mut Transform, &mut Velocity), With<Character>>,
-    mut fire_query: Query<&mut super::level::objects::Fire>,
-    mut bucket_content: ResMut<super::level::BucketContent>,
-) {
-    let Ok((mut transform, mut velocity)) = character_query.single_mut() else {
+    let Ok(transform) = character_query.single() else {
         return;
     };
 
     let fall_threshold = -500.0; // Y-position below which character respawns
 
     if transform.translation.y < fall_threshold {
-        // Reset to spawn position
-        transform.translation = spawn_point.position;
-
-        // Reset velocity to prevent continued falling
-        velocity.x = 0.0;
-        velocity.y = 0.0;
-
-        // Reset all fires to Active state
-        for mut fire in &mut fire_query {
-            fire.ignite();
-        }
-
-        // Reset bucket contents
-        *bucket_content = super::level::BucketContent::Empty;
-
-        info!("Player fell! Respawned and reset level state.");
+        death_writer.write(PlayerDeathMessage {
+            cause: DeathCause::Fell,
+        });
     }
 }
@@ -0,0 +1,600 @@
+//! Recording of per-tick player input, and deterministic playback of recorded levels.
+
+use std::time::Duration;
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+use leafwing_input_manager::plugin::InputManagerSystem;
+use leafwing_input_manager::prelude::*;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::character::CharacterLayers;
+use super::controls::Action;
+use super::interactions::LevelCompleteMessage;
+use super::level::LevelSeed;
+use super::{CompletedYear, GameLevel, Season};
+use crate::{Pause, screens::Screen};
+
+pub fn plugin(app: &mut App) {
+    app.init_resource::<ReplayRecorder>();
+    app.add_systems(
+        OnEnter(Screen::Gameplay),
+        (start_recording, begin_playback).after(super::level::spawn_level),
+    );
+    app.add_systems(OnExit(Screen::Gameplay), end_playback);
+    app.add_systems(OnEnter(Screen::Loading), load_pending_replay);
+    app.add_systems(
+        PreUpdate,
+        (play_back_frame, record_frame)
+            .chain()
+            .after(InputManagerSystem::ManualControl)
+            .run_if(in_state(Screen::Gameplay).and(in_state(Pause(false)))),
+    );
+    app.add_systems(Last, schedule_next_frame_time);
+    app.add_systems(
+        Update,
+        finish_playback.run_if(in_state(Screen::Gameplay).and(resource_exists::<ReplayPlayback>)),
+    );
+    #[cfg(not(target_arch = "wasm32"))]
+    app.add_systems(
+        Update,
+        save_completed_replay.run_if(in_state(Screen::Gameplay)),
+    );
+}
+
+/// Bump this whenever the replay format or anything affecting simulation changes
+pub const REPLAY_VERSION: u32 = 1;
+
+/// Where the fastest completion of each level and seed is kept
+#[cfg(not(target_arch = "wasm32"))]
+pub const BEST_REPLAY_DIR: &str = "replays/best";
+
+/// Where replays are exported to and imported from, to share them with teammates
+#[cfg(not(target_arch = "wasm32"))]
+pub const SHARED_REPLAY_DIR: &str = "replays/shared";
+
+/// Buttons captured in each frame, in bit order
+const RECORDED_BUTTONS: [Action; 3] = [Action::Jump, Action::Use, Action::Rewind];
+
+/// Actions driven by the replay, so unbound from live input during playback
+///
+/// Menu actions stay bound, so the player can still pause and quit out of a replay.
+const PLAYBACK_ACTIONS: [Action; 4] = [Action::Run, Action::Jump, Action::Use, Action::Rewind];
+
+#[derive(Error, Debug)]
+pub enum ReplayError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    DeserializeError(#[from] flexbuffers::DeserializationError),
+    #[error(transparent)]
+    SerializeError(#[from] flexbuffers::SerializationError),
+    #[error("Replay version {0} is not supported (expected {REPLAY_VERSION})")]
+    UnsupportedVersion(u32),
+}
+
+/// The action state for a single gameplay tick
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ReplayFrame {
+    /// Length of the tick, so playback steps time identically
+    pub delta_nanos: u64,
+    pub run: (f32, f32),
+    /// Bitset of pressed buttons, see `RECORDED_BUTTONS`
+    pub buttons: u8,
+}
+
+impl ReplayFrame {
+    pub fn new(delta: Duration, run: Vec2, pressed: impl Fn(&Action) -> bool) -> Self {
+        let buttons = RECORDED_BUTTONS
+            .iter()
+            .enumerate()
+            .filter(|(_, action)| pressed(action))
+            .fold(0, |bits, (index, _)| bits | 1 << index);
+        Self {
+            delta_nanos: delta.as_nanos() as u64,
+            run: (run.x, run.y),
+            buttons,
+        }
+    }
+
+    pub fn delta(&self) -> Duration {
+        Duration::from_nanos(self.delta_nanos)
+    }
+
+    pub fn is_pressed(&self, action: &Action) -> bool {
+        RECORDED_BUTTONS
+            .iter()
+            .position(|recorded| recorded == action)
+            .is_some_and(|index| self.buttons & (1 << index) != 0)
+    }
+}
+
+/// A recording of a level attempt, with everything needed to regenerate the level
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Replay {
+    pub version: u32,
+    pub seed: u64,
+    pub season: Season,
+    pub game_level: GameLevel,
+    pub completed_year: CompletedYear,
+    pub character_layers: CharacterLayers,
+    pub frames: Vec<ReplayFrame>,
+}
+
+impl Replay {
+    pub fn to_bytes(&self) -> Result<Vec<u8>, ReplayError> {
+        Ok(flexbuffers::to_vec(self)?)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ReplayError> {
+        let replay: Replay = flexbuffers::from_slice(bytes)?;
+        if replay.version != REPLAY_VERSION {
+            return Err(ReplayError::UnsupportedVersion(replay.version));
+        }
+        Ok(replay)
+    }
+
+    /// Total in-game time covered by the replay
+    pub fn duration(&self) -> Duration {
+        self.frames.iter().map(ReplayFrame::delta).sum()
+    }
+
+    /// Returns true if this replay is faster than `best`, or there is no best yet
+    pub fn beats(&self, best: Option<&Replay>) -> bool {
+        best.is_none_or(|best| self.duration() < best.duration())
+    }
+
+    /// The file name the replay is kept under, unique to its level and seed
+    pub fn file_name(&self) -> String {
+        replay_file_name(self.season, self.game_level, self.seed)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn save(&self, path: impl AsRef<std::path::Path>) -> Result<(), ReplayError> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, self.to_bytes()?)?;
+        Ok(())
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load(path: impl AsRef<std::path::Path>) -> Result<Self, ReplayError> {
+        Self::from_bytes(&std::fs::read(path)?)
+    }
+}
+
+/// The file name a level's replay is kept under
+pub fn replay_file_name(season: Season, game_level: GameLevel, seed: u64) -> String {
+    format!("{}-{}-{}.replay", season, game_level.0, seed)
+}
+
+/// Keeps `replay` in `best_dir` if it's the fastest of its level, returning whether it was kept
+#[cfg(not(target_arch = "wasm32"))]
+pub fn save_best_replay(
+    best_dir: impl AsRef<std::path::Path>,
+    replay: &Replay,
+) -> Result<bool, ReplayError> {
+    let path = best_dir.as_ref().join(replay.file_name());
+    // A best from an older version can't be played back, so any new run replaces it
+    let best = Replay::load(&path).ok();
+    if !replay.beats(best.as_ref()) {
+        return Ok(false);
+    }
+    replay.save(path)?;
+    Ok(true)
+}
+
+/// Copies the best replay of a level from `best_dir` to `shared_dir`, returning its new path
+#[cfg(not(target_arch = "wasm32"))]
+pub fn export_best_replay(
+    best_dir: impl AsRef<std::path::Path>,
+    shared_dir: impl AsRef<std::path::Path>,
+    file_name: &str,
+) -> Result<std::path::PathBuf, ReplayError> {
+    let replay = Replay::load(best_dir.as_ref().join(file_name))?;
+    let path = shared_dir.as_ref().join(file_name);
+    replay.save(&path)?;
+    Ok(path)
+}
+
+/// Keeps every replay in `shared_dir` that beats the best of its level, returning how many did
+///
+/// Files that aren't replays from this version of the game are skipped.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn import_replays(
+    shared_dir: impl AsRef<std::path::Path>,
+    best_dir: impl AsRef<std::path::Path>,
+) -> Result<usize, ReplayError> {
+    let entries = match std::fs::read_dir(shared_dir) {
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        entries => entries?,
+    };
+
+    let mut imported = 0;
+    for entry in entries {
+        let path = entry?.path();
+        if path
+            .extension()
+            .is_none_or(|extension| extension != "replay")
+        {
+            continue;
+        }
+        match Replay::load(&path) {
+            Ok(replay) => {
+                if save_best_replay(&best_dir, &replay)? {
+                    imported += 1;
+                }
+            }
+            Err(error) => warn!("Skipping replay {}: {error}", path.display()),
+        }
+    }
+    Ok(imported)
+}
+
+/// Resource recording the current level attempt
+#[derive(Resource, Debug, Default)]
+pub struct ReplayRecorder {
+    pub replay: Option<Replay>,
+}
+
+/// Resource present while a replay is being played back
+///
+/// The replay's level and outfit are read through [`PlayedLevel`], so the player's own progress
+/// is never changed by watching one, even if it's left part way through.
+#[derive(Resource, Debug)]
+pub struct ReplayPlayback {
+    replay: Replay,
+    cursor: usize,
+    /// The player's bindings, restored when playback ends
+    input_map: Option<InputMap<Action>>,
+}
+
+impl ReplayPlayback {
+    pub fn is_finished(&self) -> bool {
+        self.cursor >= self.replay.frames.len()
+    }
+}
+
+/// System parameter for the level and outfit being played: the replay's during playback,
+/// otherwise the player's own
+#[derive(SystemParam)]
+pub struct PlayedLevel<'w> {
+    playback: Option<Res<'w, ReplayPlayback>>,
+    season: Res<'w, Season>,
+    game_level: Res<'w, GameLevel>,
+    completed_year: Res<'w, CompletedYear>,
+    character_layers: Res<'w, CharacterLayers>,
+}
+
+impl PlayedLevel<'_> {
+    fn replay(&self) -> Option<&Replay> {
+        self.playback.as_ref().map(|playback| &playback.replay)
+    }
+
+    pub fn season(&self) -> Season {
+        self.replay().map_or(*self.season, |replay| replay.season)
+    }
+
+    pub fn game_level(&self) -> GameLevel {
+        self.replay()
+            .map_or(*self.game_level, |replay| replay.game_level)
+    }
+
+    pub fn completed_year(&self) -> CompletedYear {
+        self.replay()
+            .map_or(*self.completed_year, |replay| replay.completed_year)
+    }
+
+    pub fn character_layers(&self) -> &CharacterLayers {
+        self.replay()
+            .map_or(&self.character_layers, |replay| &replay.character_layers)
+    }
+
+    /// The seed the level has to be generated from, if it's being replayed
+    pub fn replay_seed(&self) -> Option<u64> {
+        self.replay().map(|replay| replay.seed)
+    }
+
+    /// Returns true if any of the played values changed since the system last ran
+    pub fn is_changed(&self) -> bool {
+        match &self.playback {
+            Some(playback) => playback.is_added(),
+            None => {
+                self.season.is_changed()
+                    || self.game_level.is_changed()
+                    || self.completed_year.is_changed()
+                    || self.character_layers.is_changed()
+            }
+        }
+    }
+}
+
+/// Resource holding a replay that will start playing once the current level is left
+#[derive(Resource, Debug)]
+struct PendingReplay(Replay);
+
+/// Command to play back a replay, regenerating its level from the recorded seed
+pub fn play_replay(replay: Replay) -> impl Command {
+    move |world: &mut World| {
+        world.insert_resource(PendingReplay(replay));
+        world
+            .resource_mut::<NextState<Screen>>()
+            .set(Screen::Loading);
+    }
+}
+
+/// Starts playing back the pending replay, now the level it was queued from has been left
+fn load_pending_replay(mut commands: Commands, pending: Option<Res<PendingReplay>>) {
+    let Some(pending) = pending else {
+        return;
+    };
+    commands.insert_resource(ReplayPlayback {
+        replay: pending.0.clone(),
+        cursor: 0,
+        input_map: None,
+    });
+    commands.remove_resource::<PendingReplay>();
+}
+
+/// Starts a new recording for the level that was just generated
+fn start_recording(
+    mut recorder: ResMut<ReplayRecorder>,
+    playback: Option<Res<ReplayPlayback>>,
+    level_seed: Res<LevelSeed>,
+    season: Res<Season>,
+    game_level: Res<GameLevel>,
+    completed_year: Res<CompletedYear>,
+    character_layers: Res<CharacterLayers>,
+) {
+    if playback.is_some() {
+        recorder.replay = None;
+        return;
+    }
+
+    recorder.replay = Some(Replay {
+        version: REPLAY_VERSION,
+        seed: level_seed.0,
+        season: *season,
+        game_level: *game_level,
+        completed_year: *completed_year,
+        character_layers: character_layers.clone(),
+        frames: Vec::new(),
+    });
+}
+
+/// Unbinds the gameplay actions so only the replay drives them in the `ActionState`
+fn begin_playback(
+    playback: Option<ResMut<ReplayPlayback>>,
+    mut input_query: Query<&mut InputMap<Action>>,
+) {
+    let Some(mut playback) = playback else {
+        return;
+    };
+    let Ok(mut input_map) = input_query.single_mut() else {
+        return;
+    };
+
+    playback.input_map = Some(input_map.clone());
+    for action in &PLAYBACK_ACTIONS {
+        input_map.clear_action(action);
+    }
+    info!(
+        "Playing back replay of {} Level {} ({} frames)",
+        playback.replay.season,
+        playback.replay.game_level.0,
+        playback.replay.frames.len()
+    );
+}
+
+/// Restores the player's bindings after playback
+fn end_playback(
+    mut commands: Commands,
+    playback: Option<Res<ReplayPlayback>>,
+    mut input_query: Query<(&mut InputMap<Action>, &mut ActionState<Action>)>,
+) {
+    let Some(playback) = playback else {
+        return;
+    };
+
+    if let Ok((mut input_map, mut action_state)) = input_query.single_mut() {
+        if let Some(previous_map) = &playback.input_map {
+            *input_map = previous_map.clone();
+        }
+        action_state.reset_all();
+    }
+
+    commands.insert_resource(TimeUpdateStrategy::Automatic);
+    commands.remove_resource::<ReplayPlayback>();
+}
+
+/// System to inject the next recorded frame into the `ActionState`
+pub(super) fn play_back_frame(
+    playback: Option<ResMut<ReplayPlayback>>,
+    mut action_query: Query<&mut ActionState<Action>>,
+) {
+    let Some(mut playback) = playback else {
+        return;
+    };
+    let Ok(mut action_state) = action_query.single_mut() else {
+        return;
+    };
+
+    let Some(frame) = playback.replay.frames.get(playback.cursor).copied() else {
+        return;
+    };
+    playback.cursor += 1;
+
+    action_state.set_axis_pair(&Action::Run, Vec2::new(frame.run.0, frame.run.1));
+    for action in &RECORDED_BUTTONS {
+        if frame.is_pressed(action) {
+            action_state.press(action);
+        } else {
+            action_state.release(action);
+        }
+    }
+}
+
+/// System to leave a replay that ran out of frames without finishing its level
+fn finish_playback(playback: Res<ReplayPlayback>, mut next_state: ResMut<NextState<Screen>>) {
+    if playback.is_finished() {
+        info!("Replay ended before finishing the level! Returning to title");
+        next_state.set(Screen::Title);
+    }
+}
+
+/// System to append the current `ActionState` to the recording
+fn record_frame(
+    time: Res<Time>,
+    mut recorder: ResMut<ReplayRecorder>,
+    action_query: Query<&ActionState<Action>>,
+) {
+    let Some(replay) = &mut recorder.replay else {
+        return;
+    };
+    let Ok(action_state) = action_query.single() else {
+        return;
+    };
+
+    replay.frames.push(ReplayFrame::new(
+        time.delta(),
+        action_state.axis_pair(&Action::Run),
+        |action| action_state.pressed(action),
+    ));
+}
+
+/// System to make the next frame last exactly as long as it did when recorded
+fn schedule_next_frame_time(
+    playback: Option<Res<ReplayPlayback>>,
+    mut strategy: ResMut<TimeUpdateStrategy>,
+) {
+    let Some(playback) = playback else {
+        return;
+    };
+
+    if let Some(frame) = playback.replay.frames.get(playback.cursor) {
+        *strategy = TimeUpdateStrategy::ManualDuration(frame.delta());
+    } else if !matches!(*strategy, TimeUpdateStrategy::Automatic) {
+        *strategy = TimeUpdateStrategy::Automatic;
+    }
+}
+
+/// System to keep the replay of a completed level if it's the fastest yet
+#[cfg(not(target_arch = "wasm32"))]
+fn save_completed_replay(
+    mut level_complete_reader: MessageReader<LevelCompleteMessage>,
+    recorder: Res<ReplayRecorder>,
+) {
+    if level_complete_reader.read().last().is_none() {
+        return;
+    }
+    let Some(replay) = &recorder.replay else {
+        return;
+    };
+
+    match save_best_replay(BEST_REPLAY_DIR, replay) {
+        Ok(true) => info!(
+            "Saved best replay {} ({:.1}s)",
+            replay.file_name(),
+            replay.duration().as_secs_f32()
+        ),
+        Ok(false) => {}
+        Err(error) => warn!("Failed to save replay: {error}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn replay() -> Replay {
+        Replay {
+            version: REPLAY_VERSION,
+            seed: 1234,
+            season: Season::Winter,
+            game_level: GameLevel(2),
+            completed_year: CompletedYear(false),
+            character_layers: CharacterLayers::default(),
+            frames: vec![
+                ReplayFrame::new(Duration::from_millis(16), Vec2::X, |action| {
+                    *action == Action::Jump
+                }),
+                ReplayFrame::new(Duration::from_millis(17), Vec2::ZERO, |_| false),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_frame_buttons_roundtrip() {
+        let frame = ReplayFrame::new(Duration::from_nanos(16_666_667), Vec2::ZERO, |action| {
+            matches!(action, Action::Use | Action::Rewind)
+        });
+        assert!(!frame.is_pressed(&Action::Jump));
+        assert!(frame.is_pressed(&Action::Use));
+        assert!(frame.is_pressed(&Action::Rewind));
+        assert!(!frame.is_pressed(&Action::Menu));
+        assert_eq!(frame.delta(), Duration::from_nanos(16_666_667));
+    }
+
+    #[test]
+    fn test_replay_bytes_roundtrip() {
+        let replay = replay();
+        let bytes = replay.to_bytes().unwrap();
+        let loaded = Replay::from_bytes(&bytes).unwrap();
+        assert_eq!(loaded.seed, replay.seed);
+        assert_eq!(loaded.season, replay.season);
+        assert_eq!(loaded.frames, replay.frames);
+        assert_eq!(replay.duration(), Duration::from_millis(33));
+    }
+
+    #[test]
+    fn test_faster_replays_beat_the_best() {
+        let best = replay();
+        let mut faster = replay();
+        faster.frames.pop();
+        assert!(faster.beats(Some(&best)));
+        assert!(!best.beats(Some(&faster)));
+        assert!(best.beats(None));
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn test_shared_replays_import_when_faster() {
+        let root = std::env::temp_dir().join(format!("replay-test-{}", std::process::id()));
+        let (mine, theirs) = (root.join("mine"), root.join("theirs"));
+        let _ = std::fs::remove_dir_all(&root);
+
+        let slow = replay();
+        let mut fast = replay();
+        fast.frames.pop();
+        assert!(save_best_replay(&mine, &slow).unwrap());
+        assert!(save_best_replay(&theirs, &fast).unwrap());
+        assert!(!save_best_replay(&theirs, &slow).unwrap());
+
+        let shared = root.join("shared");
+        let path = export_best_replay(&theirs, &shared, &fast.file_name()).unwrap();
+        std::fs::write(shared.join("notes.txt"), "not a replay").unwrap();
+        std::fs::write(shared.join("broken.replay"), "not a replay").unwrap();
+        assert_eq!(import_replays(&shared, &mine).unwrap(), 1);
+        assert_eq!(import_replays(&shared, &mine).unwrap(), 0);
+
+        let best = Replay::load(mine.join(slow.file_name())).unwrap();
+        assert_eq!(best.frames, fast.frames);
+        assert_eq!(path, shared.join(fast.file_name()));
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_replay_rejects_other_versions() {
+        let mut replay = replay();
+        replay.version = REPLAY_VERSION + 1;
+        let bytes = replay.to_bytes().unwrap();
+        assert!(matches!(
+            Replay::from_bytes(&bytes),
+            Err(ReplayError::UnsupportedVersion(_))
+        ));
+    }
+}
@@ -0,0 +1,251 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+use bevy_pkv::prelude::*;
+use leafwing_input_manager::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::character::{Character, Direction};
+use super::controls::Action;
+use super::level::BucketContent;
+use super::level::objects::{Container, ContainerState, Fire, FireState};
+use super::physics::Velocity;
+use crate::PausableSystems;
+use crate::screens::Screen;
+
+pub fn plugin(app: &mut App) {
+    app.register_type::<RewindSettings>();
+    app.init_persistent_resource::<RewindSettings>();
+    app.init_resource::<RewindBuffer>();
+    app.add_systems(OnEnter(Screen::Gameplay), clear_rewind_buffer);
+    app.add_systems(
+        Update,
+        record_or_rewind
+            .after(super::physics::respawn_on_fall)
+            .after(super::interactions::touch_active_fire)
+            .after(super::level::respawn_player)
+            .in_set(PausableSystems)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+}
+
+/// The least history the player can set, in seconds
+pub const MIN_HISTORY_SECS: f32 = 5.0;
+
+/// The most history the player can set, in seconds
+pub const MAX_HISTORY_SECS: f32 = 30.0;
+
+/// Resource configuring how much history the rewind buffer keeps
+#[derive(Resource, Reflect, Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[reflect(Resource)]
+pub struct RewindSettings {
+    /// How far back the player can rewind, in seconds
+    pub history_secs: f32,
+}
+
+impl Default for RewindSettings {
+    fn default() -> Self {
+        Self { history_secs: 10.0 }
+    }
+}
+
+impl RewindSettings {
+    /// Lengthens or shortens the history by `step` seconds, keeping it between
+    /// [`MIN_HISTORY_SECS`] and [`MAX_HISTORY_SECS`]
+    pub fn adjust(&mut self, step: f32) {
+        self.history_secs = (self.history_secs + step).clamp(MIN_HISTORY_SECS, MAX_HISTORY_SECS);
+    }
+}
+
+/// A snapshot of everything the player can change, taken once per frame
+#[derive(Debug, Clone, PartialEq)]
+pub struct RewindFrame {
+    pub translation: Vec3,
+    pub velocity: Vec2,
+    pub direction: Direction,
+    pub bucket_content: BucketContent,
+    pub fires: Vec<(Entity, FireState)>,
+    pub containers: Vec<(Entity, ContainerState)>,
+}
+
+/// Resource holding recorded frames, oldest first, with the time each was taken
+///
+/// Times come from the buffer's own clock, which only runs while frames are recorded, so
+/// pausing the game doesn't age the history.
+#[derive(Resource, Debug, Default)]
+pub struct RewindBuffer {
+    frames: VecDeque<(f32, RewindFrame)>,
+    clock: f32,
+}
+
+impl RewindBuffer {
+    /// Records a frame taken `delta` seconds after the last one, dropping frames older than
+    /// `history_secs`
+    pub fn push(&mut self, delta: f32, frame: RewindFrame, history_secs: f32) {
+        self.clock += delta;
+        let time = self.clock;
+        self.frames.push_back((time, frame));
+        while self
+            .frames
+            .front()
+            .is_some_and(|(recorded, _)| time - recorded > history_secs)
+        {
+            self.frames.pop_front();
+        }
+    }
+
+    /// Removes and returns the most recent frame, winding the clock back to it
+    pub fn pop(&mut self) -> Option<RewindFrame> {
+        let (time, frame) = self.frames.pop_back()?;
+        self.clock = time;
+        Some(frame)
+    }
+
+    fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.frames.clear();
+        self.clock = 0.0;
+    }
+}
+
+fn clear_rewind_buffer(mut buffer: ResMut<RewindBuffer>) {
+    buffer.clear();
+}
+
+/// System to record the puzzle state each frame, or step back through it while rewind is held
+///
+/// Rewinding pops one frame per frame, so time runs backwards at the speed it was recorded.
+/// The oldest frame is kept so holding rewind at the end of the buffer stays put.
+fn record_or_rewind(
+    time: Res<Time>,
+    settings: Res<RewindSettings>,
+    mut buffer: ResMut<RewindBuffer>,
+    action_query: Query<&ActionState<Action>>,
+    mut character_query: Query<(&mut Transform, &mut Velocity, &mut Direction), With<Character>>,
+    mut fire_query: Query<(Entity, &mut Fire)>,
+    mut container_query: Query<(Entity, &mut Container)>,
+    mut bucket_content: ResMut<BucketContent>,
+) {
+    let Ok((mut transform, mut velocity, mut direction)) = character_query.single_mut() else {
+        return;
+    };
+
+    let rewinding = action_query
+        .single()
+        .is_ok_and(|action_state| action_state.pressed(&Action::Rewind));
+
+    if !rewinding {
+        let frame = RewindFrame {
+            translation: transform.translation,
+            velocity: Vec2::new(velocity.x, velocity.y),
+            direction: *direction,
+            bucket_content: *bucket_content,
+            fires: fire_query
+                .iter()
+                .map(|(entity, fire)| (entity, fire.state))
+                .collect(),
+            containers: container_query
+                .iter()
+                .map(|(entity, container)| (entity, container.state))
+                .collect(),
+        };
+        buffer.push(time.delta_secs(), frame, settings.history_secs);
+        return;
+    }
+
+    let frame = if buffer.len() > 1 {
+        buffer.pop()
+    } else {
+        buffer.frames.back().map(|(_, frame)| frame.clone())
+    };
+    let Some(frame) = frame else {
+        return;
+    };
+
+    transform.translation = frame.translation;
+    velocity.x = frame.velocity.x;
+    velocity.y = frame.velocity.y;
+    direction.set_if_neq(frame.direction);
+    bucket_content.set_if_neq(frame.bucket_content);
+
+    for (entity, mut fire) in &mut fire_query {
+        if let Some((_, state)) = frame.fires.iter().find(|(e, _)| *e == entity)
+            && fire.state != *state
+        {
+            fire.state = *state;
+        }
+    }
+    for (entity, mut container) in &mut container_query {
+        if let Some((_, state)) = frame.containers.iter().find(|(e, _)| *e == entity)
+            && container.state != *state
+        {
+            container.state = *state;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(x: f32) -> RewindFrame {
+        RewindFrame {
+            translation: Vec3::new(x, 0.0, 0.0),
+            velocity: Vec2::ZERO,
+            direction: Direction::Right,
+            bucket_content: BucketContent::Empty,
+            fires: Vec::new(),
+            containers: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_rewind_buffer_drops_old_frames() {
+        let mut buffer = RewindBuffer::default();
+        for i in 0..=10 {
+            buffer.push(1.0, frame(i as f32), 5.0);
+        }
+
+        // Frames 5..=10 are within 5 seconds of the latest
+        assert_eq!(buffer.len(), 6);
+        assert_eq!(buffer.pop(), Some(frame(10.0)));
+        assert_eq!(buffer.pop(), Some(frame(9.0)));
+    }
+
+    #[test]
+    fn test_rewind_buffer_pops_newest_first() {
+        let mut buffer = RewindBuffer::default();
+        buffer.push(0.0, frame(1.0), 10.0);
+        buffer.push(0.1, frame(2.0), 10.0);
+
+        assert_eq!(buffer.pop(), Some(frame(2.0)));
+        assert_eq!(buffer.pop(), Some(frame(1.0)));
+        assert_eq!(buffer.len(), 0);
+    }
+
+    #[test]
+    fn test_history_stays_in_range() {
+        let mut settings = RewindSettings::default();
+        settings.adjust(-100.0);
+        assert_eq!(settings.history_secs, MIN_HISTORY_SECS);
+        settings.adjust(100.0);
+        assert_eq!(settings.history_secs, MAX_HISTORY_SECS);
+    }
+
+    #[test]
+    fn test_rewinding_winds_the_clock_back() {
+        let mut buffer = RewindBuffer::default();
+        for i in 0..5 {
+            buffer.push(1.0, frame(i as f32), 3.0);
+        }
+        buffer.pop();
+        buffer.pop();
+
+        // Recording again after rewinding doesn't age out the frames that were rewound to
+        buffer.push(1.0, frame(10.0), 3.0);
+        assert_eq!(buffer.len(), 3);
+    }
+}
@@ -0,0 +1,255 @@
+//! Versioned storage for save slots.
+//!
+//! Each slot is stored as a [`SaveEnvelope`]: a version number alongside the slot serialized
+//! in that version's format. Older versions are decoded with frozen copies of their types
+//! and migrated forward one version at a time, so changing [`SaveSlot`] or anything inside
+//! it (such as [`LayerType`]) doesn't invalidate existing saves.
+//!
+//! To change the save format, copy the current types into a new frozen module (as with
+//! [`v1`]), bump [`SAVE_VERSION`], and add a migration from the frozen copy to the next
+//! version, so each version is migrated by the steps after it.
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::SaveSlot;
+use crate::game::character::{CharacterLayer, CharacterLayers, LayerType, LayerVariant};
+use crate::game::unlocks::UnlockedItems;
+use crate::game::{CompletedYear, GameLevel, PlayerLevel, Season};
+
+/// The version slots are saved with
+pub const SAVE_VERSION: u32 = 3;
+
+#[derive(Error, Debug)]
+pub enum SaveError {
+    #[error(transparent)]
+    DeserializeError(#[from] flexbuffers::DeserializationError),
+    #[error(transparent)]
+    SerializeError(#[from] flexbuffers::SerializationError),
+    #[error("Save version {0} is newer than this game supports (up to {SAVE_VERSION})")]
+    UnsupportedVersion(u32),
+    #[error("Save could not be read")]
+    Unreadable,
+}
+
+/// A save slot in the format of a particular version
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SaveEnvelope {
+    pub version: u32,
+    #[serde(with = "serde_bytes")]
+    pub data: Vec<u8>,
+}
+
+impl SaveEnvelope {
+    /// Wraps a slot in the current version's format
+    pub fn new(slot: &SaveSlot) -> Result<Self, SaveError> {
+        Ok(Self {
+            version: SAVE_VERSION,
+            data: flexbuffers::to_vec(slot)?,
+        })
+    }
+
+    /// Reads the slot, migrating it from older versions if needed
+    pub fn decode(&self) -> Result<SaveSlot, SaveError> {
+        match self.version {
+            // Version 1 predates envelopes, but is accepted here too
+            1 => Ok(migrate_v2(migrate_v1(flexbuffers::from_slice(&self.data)?))),
+            2 => Ok(migrate_v2(flexbuffers::from_slice(&self.data)?)),
+            SAVE_VERSION => Ok(flexbuffers::from_slice(&self.data)?),
+            version => Err(SaveError::UnsupportedVersion(version)),
+        }
+    }
+}
+
+/// The save format before versioning, when a [`v1::SaveSlot`] was stored directly
+///
+/// Before save slots, each progression resource was stored on its own in this format too.
+pub mod v1 {
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct SaveSlot {
+        pub player_level: PlayerLevel,
+        pub game_level: GameLevel,
+        pub completed_year: CompletedYear,
+        pub season: Season,
+        pub character_layers: CharacterLayers,
+        pub playtime_secs: f64,
+    }
+
+    #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+    pub struct PlayerLevel(pub u8);
+
+    #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+    pub struct GameLevel(pub u8);
+
+    #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+    pub struct CompletedYear(pub bool);
+
+    #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+    pub enum Season {
+        Summer,
+        Autumn,
+        Winter,
+        Spring,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct CharacterLayers {
+        pub layers: Vec<CharacterLayer>,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct CharacterLayer {
+        pub layer_type: LayerType,
+        pub item_name: Option<String>,
+        pub variant: Option<LayerVariant>,
+    }
+
+    #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+    pub enum LayerType {
+        Cape,
+        Body,
+        Hair,
+        Underclothes,
+        Footwear,
+        Clothes,
+        Gloves,
+        Headwear,
+    }
+
+    #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+    pub enum LayerVariant {
+        HairColour(u8),
+        ClothingColour(u8),
+        Variant(u8),
+    }
+}
+
+/// The save format once slots were stored in an envelope
+///
+/// Version 2 only added the envelope, so its slots have the same layout as version 1.
+pub mod v2 {
+    pub use super::v1::SaveSlot;
+}
+
+/// Converts a version 1 slot to version 2
+pub fn migrate_v1(slot: v1::SaveSlot) -> v2::SaveSlot {
+    slot
+}
+
+/// Converts a version 2 slot to version 3, the current format
+///
+/// Version 3 added unlocked items. They're left empty here, and granted for the progress
+/// already made once the wardrobe catalog has loaded.
+pub fn migrate_v2(slot: v2::SaveSlot) -> SaveSlot {
+    SaveSlot {
+        player_level: slot.player_level.into(),
+        game_level: slot.game_level.into(),
+        completed_year: slot.completed_year.into(),
+        season: slot.season.into(),
+        character_layers: slot.character_layers.into(),
+        unlocked_items: UnlockedItems::default(),
+        playtime_secs: slot.playtime_secs,
+    }
+}
+
+impl From<v1::PlayerLevel> for PlayerLevel {
+    fn from(level: v1::PlayerLevel) -> Self {
+        PlayerLevel(level.0)
+    }
+}
+
+impl From<v1::GameLevel> for GameLevel {
+    fn from(level: v1::GameLevel) -> Self {
+        GameLevel(level.0)
+    }
+}
+
+impl From<v1::CompletedYear> for CompletedYear {
+    fn from(completed: v1::CompletedYear) -> Self {
+        CompletedYear(completed.0)
+    }
+}
+
+impl From<v1::Season> for Season {
+    fn from(season: v1::Season) -> Self {
+        match season {
+            v1::Season::Summer => Season::Summer,
+            v1::Season::Autumn => Season::Autumn,
+            v1::Season::Winter => Season::Winter,
+            v1::Season::Spring => Season::Spring,
+        }
+    }
+}
+
+impl From<v1::CharacterLayers> for CharacterLayers {
+    fn from(layers: v1::CharacterLayers) -> Self {
+        CharacterLayers {
+            layers: layers
+                .layers
+                .into_iter()
+                .map(|layer| CharacterLayer {
+                    layer_type: match layer.layer_type {
+                        v1::LayerType::Cape => LayerType::Cape,
+                        v1::LayerType::Body => LayerType::Body,
+                        v1::LayerType::Hair => LayerType::Hair,
+                        v1::LayerType::Underclothes => LayerType::Underclothes,
+                        v1::LayerType::Footwear => LayerType::Footwear,
+                        v1::LayerType::Clothes => LayerType::Clothes,
+                        v1::LayerType::Gloves => LayerType::Gloves,
+                        v1::LayerType::Headwear => LayerType::Headwear,
+                    },
+                    item_name: layer.item_name,
+                    variant: layer.variant.map(|variant| match variant {
+                        v1::LayerVariant::HairColour(index) => LayerVariant::HairColour(index),
+                        v1::LayerVariant::ClothingColour(index) => {
+                            LayerVariant::ClothingColour(index)
+                        }
+                        v1::LayerVariant::Variant(number) => LayerVariant::Variant(number),
+                    }),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::default;
+
+    use super::*;
+
+    #[test]
+    fn test_current_version_round_trips() {
+        let slot = SaveSlot {
+            game_level: GameLevel(3),
+            season: Season::Autumn,
+            ..default()
+        };
+        let decoded = SaveEnvelope::new(&slot).unwrap().decode().unwrap();
+        assert_eq!(decoded.game_level, GameLevel(3));
+        assert_eq!(decoded.season, Season::Autumn);
+    }
+
+    #[test]
+    fn test_newer_version_is_rejected() {
+        let envelope = SaveEnvelope {
+            version: SAVE_VERSION + 1,
+            data: Vec::new(),
+        };
+        assert!(matches!(
+            envelope.decode(),
+            Err(SaveError::UnsupportedVersion(version)) if version == SAVE_VERSION + 1
+        ));
+    }
+
+    #[test]
+    fn test_corrupt_data_is_an_error() {
+        let envelope = SaveEnvelope {
+            version: SAVE_VERSION,
+            data: vec![1, 2, 3],
+        };
+        assert!(envelope.decode().is_err());
+    }
+}
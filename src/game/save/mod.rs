@@ -0,0 +1,515 @@
+//! Save slots, so several players on one machine can each keep their own progress.
+//!
+//! The progression resources ([`PlayerLevel`], [`GameLevel`], [`CompletedYear`], [`Season`],
+//! [`CharacterLayers`] and [`UnlockedItems`]) always hold the active slot's values. They're written back to
+//! the slot whenever they change, and when leaving gameplay so the playtime is kept.
+//!
+//! A slot that can't be read is never overwritten. It's backed up, and progress is saved to a
+//! fresh slot instead, with a [`SlotLoadWarning`] for the menus to show.
+
+use bevy::prelude::*;
+use bevy_pkv::prelude::*;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+
+mod envelope;
+
+use envelope::v1;
+pub use envelope::{SaveEnvelope, SaveError};
+
+use super::character::CharacterLayers;
+use super::replay::ReplayPlayback;
+use super::unlocks::UnlockedItems;
+use super::{CompletedYear, GameLevel, PlayerLevel, Season};
+use crate::{PausableSystems, screens::Screen};
+
+pub fn plugin(app: &mut App) {
+    app.init_persistent_resource::<SaveSlots>();
+    app.init_resource::<Playtime>();
+    app.init_resource::<SlotLoadWarning>();
+    app.add_systems(Startup, load_initial_slot);
+    app.add_systems(
+        Update,
+        (
+            track_playtime
+                .in_set(PausableSystems)
+                .run_if(in_state(Screen::Gameplay)),
+            save_active_slot.run_if(
+                resource_changed::<PlayerLevel>
+                    .or(resource_changed::<GameLevel>)
+                    .or(resource_changed::<CompletedYear>)
+                    .or(resource_changed::<Season>)
+                    .or(resource_changed::<CharacterLayers>)
+                    .or(resource_changed::<UnlockedItems>),
+            ),
+        )
+            .run_if(not(resource_exists::<ReplayPlayback>)),
+    );
+    app.add_systems(
+        OnExit(Screen::Gameplay),
+        save_active_slot.run_if(not(resource_exists::<ReplayPlayback>)),
+    );
+}
+
+/// Resource listing the save slots, and which one is being played
+#[derive(Resource, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SaveSlots {
+    /// Slot names, in the order they were created
+    pub names: Vec<String>,
+    pub active: Option<String>,
+}
+
+/// Resource counting how long the active slot has been played for, in seconds
+#[derive(Resource, Debug, Clone, Copy, Default)]
+pub struct Playtime(pub f64);
+
+/// Resource holding why the last slot to load couldn't be, until the player has seen it
+#[derive(Resource, Debug, Default)]
+pub struct SlotLoadWarning(pub Option<String>);
+
+/// Everything saved in a slot
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SaveSlot {
+    pub player_level: PlayerLevel,
+    pub game_level: GameLevel,
+    pub completed_year: CompletedYear,
+    pub season: Season,
+    pub character_layers: CharacterLayers,
+    pub unlocked_items: UnlockedItems,
+    pub playtime_secs: f64,
+}
+
+impl SaveSlot {
+    /// A one-line description of the slot's progress, for the slot picker
+    pub fn summary(&self) -> String {
+        format!(
+            "{} Level {} - {}",
+            self.season,
+            self.game_level.0,
+            format_playtime(self.playtime_secs)
+        )
+    }
+
+    /// Makes this slot's values the current progression
+    fn apply(self, world: &mut World) {
+        world.insert_resource(self.player_level);
+        world.insert_resource(self.game_level);
+        world.insert_resource(self.completed_year);
+        world.insert_resource(self.season);
+        world.insert_resource(self.character_layers);
+        world.insert_resource(self.unlocked_items);
+        world.insert_resource(Playtime(self.playtime_secs));
+    }
+}
+
+/// Formats a playtime as hours and minutes, e.g. "1h 05m"
+pub fn format_playtime(secs: f64) -> String {
+    let minutes = (secs / 60.0) as u64;
+    match minutes / 60 {
+        0 => format!("{}m", minutes),
+        hours => format!("{}h {:02}m", hours, minutes % 60),
+    }
+}
+
+/// The key a slot is stored under in the [`PkvStore`]
+fn slot_key(name: &str) -> String {
+    format!("slot-{name}")
+}
+
+/// Returns the first "Save N" name that isn't taken
+pub fn next_slot_name(names: &[String]) -> String {
+    (1..)
+        .map(|i| format!("Save {i}"))
+        .find(|name| !names.contains(name))
+        .unwrap()
+}
+
+/// Returns a name for a copy of the slot `name` that isn't taken
+pub fn copy_slot_name(name: &str, names: &[String]) -> String {
+    std::iter::once(format!("{name} (copy)"))
+        .chain((2..).map(|i| format!("{name} (copy {i})")))
+        .find(|copy| !names.contains(copy))
+        .unwrap()
+}
+
+/// Reads a slot from storage, migrating it from older versions if needed
+pub fn read_slot(pkv: &PkvStore, name: &str) -> Result<SaveSlot, SaveError> {
+    let key = slot_key(name);
+    match pkv.get::<SaveEnvelope>(&key) {
+        Ok(envelope) => envelope.decode(),
+        // Slots saved before versioning were stored without an envelope
+        Err(_) => pkv
+            .get::<envelope::v1::SaveSlot>(&key)
+            .map(envelope::migrate_v1)
+            .map(envelope::migrate_v2)
+            .map_err(|_| SaveError::Unreadable),
+    }
+}
+
+/// Writes a slot to storage, returning whether it was saved
+fn write_slot(pkv: &mut PkvStore, name: &str, slot: &SaveSlot) -> bool {
+    let result = SaveEnvelope::new(slot)
+        .map_err(|error| error.to_string())
+        .and_then(|envelope| {
+            pkv.set(slot_key(name), &envelope)
+                .map_err(|error| error.to_string())
+        });
+    if let Err(error) = &result {
+        warn!("Failed to save slot {name}: {error}");
+    }
+    result.is_ok()
+}
+
+/// The key an unreadable slot is backed up under
+fn backup_key(name: &str) -> String {
+    format!("{}-backup", slot_key(name))
+}
+
+/// Copies an unreadable slot aside, so it survives even if the slot is deleted
+///
+/// The [`PkvStore`] only hands out deserialized values, so the slot is copied as an untyped
+/// value without reading it as any version of a slot. Whatever was stored is backed up, even
+/// if it predates envelopes or can't be migrated.
+fn back_up_slot(pkv: &mut PkvStore, name: &str) {
+    let result = pkv
+        .get::<ron::Value>(slot_key(name))
+        .map_err(|error| error.to_string())
+        .and_then(|value| {
+            pkv.set(backup_key(name), &value)
+                .map_err(|error| error.to_string())
+        });
+    if let Err(error) = result {
+        warn!("Failed to back up slot {name}: {error}");
+    }
+}
+
+/// Command to make `name` the active slot, loading its progress
+///
+/// If the slot can't be read, it's backed up and left as it is rather than reset. If it was
+/// the active slot, a fresh slot is made active instead, so progress is still saved.
+pub fn load_slot(name: String) -> impl Command {
+    move |world: &mut World| match read_slot(world.resource::<PkvStore>(), &name) {
+        Ok(slot) => {
+            slot.apply(world);
+            world.resource_mut::<SaveSlots>().active = Some(name);
+        }
+        Err(error) => {
+            warn!("Failed to load slot {name}: {error}");
+            back_up_slot(&mut world.resource_mut::<PkvStore>(), &name);
+
+            let slots = world.resource::<SaveSlots>();
+            let message = match &slots.active {
+                Some(active) if *active != name => {
+                    format!(
+                        "{name} couldn't be loaded ({error}), so {active} is still being played"
+                    )
+                }
+                _ => {
+                    let fresh = next_slot_name(&slots.names);
+                    write_slot(
+                        &mut world.resource_mut::<PkvStore>(),
+                        &fresh,
+                        &SaveSlot::default(),
+                    );
+                    SaveSlot::default().apply(world);
+                    let mut slots = world.resource_mut::<SaveSlots>();
+                    slots.names.push(fresh.clone());
+                    slots.active = Some(fresh.clone());
+                    format!("{name} couldn't be loaded ({error}), so progress is saved to {fresh}")
+                }
+            };
+            world.insert_resource(SlotLoadWarning(Some(message)));
+        }
+    }
+}
+
+/// Command to add an empty slot
+pub fn create_slot() -> impl Command {
+    |world: &mut World| {
+        let name = next_slot_name(&world.resource::<SaveSlots>().names);
+        write_slot(
+            &mut world.resource_mut::<PkvStore>(),
+            &name,
+            &SaveSlot::default(),
+        );
+        world.resource_mut::<SaveSlots>().names.push(name);
+    }
+}
+
+/// Command to add a copy of the slot `name`
+pub fn copy_slot(name: String) -> impl Command {
+    move |world: &mut World| {
+        let slot = match read_slot(world.resource::<PkvStore>(), &name) {
+            Ok(slot) => slot,
+            Err(error) => {
+                warn!("Failed to copy slot {name}: {error}");
+                return;
+            }
+        };
+        let copy = copy_slot_name(&name, &world.resource::<SaveSlots>().names);
+        write_slot(&mut world.resource_mut::<PkvStore>(), &copy, &slot);
+        world.resource_mut::<SaveSlots>().names.push(copy);
+    }
+}
+
+/// Command to delete the slot `name`
+///
+/// Deleting the active slot switches to the first remaining slot, or to a new empty one if
+/// it was the last, so progress is never left unsaved.
+pub fn delete_slot(name: String) -> impl Command {
+    move |world: &mut World| {
+        if let Err(error) = world.resource_mut::<PkvStore>().remove(slot_key(&name)) {
+            warn!("Failed to delete slot {name}: {error}");
+        }
+        let mut slots = world.resource_mut::<SaveSlots>();
+        slots.names.retain(|other| *other != name);
+        if slots.active.as_ref() != Some(&name) {
+            return;
+        }
+        slots.active = None;
+
+        if slots.names.is_empty() {
+            create_slot().apply(world);
+        }
+        let next = world.resource::<SaveSlots>().names[0].clone();
+        load_slot(next).apply(world);
+    }
+}
+
+/// The keys the progression resources were stored under before save slots existed
+///
+/// They're the resources' type names at the time, so they're kept here in case the types move.
+const LEGACY_PLAYER_LEVEL_KEY: &str = "glass_half_full::game::PlayerLevel";
+const LEGACY_GAME_LEVEL_KEY: &str = "glass_half_full::game::GameLevel";
+const LEGACY_COMPLETED_YEAR_KEY: &str = "glass_half_full::game::CompletedYear";
+const LEGACY_SEASON_KEY: &str = "glass_half_full::game::Season";
+const LEGACY_CHARACTER_LAYERS_KEY: &str = "glass_half_full::game::character::CharacterLayers";
+
+/// Reads a value saved before save slots existed, in the format it was saved in
+fn legacy_value<Old: DeserializeOwned, New: From<Old> + Default>(pkv: &PkvStore, key: &str) -> New {
+    pkv.get::<Old>(key).map(New::from).unwrap_or_default()
+}
+
+/// Builds a slot from the values saved before save slots existed, or an empty slot if there
+/// weren't any
+fn legacy_slot(pkv: &PkvStore) -> SaveSlot {
+    SaveSlot {
+        player_level: legacy_value::<v1::PlayerLevel, _>(pkv, LEGACY_PLAYER_LEVEL_KEY),
+        game_level: legacy_value::<v1::GameLevel, _>(pkv, LEGACY_GAME_LEVEL_KEY),
+        completed_year: legacy_value::<v1::CompletedYear, _>(pkv, LEGACY_COMPLETED_YEAR_KEY),
+        season: legacy_value::<v1::Season, _>(pkv, LEGACY_SEASON_KEY),
+        character_layers: legacy_value::<v1::CharacterLayers, _>(pkv, LEGACY_CHARACTER_LAYERS_KEY),
+        // Granted for the progress already made once the wardrobe catalog loads
+        unlocked_items: UnlockedItems::default(),
+        playtime_secs: 0.0,
+    }
+}
+
+/// Removes the values saved before save slots existed, once they've been moved into a slot
+fn remove_legacy_values(pkv: &mut PkvStore) {
+    for key in [
+        LEGACY_PLAYER_LEVEL_KEY,
+        LEGACY_GAME_LEVEL_KEY,
+        LEGACY_COMPLETED_YEAR_KEY,
+        LEGACY_SEASON_KEY,
+        LEGACY_CHARACTER_LAYERS_KEY,
+    ] {
+        // Most players never had an older save, so a missing key isn't worth a warning
+        pkv.remove(key).ok();
+    }
+}
+
+/// Loads the last played slot, creating the first slot from any older save if there are none
+fn load_initial_slot(mut commands: Commands, pkv: Res<PkvStore>, slots: Res<SaveSlots>) {
+    if slots.names.is_empty() {
+        let name = next_slot_name(&[]);
+        let slot = legacy_slot(&pkv);
+        let key = name.clone();
+        commands.queue(move |world: &mut World| {
+            let mut pkv = world.resource_mut::<PkvStore>();
+            if write_slot(&mut pkv, &key, &slot) {
+                remove_legacy_values(&mut pkv);
+            }
+            world.resource_mut::<SaveSlots>().names.push(key);
+        });
+        commands.queue(load_slot(name));
+        return;
+    }
+
+    let name = slots
+        .active
+        .clone()
+        .filter(|active| slots.names.contains(active))
+        .unwrap_or_else(|| slots.names[0].clone());
+    commands.queue(load_slot(name));
+}
+
+fn track_playtime(time: Res<Time>, mut playtime: ResMut<Playtime>) {
+    playtime.0 += time.delta_secs_f64();
+}
+
+/// System to write the current progress to the active slot
+fn save_active_slot(
+    mut pkv: ResMut<PkvStore>,
+    slots: Res<SaveSlots>,
+    player_level: Res<PlayerLevel>,
+    game_level: Res<GameLevel>,
+    completed_year: Res<CompletedYear>,
+    season: Res<Season>,
+    character_layers: Res<CharacterLayers>,
+    unlocked_items: Res<UnlockedItems>,
+    playtime: Res<Playtime>,
+) {
+    let Some(name) = &slots.active else {
+        return;
+    };
+
+    let slot = SaveSlot {
+        player_level: *player_level,
+        game_level: *game_level,
+        completed_year: *completed_year,
+        season: *season,
+        character_layers: character_layers.clone(),
+        unlocked_items: unlocked_items.clone(),
+        playtime_secs: playtime.0,
+    };
+    write_slot(&mut pkv, name, &slot);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::character::{LayerType, LayerVariant};
+
+    /// Opens a copy of a store saved by an older version of the game, so the fixture itself is
+    /// never changed
+    fn fixture_store(name: &str, bytes: &[u8]) -> PkvStore {
+        let dir = std::env::temp_dir().join(format!("save-{name}-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("bevy_pkv.redb"), bytes).unwrap();
+        PkvStore::new_in_dir(dir)
+    }
+
+    /// Checks a slot holds the progress saved in the `v1`, `v2` and `pre_slots` fixtures
+    fn assert_fixture_progress(slot: &SaveSlot) {
+        assert_eq!(slot.player_level, PlayerLevel(3));
+        assert_eq!(slot.game_level, GameLevel(2));
+        assert_eq!(slot.completed_year, CompletedYear(true));
+        assert_eq!(slot.season, Season::Winter);
+
+        let layers = &slot.character_layers.layers;
+        assert_eq!(layers.len(), 4);
+        assert_eq!(layers[1].layer_type, LayerType::Hair);
+        assert!(matches!(
+            layers[1].variant,
+            Some(LayerVariant::HairColour(4))
+        ));
+        assert_eq!(layers[3].item_name.as_deref(), Some("santa-hat"));
+
+        // Items are granted for the seasons already completed once the catalog loads
+        assert_eq!(slot.unlocked_items, UnlockedItems::default());
+    }
+
+    #[test]
+    fn test_v1_slots_load() {
+        let pkv = fixture_store("v1", include_bytes!("fixtures/v1.redb"));
+        let slots: SaveSlots = pkv.get(std::any::type_name::<SaveSlots>()).unwrap();
+        assert_eq!(slots.names, ["Save 1", "Save 2"]);
+        assert_eq!(slots.active.as_deref(), Some("Save 1"));
+
+        let slot = read_slot(&pkv, "Save 1").unwrap();
+        assert_fixture_progress(&slot);
+        assert_eq!(slot.playtime_secs, 754.5);
+        assert_eq!(read_slot(&pkv, "Save 2").unwrap().season, Season::Summer);
+    }
+
+    #[test]
+    fn test_v2_slots_load() {
+        let pkv = fixture_store("v2", include_bytes!("fixtures/v2.redb"));
+        let slot = read_slot(&pkv, "Save 1").unwrap();
+        assert_fixture_progress(&slot);
+        assert_eq!(slot.playtime_secs, 754.5);
+    }
+
+    #[test]
+    fn test_missing_slot_is_unreadable() {
+        let pkv = fixture_store("missing", include_bytes!("fixtures/v2.redb"));
+        assert!(matches!(
+            read_slot(&pkv, "Save 2"),
+            Err(SaveError::Unreadable)
+        ));
+    }
+
+    #[test]
+    fn test_pre_slot_values_migrate() {
+        let mut pkv = fixture_store("pre-slots", include_bytes!("fixtures/pre_slots.redb"));
+        let slot = legacy_slot(&pkv);
+        assert_fixture_progress(&slot);
+        assert_eq!(slot.playtime_secs, 0.0);
+
+        remove_legacy_values(&mut pkv);
+        assert!(pkv.get::<v1::Season>(LEGACY_SEASON_KEY).is_err());
+        assert_eq!(legacy_slot(&pkv).season, Season::default());
+    }
+
+    #[test]
+    fn test_unreadable_slots_are_backed_up_as_stored() {
+        let mut pkv = fixture_store("backup", include_bytes!("fixtures/v2.redb"));
+        // Neither an envelope nor a version 1 slot, so it can't be migrated
+        let stored = (7_u8, "not a save", vec![1.5_f32, 2.5]);
+        pkv.set(slot_key("Broken"), &stored).unwrap();
+        assert!(read_slot(&pkv, "Broken").is_err());
+
+        back_up_slot(&mut pkv, "Broken");
+        let backup: (u8, String, Vec<f32>) = pkv.get(backup_key("Broken")).unwrap();
+        assert_eq!(backup, (7, "not a save".to_string(), vec![1.5, 2.5]));
+    }
+
+    #[test]
+    fn test_unreadable_active_slot_saves_to_a_fresh_slot() {
+        let mut world = World::new();
+        let mut pkv = fixture_store("load-unreadable", include_bytes!("fixtures/v2.redb"));
+        pkv.set(slot_key("Broken"), &"not a save").unwrap();
+        world.insert_resource(pkv);
+        world.insert_resource(SaveSlots {
+            names: vec!["Save 1".to_string(), "Broken".to_string()],
+            active: Some("Broken".to_string()),
+        });
+
+        load_slot("Broken".to_string()).apply(&mut world);
+
+        let slots = world.resource::<SaveSlots>();
+        assert_eq!(slots.names, ["Save 1", "Broken", "Save 2"]);
+        assert_eq!(slots.active.as_deref(), Some("Save 2"));
+        let pkv = world.resource::<PkvStore>();
+        assert!(read_slot(pkv, "Save 2").is_ok());
+        // The unreadable slot is kept as it was, with a backup
+        assert_eq!(pkv.get::<String>(slot_key("Broken")).unwrap(), "not a save");
+        assert_eq!(
+            pkv.get::<String>(backup_key("Broken")).unwrap(),
+            "not a save"
+        );
+        assert!(world.resource::<SlotLoadWarning>().0.is_some());
+    }
+
+    #[test]
+    fn test_next_slot_name_fills_gaps() {
+        assert_eq!(next_slot_name(&[]), "Save 1");
+        let names = vec!["Save 1".to_string(), "Save 3".to_string()];
+        assert_eq!(next_slot_name(&names), "Save 2");
+    }
+
+    #[test]
+    fn test_copy_slot_name_is_unique() {
+        let mut names = vec!["Save 1".to_string()];
+        assert_eq!(copy_slot_name("Save 1", &names), "Save 1 (copy)");
+        names.push("Save 1 (copy)".to_string());
+        assert_eq!(copy_slot_name("Save 1", &names), "Save 1 (copy 2)");
+    }
+
+    #[test]
+    fn test_format_playtime() {
+        assert_eq!(format_playtime(59.0), "0m");
+        assert_eq!(format_playtime(125.0), "2m");
+        assert_eq!(format_playtime(3900.0), "1h 05m");
+    }
+}
@@ -0,0 +1,357 @@
+//! Outfit share codes, for trading character looks as short copy-pasteable strings.
+//!
+//! A code is the outfit's layers packed into bytes, followed by a checksum, written in URL-safe
+//! base64 after a `GHF-` prefix. Items are stored by name rather than position in the wardrobe
+//! catalog, so codes keep working when items are added.
+
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use super::character::{CharacterLayer, CharacterLayers, LayerType, LayerVariant};
+use super::unlocks::UnlockedItems;
+use super::wardrobe::WardrobeCatalog;
+
+/// The start of every share code
+const PREFIX: &str = "GHF-";
+
+/// Version of the packed layers, written at the start of every code
+const CODE_VERSION: u8 = 1;
+
+/// Number of checksum bytes after the packed layers
+const CHECKSUM_LENGTH: usize = 4;
+
+/// Marks a layer with no item name, as names are written with their length
+const NO_ITEM_NAME: u8 = u8::MAX;
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum ShareCodeError {
+    #[error("That isn't an outfit code")]
+    Format,
+    #[error("The code has been mistyped")]
+    Checksum,
+    #[error("The code is from a newer version of the game")]
+    UnsupportedVersion(u8),
+}
+
+/// Why a layer from a share code can't be worn
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum ImportIssue {
+    #[error("{0:?} isn't in the wardrobe")]
+    Unknown(CharacterLayer),
+    #[error("{name} is locked ({requirement})")]
+    Locked { name: String, requirement: String },
+}
+
+/// Writes the outfit as a share code
+pub fn encode(layers: &CharacterLayers) -> String {
+    // An outfit has one layer of each type, but any past the count's limit are left out
+    let layers = &layers.layers[..layers.layers.len().min(u8::MAX as usize)];
+    let mut bytes = vec![CODE_VERSION, layers.len() as u8];
+    for layer in layers {
+        bytes.push(layer.layer_type as u8);
+        match &layer.item_name {
+            // Names are short, but are cut off at a character rather than allowed to overflow
+            // the length
+            Some(name) => {
+                let name = &name[..name.floor_char_boundary(NO_ITEM_NAME as usize - 1)];
+                bytes.push(name.len() as u8);
+                bytes.extend_from_slice(name.as_bytes());
+            }
+            None => bytes.push(NO_ITEM_NAME),
+        }
+        match layer.variant {
+            None => bytes.push(0),
+            Some(LayerVariant::HairColour(index)) => bytes.extend([1, index]),
+            Some(LayerVariant::ClothingColour(index)) => bytes.extend([2, index]),
+            Some(LayerVariant::Variant(number)) => bytes.extend([3, number]),
+            Some(LayerVariant::Tint(red, green, blue)) => bytes.extend([4, red, green, blue]),
+        }
+    }
+    bytes.extend_from_slice(&checksum(&bytes));
+    format!("{PREFIX}{}", encode_base64(&bytes))
+}
+
+/// Reads an outfit from a share code
+///
+/// The layers aren't checked against the wardrobe, so use [`wearable_layers`] before wearing it.
+pub fn decode(code: &str) -> Result<CharacterLayers, ShareCodeError> {
+    let code = code
+        .trim()
+        .strip_prefix(PREFIX)
+        .ok_or(ShareCodeError::Format)?;
+    let bytes = decode_base64(code).ok_or(ShareCodeError::Format)?;
+    if bytes.len() < CHECKSUM_LENGTH {
+        return Err(ShareCodeError::Format);
+    }
+    let (payload, sum) = bytes.split_at(bytes.len() - CHECKSUM_LENGTH);
+    if checksum(payload) != sum {
+        return Err(ShareCodeError::Checksum);
+    }
+
+    let mut reader = payload.iter().copied();
+    let mut next = || reader.next().ok_or(ShareCodeError::Format);
+    let version = next()?;
+    if version != CODE_VERSION {
+        return Err(ShareCodeError::UnsupportedVersion(version));
+    }
+
+    let count = next()?;
+    let mut layers = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let layer_type = *LayerType::ALL
+            .get(next()? as usize)
+            .ok_or(ShareCodeError::Format)?;
+        let item_name = match next()? {
+            NO_ITEM_NAME => None,
+            length => {
+                let name = (0..length).map(|_| next()).collect::<Result<Vec<_>, _>>()?;
+                Some(String::from_utf8(name).map_err(|_| ShareCodeError::Format)?)
+            }
+        };
+        let variant = match next()? {
+            0 => None,
+            1 => Some(LayerVariant::HairColour(next()?)),
+            2 => Some(LayerVariant::ClothingColour(next()?)),
+            3 => Some(LayerVariant::Variant(next()?)),
+            4 => Some(LayerVariant::Tint(next()?, next()?, next()?)),
+            _ => return Err(ShareCodeError::Format),
+        };
+        layers.push(CharacterLayer {
+            layer_type,
+            item_name,
+            variant,
+        });
+    }
+    if next().is_ok() {
+        return Err(ShareCodeError::Format);
+    }
+
+    Ok(CharacterLayers { layers })
+}
+
+/// Splits imported layers into those that can be worn and the issues with the rest
+///
+/// Only the first layer of each type is kept, as the character wears one item per layer.
+pub fn wearable_layers(
+    catalog: &WardrobeCatalog,
+    unlocked: &UnlockedItems,
+    layers: CharacterLayers,
+) -> (Vec<CharacterLayer>, Vec<ImportIssue>) {
+    let mut wearable: Vec<CharacterLayer> = vec![];
+    let mut issues = vec![];
+    for layer in layers.layers {
+        if wearable
+            .iter()
+            .any(|worn| worn.layer_type == layer.layer_type)
+        {
+            continue;
+        }
+        let Some(item) = catalog
+            .find(&layer)
+            .filter(|_| catalog.texture_path(&layer).is_some())
+        else {
+            issues.push(ImportIssue::Unknown(layer));
+            continue;
+        };
+        if !unlocked.is_unlocked(catalog, &layer) {
+            issues.push(ImportIssue::Locked {
+                name: item.display_name.clone(),
+                requirement: item
+                    .unlock
+                    .map(|milestone| milestone.requirement())
+                    .unwrap_or_default(),
+            });
+            continue;
+        }
+        wearable.push(layer);
+    }
+    (wearable, issues)
+}
+
+fn checksum(bytes: &[u8]) -> [u8; CHECKSUM_LENGTH] {
+    let hash = Sha256::digest(bytes);
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+/// Writes bytes as unpadded URL-safe base64
+fn encode_base64(bytes: &[u8]) -> String {
+    let mut text = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let group = chunk.iter().enumerate().fold(0u32, |group, (i, byte)| {
+            group | ((*byte as u32) << (16 - i * 8))
+        });
+        for i in 0..=chunk.len() {
+            let index = (group >> (18 - i * 6)) & 0x3f;
+            text.push(BASE64_ALPHABET[index as usize] as char);
+        }
+    }
+    text
+}
+
+/// Reads unpadded URL-safe base64, or none if it isn't valid
+fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    let digits = text
+        .bytes()
+        .map(|c| BASE64_ALPHABET.iter().position(|&digit| digit == c))
+        .collect::<Option<Vec<_>>>()?;
+    let mut bytes = Vec::with_capacity(digits.len() * 3 / 4);
+    for chunk in digits.chunks(4) {
+        if chunk.len() < 2 {
+            return None;
+        }
+        let group = chunk.iter().enumerate().fold(0u32, |group, (i, digit)| {
+            group | ((*digit as u32) << (18 - i * 6))
+        });
+        for i in 0..chunk.len() - 1 {
+            bytes.push((group >> (16 - i * 8)) as u8);
+        }
+    }
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::wardrobe::bundled_catalog;
+
+    fn tinted_outfit() -> CharacterLayers {
+        let mut layers = CharacterLayers::default();
+        layers.layers.push(CharacterLayer {
+            layer_type: LayerType::Headwear,
+            item_name: Some("hat".to_string()),
+            variant: Some(LayerVariant::Tint(200, 40, 90)),
+        });
+        layers
+    }
+
+    #[test]
+    fn test_codes_round_trip() {
+        let outfit = tinted_outfit();
+        let code = encode(&outfit);
+        assert!(code.starts_with(PREFIX));
+        assert_eq!(decode(&code).unwrap().layers, outfit.layers);
+        // Codes pasted with surrounding whitespace still work
+        assert_eq!(decode(&format!(" {code}\n")).unwrap().layers, outfit.layers);
+    }
+
+    #[test]
+    fn test_long_names_are_cut_off_between_characters() {
+        let mut outfit = tinted_outfit();
+        // 255 bytes, so the name's byte limit falls inside the last two-byte character
+        let name = format!("a{}", "é".repeat(127));
+        outfit.layers[0].item_name = Some(name.clone());
+        let decoded = decode(&encode(&outfit)).unwrap();
+        assert_eq!(
+            decoded.layers[0].item_name.as_deref(),
+            Some(&name[..name.len() - 2])
+        );
+
+        outfit.layers[0].item_name = Some("chapeau-été".to_string());
+        assert_eq!(decode(&encode(&outfit)).unwrap().layers, outfit.layers);
+    }
+
+    #[test]
+    fn test_layer_count_is_capped() {
+        let mut outfit = tinted_outfit();
+        outfit.layers = vec![outfit.layers[0].clone(); 300];
+        let decoded = decode(&encode(&outfit)).unwrap();
+        assert_eq!(decoded.layers.len(), u8::MAX as usize);
+    }
+
+    #[test]
+    fn test_base64_round_trips() {
+        for length in 0..8_u8 {
+            let bytes: Vec<u8> = (0..length)
+                .map(|i| i.wrapping_mul(37).wrapping_add(200))
+                .collect();
+            assert_eq!(decode_base64(&encode_base64(&bytes)).unwrap(), bytes);
+        }
+        assert!(decode_base64("not base64!").is_none());
+    }
+
+    #[test]
+    fn test_mistyped_codes_are_rejected() {
+        let code = encode(&tinted_outfit());
+        let mut mistyped = code.clone().into_bytes();
+        let last = mistyped.len() - 10;
+        mistyped[last] = if mistyped[last] == b'A' { b'B' } else { b'A' };
+        let mistyped = String::from_utf8(mistyped).unwrap();
+
+        assert_eq!(decode(&mistyped).err(), Some(ShareCodeError::Checksum));
+        assert_eq!(
+            decode(&code[PREFIX.len()..]).err(),
+            Some(ShareCodeError::Format)
+        );
+        assert_eq!(decode("GHF-").err(), Some(ShareCodeError::Format));
+    }
+
+    #[test]
+    fn test_newer_codes_are_rejected() {
+        let mut bytes = vec![CODE_VERSION + 1, 0];
+        bytes.extend_from_slice(&checksum(&bytes));
+        let code = format!("{PREFIX}{}", encode_base64(&bytes));
+        assert_eq!(
+            decode(&code).err(),
+            Some(ShareCodeError::UnsupportedVersion(CODE_VERSION + 1))
+        );
+    }
+
+    #[test]
+    fn test_unknown_and_locked_layers_are_reported() {
+        let catalog = bundled_catalog();
+        let unlocked = UnlockedItems::default();
+
+        let unknown = CharacterLayer {
+            layer_type: LayerType::Gloves,
+            item_name: Some("oven-mitts".to_string()),
+            variant: None,
+        };
+        let outfit = CharacterLayers {
+            layers: vec![unknown.clone()],
+        };
+        let (wearable, issues) = wearable_layers(&catalog, &unlocked, outfit);
+        assert!(wearable.is_empty());
+        assert_eq!(issues, vec![ImportIssue::Unknown(unknown)]);
+
+        let locked = catalog
+            .items
+            .iter()
+            .find(|item| item.unlock.is_some())
+            .unwrap();
+        let outfit = CharacterLayers {
+            layers: vec![locked.preview()],
+        };
+        let (wearable, issues) = wearable_layers(&catalog, &unlocked, outfit);
+        assert!(wearable.is_empty());
+        assert!(matches!(
+            &issues[..],
+            [ImportIssue::Locked { name, .. }] if *name == locked.display_name
+        ));
+    }
+
+    #[test]
+    fn test_one_layer_of_each_type_is_kept() {
+        let catalog = bundled_catalog();
+        let mut outfit = CharacterLayers::default();
+        let first_clothes = outfit
+            .layers
+            .iter()
+            .find(|layer| layer.layer_type == LayerType::Clothes)
+            .cloned()
+            .unwrap();
+        let mut second_clothes = first_clothes.clone();
+        second_clothes.item_name = Some("oven-mitts".to_string());
+        outfit.layers.push(second_clothes);
+
+        let (wearable, issues) = wearable_layers(&catalog, &UnlockedItems::default(), outfit);
+        let clothes: Vec<_> = wearable
+            .iter()
+            .filter(|layer| layer.layer_type == LayerType::Clothes)
+            .collect();
+        assert_eq!(clothes, vec![&first_clothes]);
+        assert!(issues.is_empty());
+    }
+}
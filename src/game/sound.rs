@@ -0,0 +1,242 @@
+//! Gameplay sound effects, and looping ambience from fires and waterfalls.
+//!
+//! Gameplay systems write a [`SoundEffectMessage`] when something audible happens, and this
+//! module plays it. Ambience is spatial, so it pans and fades with distance from the
+//! [`PixelCamera`](crate::pixel_camera::PixelCamera), which follows the character.
+
+use bevy::audio::SpatialScale;
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+
+use super::level::objects::{Fire, Water, WaterType};
+use super::level::{DeathCause, PlayerDeathMessage};
+use crate::audio::{SoundEffect, sound_effect};
+use crate::screens::Screen;
+
+pub fn plugin(app: &mut App) {
+    app.register_type::<Ambience>();
+    app.add_message::<SoundEffectMessage>();
+    app.init_resource::<GameSoundAssets>();
+    app.add_systems(
+        Update,
+        (
+            sound_death,
+            play_sound_effects,
+            sync_fire_ambience,
+            add_waterfall_ambience,
+        )
+            .chain()
+            .run_if(in_state(Screen::Gameplay)),
+    );
+}
+
+/// Scale from world pixels to spatial audio units, so a sound is at full volume within about
+/// 100 pixels of the listener
+const AUDIO_SCALE: f32 = 1.0 / 100.0;
+
+/// A sound played in response to something happening in gameplay
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+pub enum GameSound {
+    BucketFill,
+    Pour,
+    SnowScoop,
+    Melt,
+    Extinguish,
+    FireTouch,
+    Death,
+    Jump,
+    Land,
+    ContainerFull,
+}
+
+impl GameSound {
+    pub const ALL: [GameSound; 10] = [
+        GameSound::BucketFill,
+        GameSound::Pour,
+        GameSound::SnowScoop,
+        GameSound::Melt,
+        GameSound::Extinguish,
+        GameSound::FireTouch,
+        GameSound::Death,
+        GameSound::Jump,
+        GameSound::Land,
+        GameSound::ContainerFull,
+    ];
+
+    fn path(self) -> &'static str {
+        match self {
+            GameSound::BucketFill => "audio/sound_effects/bucket_fill.eogg",
+            GameSound::Pour => "audio/sound_effects/pour.eogg",
+            GameSound::SnowScoop => "audio/sound_effects/snow_scoop.eogg",
+            GameSound::Melt => "audio/sound_effects/melt.eogg",
+            GameSound::Extinguish => "audio/sound_effects/extinguish.eogg",
+            GameSound::FireTouch => "audio/sound_effects/fire_touch.eogg",
+            GameSound::Death => "audio/sound_effects/death.eogg",
+            GameSound::Jump => "audio/sound_effects/jump.eogg",
+            GameSound::Land => "audio/sound_effects/land.eogg",
+            GameSound::ContainerFull => "audio/sound_effects/container_full.eogg",
+        }
+    }
+}
+
+/// Message written when a gameplay sound should be played
+#[derive(Message, Debug, Clone, Copy)]
+pub struct SoundEffectMessage {
+    pub sound: GameSound,
+}
+
+impl SoundEffectMessage {
+    pub fn new(sound: GameSound) -> Self {
+        Self { sound }
+    }
+}
+
+/// The crackle of a lit fire
+const FIRE_LOOP: &str = "audio/sound_effects/fire_loop.eogg";
+
+/// The rush of water at the base of a waterfall
+const WATERFALL_LOOP: &str = "audio/sound_effects/waterfall_loop.eogg";
+
+/// Resource holding the gameplay sounds, so they stay loaded between plays
+///
+/// They aren't waited for on the loading screen, as they're only needed once something
+/// happens in gameplay.
+#[derive(Resource, Debug)]
+struct GameSoundAssets {
+    sounds: HashMap<GameSound, Handle<AudioSource>>,
+    fire: Handle<AudioSource>,
+    waterfall: Handle<AudioSource>,
+}
+
+impl FromWorld for GameSoundAssets {
+    fn from_world(world: &mut World) -> Self {
+        let assets = world.resource::<AssetServer>();
+        Self {
+            sounds: GameSound::ALL
+                .into_iter()
+                .map(|sound| (sound, assets.load(sound.path())))
+                .collect(),
+            fire: assets.load(FIRE_LOOP),
+            waterfall: assets.load(WATERFALL_LOOP),
+        }
+    }
+}
+
+/// Marker component for a looping sound attached to a fire or waterfall
+#[derive(Component, Debug, Reflect)]
+#[reflect(Component)]
+struct Ambience;
+
+/// A looping sound that pans and fades with its distance from the listener
+fn ambience(handle: Handle<AudioSource>) -> impl Bundle {
+    (
+        Name::new("Ambience"),
+        Ambience,
+        AudioPlayer(handle),
+        PlaybackSettings::LOOP
+            .with_spatial(true)
+            .with_spatial_scale(SpatialScale::new_2d(AUDIO_SCALE)),
+        SoundEffect,
+        Transform::default(),
+    )
+}
+
+/// System to play a sound when the player dies
+fn sound_death(
+    mut death_reader: MessageReader<PlayerDeathMessage>,
+    mut sound_writer: MessageWriter<SoundEffectMessage>,
+) {
+    if let Some(death) = death_reader.read().last() {
+        sound_writer.write(SoundEffectMessage::new(match death.cause {
+            DeathCause::Fire => GameSound::FireTouch,
+            DeathCause::Fell => GameSound::Death,
+        }));
+    }
+}
+
+/// System to play the sounds written this frame, each at most once
+fn play_sound_effects(
+    mut commands: Commands,
+    mut sound_reader: MessageReader<SoundEffectMessage>,
+    assets: Res<GameSoundAssets>,
+) {
+    let mut played = vec![];
+    for message in sound_reader.read() {
+        if played.contains(&message.sound) {
+            continue;
+        }
+        played.push(message.sound);
+        if let Some(handle) = assets.sounds.get(&message.sound) {
+            commands.spawn((Name::new("Sound Effect"), sound_effect(handle.clone())));
+        }
+    }
+}
+
+/// System to start a fire's crackle when it's lit, and stop it when it's put out
+fn sync_fire_ambience(
+    mut commands: Commands,
+    assets: Res<GameSoundAssets>,
+    fire_query: Query<(Entity, &Fire, Option<&Children>), Changed<Fire>>,
+    ambience_query: Query<(), With<Ambience>>,
+) {
+    for (entity, fire, children) in &fire_query {
+        let playing = children
+            .into_iter()
+            .flat_map(|children| children.iter())
+            .find(|child| ambience_query.contains(*child));
+
+        match (fire.is_active(), playing) {
+            (true, None) => {
+                commands
+                    .entity(entity)
+                    .with_child(ambience(assets.fire.clone()));
+            }
+            (false, Some(playing)) => commands.entity(playing).despawn(),
+            _ => {}
+        }
+    }
+}
+
+/// System to add the sound of falling water to the base of each waterfall
+fn add_waterfall_ambience(
+    mut commands: Commands,
+    assets: Res<GameSoundAssets>,
+    water_query: Query<(Entity, &Water), Added<Water>>,
+) {
+    for (entity, water) in &water_query {
+        if water.water_type == WaterType::WaterfallBase {
+            commands
+                .entity(entity)
+                .with_child(ambience(assets.waterfall.clone()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::asset_format::bundled_manifest;
+
+    #[test]
+    fn test_each_sound_has_its_own_file() {
+        let mut paths: Vec<_> = GameSound::ALL.into_iter().map(GameSound::path).collect();
+        paths.sort();
+        paths.dedup();
+        assert_eq!(paths.len(), GameSound::ALL.len());
+    }
+
+    #[test]
+    fn test_bundled_sounds_exist() {
+        let manifest = bundled_manifest();
+        let paths = GameSound::ALL.into_iter().map(GameSound::path);
+        for path in paths.chain([FIRE_LOOP, WATERFALL_LOOP]) {
+            assert!(Path::new("assets").join(path).exists(), "{path} is missing");
+            assert!(
+                manifest.hashes.contains_key(path),
+                "{path} isn't in the manifest"
+            );
+        }
+    }
+}
@@ -1,6 +1,9 @@
 use derive_more::From;
 
-use crate::{PausableSystems, asset_tracking::LoadResource, game::Season, screens::Screen};
+use crate::{
+    PausableSystems, asset_tracking::LoadResource, game::Season, game::replay::PlayedLevel,
+    screens::Screen,
+};
 use bevy::platform::collections::{HashMap, HashSet};
 use bevy::prelude::*;
 
@@ -333,7 +336,7 @@ pub fn update_dual_tiles(
     terrain_query: Query<(Ref<TerrainTile>, Ref<GridPosition>), Without<DualTile>>,
     dual_query: Query<(Entity, &GridPosition), With<DualTile>>,
     tileset_atlases: If<Res<TilesetAtlases>>,
-    season: Res<Season>,
+    played: PlayedLevel,
 ) {
     // Check if any terrain tiles changed
     let has_changes = terrain_query
@@ -414,7 +417,7 @@ pub fn update_dual_tiles(
                 position,
                 dual_tile,
                 Sprite::from_atlas_image(
-                    tileset_atlases.get_texture(*season),
+                    tileset_atlases.get_texture(played.season()),
                     TextureAtlas {
                         layout: tileset_atlases.layout.clone(),
                         index: atlas_index,
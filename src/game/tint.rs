@@ -0,0 +1,67 @@
+//! Recolouring a character layer's sprite sheet to any colour.
+//!
+//! Tinted layers ([`LayerVariant::Tint`]) are drawn from a single greyscale sheet per item,
+//! shaded so its mid-tones are mid-grey. The sheet is recoloured by mapping its shading onto
+//! the tint as the character's layers are baked together. The recolour happens on the CPU
+//! rather than in a material, since the baked sheet is drawn as one sprite and exported as an
+//! avatar.
+//!
+//! [`LayerVariant::Tint`]: super::character::LayerVariant::Tint
+
+use bevy::prelude::*;
+
+/// Recolours a pixel by mapping its shading onto `tint`
+///
+/// Dark shades blend from black to the tint, and light shades from the tint to white, so
+/// mid-grey in the base becomes the tint itself.
+pub fn recolour(base: Srgba, tint: LinearRgba) -> Srgba {
+    let base = LinearRgba::from(base);
+    let luminance = 0.2126 * base.red + 0.7152 * base.green + 0.0722 * base.blue;
+    // Shade in perceptual space, so mid-tones land on the tint itself
+    let shade = luminance.powf(1.0 / 2.2);
+    let to_tint = (shade * 2.0).clamp(0.0, 1.0);
+    let to_white = (shade * 2.0 - 1.0).clamp(0.0, 1.0);
+    let channel = |tint: f32| {
+        let shaded = tint * to_tint;
+        shaded + (1.0 - shaded) * to_white
+    };
+
+    LinearRgba::new(
+        channel(tint.red),
+        channel(tint.green),
+        channel(tint.blue),
+        base.alpha * tint.alpha,
+    )
+    .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: Srgba, expected: Srgba) {
+        let difference = actual.to_vec4() - expected.to_vec4();
+        assert!(
+            difference.abs().max_element() < 0.01,
+            "{actual:?} != {expected:?}"
+        );
+    }
+
+    #[test]
+    fn test_recolour_maps_shading_onto_tint() {
+        let tint = LinearRgba::from(Srgba::rgb(0.8, 0.2, 0.4));
+        assert_close(recolour(Srgba::BLACK, tint), Srgba::BLACK);
+        assert_close(recolour(Srgba::WHITE, tint), Srgba::WHITE);
+        // Mid-grey is the tint itself
+        let level = 0.5_f32.powf(2.2);
+        let mid_grey = Srgba::from(LinearRgba::rgb(level, level, level));
+        assert_close(recolour(mid_grey, tint), Srgba::rgb(0.8, 0.2, 0.4));
+    }
+
+    #[test]
+    fn test_recolour_keeps_transparency() {
+        let tint = LinearRgba::from(Srgba::rgb(0.8, 0.2, 0.4));
+        let pixel = recolour(Srgba::new(0.5, 0.5, 0.5, 0.0), tint);
+        assert_eq!(pixel.alpha, 0.0);
+    }
+}
@@ -0,0 +1,298 @@
+//! On-screen touch controls for playing on phones and tablets.
+//!
+//! The overlay feeds the same [`ActionState<Action>`] as the keyboard and gamepad, so gameplay
+//! (and replay recording) doesn't need to know where its input came from.
+
+use bevy::{picking::pointer::PointerButton, prelude::*};
+use leafwing_input_manager::{buttonlike::ButtonState, plugin::InputManagerSystem, prelude::*};
+
+use super::controls::{Action, InputDevice};
+use crate::{screens::Screen, theme::palette::*};
+
+pub fn plugin(app: &mut App) {
+    app.add_observer(on_stick_drag);
+    app.add_observer(on_stick_release);
+    app.add_observer(on_button_press);
+    app.add_observer(on_button_release);
+    app.add_observer(on_button_out);
+    app.add_systems(OnEnter(Screen::Gameplay), spawn_touch_controls);
+    app.add_systems(
+        PreUpdate,
+        apply_touch_input
+            .after(InputManagerSystem::ManualControl)
+            .before(super::replay::play_back_frame)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+    app.add_systems(
+        Update,
+        (show_touch_controls, move_stick_knob).run_if(in_state(Screen::Gameplay)),
+    );
+}
+
+/// Diameter of the virtual stick's base
+const STICK_SIZE: f32 = 160.0;
+
+/// Diameter of the virtual stick's knob
+const KNOB_SIZE: f32 = 64.0;
+
+/// Diameter of the action buttons
+const TOUCH_BUTTON_SIZE: f32 = 96.0;
+
+/// How far the stick can be dragged from its centre, in logical pixels
+const STICK_RADIUS: f32 = (STICK_SIZE - KNOB_SIZE) / 2.0;
+
+/// Marker component for the root of the touch overlay
+#[derive(Component)]
+struct TouchControls;
+
+/// Component for the virtual stick, holding how far it is pushed in each direction
+#[derive(Component, Debug, Default)]
+struct TouchStick(Vec2);
+
+/// Marker component for the part of the virtual stick that follows the finger
+#[derive(Component)]
+struct TouchStickKnob;
+
+/// Component for an on-screen button that holds down an action while touched
+#[derive(Component, Debug)]
+struct TouchButton {
+    action: Action,
+    held: bool,
+}
+
+fn spawn_touch_controls(mut commands: Commands, device: Res<InputDevice>) {
+    commands.spawn((
+        Name::new("Touch Controls"),
+        TouchControls,
+        Node {
+            position_type: PositionType::Absolute,
+            width: percent(100),
+            height: percent(100),
+            padding: UiRect::all(px(30)),
+            justify_content: JustifyContent::SpaceBetween,
+            align_items: AlignItems::End,
+            ..default()
+        },
+        if *device == InputDevice::Touch {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        },
+        // Only the controls themselves should block touches
+        Pickable::IGNORE,
+        DespawnOnExit(Screen::Gameplay),
+        children![
+            touch_stick(),
+            (
+                Name::new("Touch Buttons"),
+                Node {
+                    column_gap: px(20),
+                    align_items: AlignItems::End,
+                    ..default()
+                },
+                Pickable::IGNORE,
+                children![
+                    touch_button("Use", Action::Use),
+                    touch_button("Jump", Action::Jump),
+                ],
+            ),
+        ],
+    ));
+    commands.spawn((
+        Name::new("Touch Pause"),
+        TouchControls,
+        Node {
+            position_type: PositionType::Absolute,
+            top: px(20),
+            right: px(20),
+            ..default()
+        },
+        if *device == InputDevice::Touch {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        },
+        DespawnOnExit(Screen::Gameplay),
+        children![touch_button("II", Action::Menu)],
+    ));
+}
+
+fn touch_stick() -> impl Bundle {
+    (
+        Name::new("Touch Stick"),
+        TouchStick::default(),
+        Node {
+            width: px(STICK_SIZE),
+            height: px(STICK_SIZE),
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        BorderRadius::MAX,
+        BackgroundColor(TOUCH_BACKGROUND.with_alpha(0.3)),
+        children![(
+            Name::new("Touch Stick Knob"),
+            TouchStickKnob,
+            Node {
+                width: px(KNOB_SIZE),
+                height: px(KNOB_SIZE),
+                ..default()
+            },
+            BorderRadius::MAX,
+            BackgroundColor(TOUCH_BACKGROUND.with_alpha(0.6)),
+            Pickable::IGNORE,
+        )],
+    )
+}
+
+fn touch_button(text: &'static str, action: Action) -> impl Bundle {
+    (
+        Name::new(format!("Touch {action}")),
+        TouchButton {
+            action,
+            held: false,
+        },
+        Node {
+            width: px(TOUCH_BUTTON_SIZE),
+            height: px(TOUCH_BUTTON_SIZE),
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        BorderRadius::MAX,
+        BackgroundColor(TOUCH_BACKGROUND.with_alpha(0.4)),
+        children![(
+            Name::new("Touch Button Text"),
+            Text(text.to_string()),
+            TextFont::from_font_size(24.0),
+            TextColor(*BUTTON_TEXT),
+            Pickable::IGNORE,
+        )],
+    )
+}
+
+/// Returns the stick's value for a drag of `distance` logical pixels from its centre
+///
+/// UI coordinates grow downwards, so the y axis is flipped to match the gamepad stick.
+fn stick_value(distance: Vec2) -> Vec2 {
+    (Vec2::new(distance.x, -distance.y) / STICK_RADIUS).clamp_length_max(1.0)
+}
+
+fn on_stick_drag(drag: On<Pointer<Drag>>, mut stick_query: Query<&mut TouchStick>) {
+    if let Ok(mut stick) = stick_query.get_mut(drag.entity) {
+        stick.0 = stick_value(drag.distance);
+    }
+}
+
+fn on_stick_release(drag_end: On<Pointer<DragEnd>>, mut stick_query: Query<&mut TouchStick>) {
+    if let Ok(mut stick) = stick_query.get_mut(drag_end.entity) {
+        stick.0 = Vec2::ZERO;
+    }
+}
+
+fn on_button_press(press: On<Pointer<Press>>, mut button_query: Query<&mut TouchButton>) {
+    if press.button == PointerButton::Primary
+        && let Ok(mut button) = button_query.get_mut(press.entity)
+    {
+        button.held = true;
+    }
+}
+
+fn on_button_release(release: On<Pointer<Release>>, mut button_query: Query<&mut TouchButton>) {
+    if let Ok(mut button) = button_query.get_mut(release.entity) {
+        button.held = false;
+    }
+}
+
+/// Releases a button when the finger slides off it, or is covered by a menu
+fn on_button_out(out: On<Pointer<Out>>, mut button_query: Query<&mut TouchButton>) {
+    if let Ok(mut button) = button_query.get_mut(out.entity) {
+        button.held = false;
+    }
+}
+
+/// System to add the touch controls' state on top of the keyboard and gamepad input
+fn apply_touch_input(
+    mut action_query: Query<&mut ActionState<Action>>,
+    stick_query: Query<&TouchStick>,
+    button_query: Query<&TouchButton>,
+) {
+    let Ok(mut action_state) = action_query.single_mut() else {
+        return;
+    };
+
+    if let Ok(stick) = stick_query.single()
+        && stick.0 != Vec2::ZERO
+    {
+        action_state.set_axis_pair(&Action::Run, stick.0);
+    }
+
+    for button in button_query.iter().filter(|button| button.held) {
+        hold_action(&mut action_state, &button.action);
+    }
+}
+
+/// Holds down `action`, keeping it pressed rather than re-pressing it every frame
+///
+/// The input map releases actions whose keys aren't held before this runs, which would
+/// otherwise make a held touch button look like a fresh press each frame.
+fn hold_action(action_state: &mut ActionState<Action>, action: &Action) {
+    if action_state.just_released(action) {
+        if let Some(button_data) = action_state.button_data_mut(action) {
+            button_data.state = ButtonState::Pressed;
+            button_data.value = 1.0;
+        }
+    } else {
+        action_state.press(action);
+    }
+}
+
+/// System to show the overlay only while touch is the active input device
+fn show_touch_controls(
+    device: Res<InputDevice>,
+    mut controls_query: Query<&mut Visibility, With<TouchControls>>,
+) {
+    if !device.is_changed() {
+        return;
+    }
+
+    for mut visibility in &mut controls_query {
+        *visibility = if *device == InputDevice::Touch {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+/// System to move the stick's knob under the finger
+fn move_stick_knob(
+    stick_query: Query<&TouchStick, Changed<TouchStick>>,
+    mut knob_query: Query<&mut UiTransform, With<TouchStickKnob>>,
+) {
+    let Ok(stick) = stick_query.single() else {
+        return;
+    };
+
+    for mut transform in &mut knob_query {
+        transform.translation = Val2::px(stick.0.x * STICK_RADIUS, -stick.0.y * STICK_RADIUS);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stick_value_is_clamped_and_flipped() {
+        assert_eq!(stick_value(Vec2::ZERO), Vec2::ZERO);
+        assert_eq!(stick_value(Vec2::new(STICK_RADIUS, 0.0)), Vec2::X);
+        // Dragging up the screen pushes the stick up
+        assert_eq!(
+            stick_value(Vec2::new(0.0, -STICK_RADIUS / 2.0)),
+            Vec2::new(0.0, 0.5)
+        );
+        // Dragging past the edge is the same as dragging to it
+        assert_eq!(stick_value(Vec2::new(STICK_RADIUS * 4.0, 0.0)), Vec2::X);
+    }
+}
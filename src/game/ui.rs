@@ -1,16 +1,30 @@
+use super::controls::{Action, InputDevice, binding_label};
+use super::interactions::{InteractionKind, InteractionTarget};
 use super::level::BucketContent;
-use super::{GameLevel, Season};
+use super::level::objects::{Container, ContainerState};
+use super::replay::PlayedLevel;
+use crate::Pause;
 use crate::screens::Screen;
 use bevy::prelude::*;
+use leafwing_input_manager::prelude::*;
 
 pub fn plugin(app: &mut App) {
     app.add_systems(
         OnEnter(Screen::Gameplay),
-        (spawn_bucket_ui, spawn_level_info_ui),
+        (
+            spawn_bucket_ui,
+            spawn_level_info_ui,
+            spawn_interaction_prompt,
+        ),
     );
     app.add_systems(
         Update,
-        (update_bucket_ui, update_level_info_ui).run_if(in_state(Screen::Gameplay)),
+        (
+            update_bucket_ui,
+            update_level_info_ui,
+            update_interaction_prompt,
+        )
+            .run_if(in_state(Screen::Gameplay)),
     );
 }
 
@@ -90,7 +104,7 @@ fn update_bucket_ui(
 struct LevelInfoText;
 
 /// Spawns the level info UI in the top-right corner
-fn spawn_level_info_ui(mut commands: Commands, season: Res<Season>, game_level: Res<GameLevel>) {
+fn spawn_level_info_ui(mut commands: Commands, played: PlayedLevel) {
     commands
         .spawn((
             Name::new("Level Info UI Root"),
@@ -109,7 +123,11 @@ fn spawn_level_info_ui(mut commands: Commands, season: Res<Season>, game_level:
             parent.spawn((
                 Name::new("Level Info Text"),
                 LevelInfoText,
-                Text::new(format!("{}\nLevel {}", *season, game_level.0)),
+                Text::new(format!(
+                    "{}\nLevel {}",
+                    played.season(),
+                    played.game_level().0
+                )),
                 TextFont {
                     font_size: 20.0,
                     ..default()
@@ -120,16 +138,97 @@ fn spawn_level_info_ui(mut commands: Commands, season: Res<Season>, game_level:
 }
 
 /// Updates the level info text when season or level changes
-fn update_level_info_ui(
-    season: Res<Season>,
-    game_level: Res<GameLevel>,
-    mut query: Query<&mut Text, With<LevelInfoText>>,
-) {
-    if !season.is_changed() && !game_level.is_changed() {
+fn update_level_info_ui(played: PlayedLevel, mut query: Query<&mut Text, With<LevelInfoText>>) {
+    if !played.is_changed() {
         return;
     }
 
     for mut text in query.iter_mut() {
-        **text = format!("{}\nLevel {}", *season, game_level.0);
+        **text = format!("{}\nLevel {}", played.season(), played.game_level().0);
+    }
+}
+
+/// Marker component for the world-space interaction prompt
+#[derive(Component)]
+struct InteractionPrompt;
+
+/// Height above the target object at which the prompt is drawn
+const PROMPT_OFFSET: f32 = 28.0;
+
+/// Spawns the (initially hidden) prompt shown over the current interaction target
+fn spawn_interaction_prompt(mut commands: Commands) {
+    commands.spawn((
+        Name::new("Interaction Prompt"),
+        InteractionPrompt,
+        Text2d::new(""),
+        TextFont {
+            font_size: 12.0,
+            ..default()
+        },
+        TextColor(Color::WHITE),
+        Transform::from_xyz(0.0, 0.0, 50.0),
+        Visibility::Hidden,
+        DespawnOnExit(Screen::Gameplay),
+    ));
+}
+
+/// Describes what an interaction will do, e.g. "Pour (1/2)"
+fn prompt_text(kind: InteractionKind, container: Option<&Container>) -> String {
+    match kind {
+        InteractionKind::FillBucket => "Fill bucket".to_string(),
+        InteractionKind::ScoopSnow => "Scoop snow".to_string(),
+        InteractionKind::MeltSnow => "Melt snow".to_string(),
+        InteractionKind::Extinguish => "Extinguish".to_string(),
+        InteractionKind::Pour => {
+            let fill = match container.map(|container| container.state) {
+                Some(ContainerState::HalfFull | ContainerState::Full) => 2,
+                _ => 1,
+            };
+            format!("Pour ({}/2)", fill)
+        }
+    }
+}
+
+/// Moves the prompt over the current interaction target and updates its text
+fn update_interaction_prompt(
+    target: Res<InteractionTarget>,
+    pause: Res<State<Pause>>,
+    device: Res<InputDevice>,
+    input_map_query: Query<&InputMap<Action>>,
+    target_query: Query<(&Transform, Option<&Container>), Without<InteractionPrompt>>,
+    mut prompt_query: Query<
+        (&mut Text2d, &mut Transform, &mut Visibility),
+        With<InteractionPrompt>,
+    >,
+) {
+    let Ok((mut text, mut transform, mut visibility)) = prompt_query.single_mut() else {
+        return;
+    };
+
+    let target = target
+        .0
+        .filter(|_| !pause.get().0)
+        .and_then(|(entity, kind)| Some((kind, target_query.get(entity).ok()?)));
+
+    let Some((kind, (target_transform, container))) = target else {
+        visibility.set_if_neq(Visibility::Hidden);
+        return;
+    };
+
+    let description = prompt_text(kind, container);
+    let prompt = match input_map_query
+        .single()
+        .ok()
+        .and_then(|input_map| binding_label(input_map, Action::Use, *device))
+    {
+        Some(label) => format!("[{}] {}", label, description),
+        None => description,
+    };
+
+    if text.0 != prompt {
+        text.0 = prompt;
     }
+    transform.translation.x = target_transform.translation.x;
+    transform.translation.y = target_transform.translation.y + PROMPT_OFFSET;
+    visibility.set_if_neq(Visibility::Inherited);
 }
@@ -0,0 +1,174 @@
+//! The clothing items unlocked by completing seasons, and which ones each save has unlocked.
+//!
+//! Which milestone unlocks each item is set in the wardrobe catalog.
+
+use std::collections::BTreeSet;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::character::{CharacterLayer, LayerType};
+use super::wardrobe::{Wardrobe, WardrobeAssets, WardrobeCatalog, WardrobeItem};
+use super::{PlayerLevel, Season};
+
+pub fn plugin(app: &mut App) {
+    app.init_resource::<UnlockedItems>();
+    app.add_systems(
+        Update,
+        grant_unlocks.run_if(resource_changed::<PlayerLevel>.or(resource_added::<WardrobeAssets>)),
+    );
+}
+
+/// Completing every level of a season, in a given year of play
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+pub struct Milestone {
+    pub season: Season,
+    /// 1 for the first year, 2 for the year after
+    pub year: u8,
+}
+
+impl Milestone {
+    /// The milestone reached by completing `season`
+    pub fn completed(season: Season, completed_year: bool) -> Self {
+        Self {
+            season,
+            year: if completed_year { 2 } else { 1 },
+        }
+    }
+
+    /// The player level reached along with this milestone
+    pub fn player_level(&self) -> u8 {
+        let season_index = match self.season {
+            Season::Summer => 1,
+            Season::Autumn => 2,
+            Season::Winter => 3,
+            Season::Spring => 4,
+        };
+        (self.year - 1) * 4 + season_index
+    }
+
+    /// Describes what the player needs to do, e.g. "Complete Autumn"
+    pub fn requirement(&self) -> String {
+        match self.year {
+            1 => format!("Complete {}", self.season),
+            year => format!("Complete {} in year {}", self.season, year),
+        }
+    }
+}
+
+/// Returns the items unlocked by reaching `milestone`
+pub fn unlocks_at(
+    catalog: &WardrobeCatalog,
+    milestone: Milestone,
+) -> impl Iterator<Item = &WardrobeItem> {
+    catalog
+        .items
+        .iter()
+        .filter(move |item| item.unlock == Some(milestone))
+}
+
+/// Returns the items unlocked by every milestone up to `player_level`
+fn reached_unlocks(
+    catalog: &WardrobeCatalog,
+    player_level: PlayerLevel,
+) -> impl Iterator<Item = &WardrobeItem> {
+    catalog.items.iter().filter(move |item| {
+        item.unlock
+            .is_some_and(|milestone| milestone.player_level() <= player_level.0)
+    })
+}
+
+/// Resource holding the items the current save has unlocked
+#[derive(Resource, Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UnlockedItems(BTreeSet<(LayerType, String)>);
+
+impl UnlockedItems {
+    pub fn grant(&mut self, item: &WardrobeItem) {
+        self.0.insert(Self::key(item));
+    }
+
+    pub fn contains(&self, item: &WardrobeItem) -> bool {
+        self.0.contains(&Self::key(item))
+    }
+
+    /// Returns true if the layer can be worn
+    pub fn is_unlocked(&self, catalog: &WardrobeCatalog, layer: &CharacterLayer) -> bool {
+        catalog
+            .find(layer)
+            .is_none_or(|item| item.unlock.is_none() || self.contains(item))
+    }
+
+    fn key(item: &WardrobeItem) -> (LayerType, String) {
+        (item.layer_type, item.item_name.clone().unwrap_or_default())
+    }
+}
+
+/// System to grant the items for every milestone the player has reached
+fn grant_unlocks(
+    player_level: Res<PlayerLevel>,
+    wardrobe: Wardrobe,
+    mut unlocked: ResMut<UnlockedItems>,
+) {
+    // Items are granted once the catalog has loaded, if it hasn't yet
+    let Some(catalog) = wardrobe.catalog() else {
+        return;
+    };
+    for item in reached_unlocks(catalog, *player_level) {
+        if !unlocked.contains(item) {
+            unlocked.grant(item);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::wardrobe::bundled_catalog;
+
+    fn layer(layer_type: LayerType, item_name: &str) -> CharacterLayer {
+        CharacterLayer {
+            layer_type,
+            item_name: Some(item_name.to_string()),
+            variant: None,
+        }
+    }
+
+    #[test]
+    fn test_milestone_player_levels() {
+        assert_eq!(
+            Milestone::completed(Season::Summer, false).player_level(),
+            1
+        );
+        assert_eq!(
+            Milestone::completed(Season::Spring, false).player_level(),
+            4
+        );
+        assert_eq!(Milestone::completed(Season::Summer, true).player_level(), 5);
+    }
+
+    #[test]
+    fn test_unlocked_up_to_level() {
+        let catalog = bundled_catalog();
+        let mut unlocked = UnlockedItems::default();
+        for item in reached_unlocks(&catalog, PlayerLevel(2)) {
+            unlocked.grant(item);
+        }
+        assert!(unlocked.is_unlocked(&catalog, &layer(LayerType::Headwear, "witch-hat")));
+        assert!(!unlocked.is_unlocked(&catalog, &layer(LayerType::Headwear, "santa-hat")));
+        // Items without an unlock rule are always available
+        assert!(unlocked.is_unlocked(&catalog, &layer(LayerType::Clothes, "sleeve-dress")));
+    }
+
+    #[test]
+    fn test_unlocks_at_milestone() {
+        let catalog = bundled_catalog();
+        let names: Vec<_> = unlocks_at(&catalog, Milestone::completed(Season::Summer, true))
+            .filter_map(|item| item.item_name.as_deref())
+            .collect();
+        assert_eq!(names, vec!["underwear"]);
+        assert_eq!(
+            unlocks_at(&catalog, Milestone::completed(Season::Autumn, false)).count(),
+            3
+        );
+    }
+}
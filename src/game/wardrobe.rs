@@ -0,0 +1,377 @@
+//! The wardrobe catalog: every clothing item the character can wear, loaded from
+//! `assets/data/wardrobe.catalog.eron`.
+//!
+//! Each item lists its layer, variants, display name and unlock rule, so adding an item only
+//! needs a new entry in the catalog and its images. Items whose images are missing are left
+//! out when the catalog loads, rather than failing later when the character is spawned.
+
+use bevy::{
+    asset::{AssetLoader, LoadContext, io::Reader},
+    ecs::system::SystemParam,
+    prelude::*,
+};
+use serde::Deserialize;
+use thiserror::Error;
+
+use super::character::{CharacterLayer, LayerType, LayerVariant};
+use super::unlocks::Milestone;
+use crate::asset_format::{AssetFormatError, EncryptedLoader, asset_manifest};
+use crate::asset_tracking::LoadResource;
+
+pub fn plugin(app: &mut App) {
+    app.init_asset::<WardrobeCatalog>();
+    app.init_asset_loader::<WardrobeCatalogLoader>();
+    app.register_asset_loader(EncryptedLoader::new(
+        WardrobeCatalogLoader,
+        &["catalog.eron"],
+    ));
+    app.load_resource::<WardrobeAssets>();
+}
+
+/// Every item the character can wear, in the order they're listed in character select
+#[derive(Asset, TypePath, Clone, Debug, Deserialize)]
+pub struct WardrobeCatalog {
+    /// Names of the hair colours, indexed by [`LayerVariant::HairColour`]
+    pub hair_colours: Vec<String>,
+    /// Names of the clothing colours, indexed by [`LayerVariant::ClothingColour`]
+    pub clothing_colours: Vec<String>,
+    pub items: Vec<WardrobeItem>,
+}
+
+/// A clothing item, with every variant it comes in
+#[derive(Clone, Debug, Deserialize)]
+pub struct WardrobeItem {
+    pub layer_type: LayerType,
+    #[serde(default)]
+    pub item_name: Option<String>,
+    pub display_name: String,
+    /// Variants of the item, or empty if it only comes in one
+    #[serde(default)]
+    pub variants: Vec<LayerVariant>,
+    /// The milestone that unlocks the item, or none if it's available from the start
+    #[serde(default)]
+    pub unlock: Option<Milestone>,
+    /// Whether the item can be recoloured with [`LayerVariant::Tint`], from its greyscale
+    /// `tint` image
+    #[serde(default)]
+    pub tintable: bool,
+}
+
+impl WardrobeItem {
+    /// Returns true if `layer` is one of this item's variants
+    pub fn matches(&self, layer: &CharacterLayer) -> bool {
+        layer.layer_type == self.layer_type && layer.item_name == self.item_name
+    }
+
+    /// Every variant of the item as a layer
+    pub fn layers(&self) -> Vec<CharacterLayer> {
+        if self.variants.is_empty() {
+            return vec![self.layer(None)];
+        }
+        self.variants
+            .iter()
+            .map(|variant| self.layer(Some(*variant)))
+            .collect()
+    }
+
+    /// Every layer with its own image, including the `tint` image if the item can be tinted
+    fn image_layers(&self) -> Vec<CharacterLayer> {
+        let mut layers = self.layers();
+        if self.tintable {
+            layers.push(self.layer(Some(LayerVariant::Tint(u8::MAX, u8::MAX, u8::MAX))));
+        }
+        layers
+    }
+
+    /// The item's first variant, shown when it's unlocked
+    pub fn preview(&self) -> CharacterLayer {
+        self.layer(self.variants.first().copied())
+    }
+
+    fn layer(&self, variant: Option<LayerVariant>) -> CharacterLayer {
+        CharacterLayer {
+            layer_type: self.layer_type,
+            item_name: self.item_name.clone(),
+            variant,
+        }
+    }
+}
+
+impl WardrobeCatalog {
+    /// Every layer of `layer_type`, in the order they're listed
+    pub fn layers_of(&self, layer_type: LayerType) -> Vec<CharacterLayer> {
+        self.items
+            .iter()
+            .filter(|item| item.layer_type == layer_type)
+            .flat_map(WardrobeItem::layers)
+            .collect()
+    }
+
+    /// Returns the item `layer` is a variant of
+    pub fn find(&self, layer: &CharacterLayer) -> Option<&WardrobeItem> {
+        self.items.iter().find(|item| item.matches(layer))
+    }
+
+    /// Returns the path of the layer's image, or none if it isn't in the catalog
+    ///
+    /// Tinted layers all use the item's greyscale `tint` image.
+    pub fn texture_path(&self, layer: &CharacterLayer) -> Option<String> {
+        let item = self.find(layer)?;
+        let variant = match layer.variant {
+            Some(variant @ LayerVariant::Tint(..)) if item.tintable => Some(variant),
+            Some(variant) if item.variants.contains(&variant) => Some(variant),
+            None if item.variants.is_empty() => None,
+            _ => return None,
+        };
+        self.path(&item.layer(variant))
+    }
+
+    /// Builds the path of the layer's image, without checking the catalog lists it
+    fn path(&self, layer: &CharacterLayer) -> Option<String> {
+        if layer.item_name.is_none() && layer.variant.is_none() {
+            return None;
+        }
+        let mut path = format!(
+            "images/character/{}",
+            layer.layer_type.to_string().to_lowercase()
+        );
+        if let Some(item_name) = &layer.item_name {
+            path.push('/');
+            path.push_str(item_name);
+        }
+        if let Some(variant) = layer.variant {
+            path.push('/');
+            match variant {
+                LayerVariant::HairColour(index) => {
+                    path.push_str(self.hair_colours.get(index as usize)?)
+                }
+                LayerVariant::ClothingColour(index) => {
+                    path.push_str(self.clothing_colours.get(index as usize)?)
+                }
+                LayerVariant::Variant(number) => path.push_str(&number.to_string()),
+                LayerVariant::Tint(..) => path.push_str("tint"),
+            }
+        }
+        path.push_str(".epng");
+        Some(path)
+    }
+
+    /// Removes the given layers, and any item left without variants
+    ///
+    /// A missing tinted layer leaves the item's other variants, but it can't be tinted.
+    fn remove_layers(&mut self, missing: &[CharacterLayer]) {
+        for item in &mut self.items {
+            if missing.iter().any(|layer| {
+                item.matches(layer) && matches!(layer.variant, Some(LayerVariant::Tint(..)))
+            }) {
+                item.tintable = false;
+            }
+        }
+        self.items.retain_mut(|item| {
+            if item.variants.is_empty() {
+                return !missing
+                    .iter()
+                    .any(|layer| item.matches(layer) && layer.variant.is_none());
+            }
+            let item_type = item.layer_type;
+            let item_name = item.item_name.clone();
+            item.variants.retain(|variant| {
+                !missing.iter().any(|layer| {
+                    layer.layer_type == item_type
+                        && layer.item_name == item_name
+                        && layer.variant == Some(*variant)
+                })
+            });
+            !item.variants.is_empty()
+        });
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum WardrobeError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Ron(#[from] ron::error::SpannedError),
+    #[error(transparent)]
+    AssetFormat(#[from] AssetFormatError),
+}
+
+#[derive(Default)]
+pub struct WardrobeCatalogLoader;
+
+impl AssetLoader for WardrobeCatalogLoader {
+    type Asset = WardrobeCatalog;
+    type Settings = ();
+    type Error = WardrobeError;
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<WardrobeCatalog, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let mut catalog: WardrobeCatalog = ron::de::from_bytes(&bytes)?;
+
+        // Check every image exists now, so a typo can't break spawning the character. Images
+        // are looked up in the asset manifest rather than read, unless it hasn't been generated.
+        let manifest = asset_manifest(load_context).await?;
+        let mut missing = Vec::new();
+        for layer in catalog.items.iter().flat_map(WardrobeItem::image_layers) {
+            let exists = match (catalog.path(&layer), manifest.as_ref()) {
+                (Some(path), Some(manifest)) => manifest.hashes.contains_key(&path),
+                (Some(path), None) => load_context.read_asset_bytes(path).await.is_ok(),
+                (None, _) => false,
+            };
+            if !exists {
+                warn!("Leaving {layer:?} out of the wardrobe, as its image is missing");
+                missing.push(layer);
+            }
+        }
+        catalog.remove_layers(&missing);
+
+        Ok(catalog)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["catalog.ron"]
+    }
+}
+
+/// Resource holding the wardrobe catalog, inserted once it has loaded
+#[derive(Resource, Asset, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct WardrobeAssets {
+    #[dependency]
+    pub catalog: Handle<WardrobeCatalog>,
+}
+
+impl FromWorld for WardrobeAssets {
+    fn from_world(world: &mut World) -> Self {
+        let assets = world.resource::<AssetServer>();
+        Self {
+            catalog: assets.load("data/wardrobe.catalog.eron"),
+        }
+    }
+}
+
+/// System parameter for reading the wardrobe catalog
+#[derive(SystemParam)]
+pub struct Wardrobe<'w> {
+    assets: Option<Res<'w, WardrobeAssets>>,
+    catalogs: Res<'w, Assets<WardrobeCatalog>>,
+}
+
+impl Wardrobe<'_> {
+    /// Returns the catalog, or none if it hasn't loaded yet
+    pub fn catalog(&self) -> Option<&WardrobeCatalog> {
+        self.catalogs.get(&self.assets.as_ref()?.catalog)
+    }
+
+    /// Returns the path of the layer's image, or none if it isn't in the catalog
+    pub fn texture_path(&self, layer: &CharacterLayer) -> Option<String> {
+        self.catalog()?.texture_path(layer)
+    }
+}
+
+/// The catalog shipped with the game, decrypted straight from the assets folder
+#[cfg(test)]
+pub fn bundled_catalog() -> WardrobeCatalog {
+    let encrypted = crate::asset_format::EncryptedAsset::from_bytes(include_bytes!(
+        "../../assets/data/wardrobe.catalog.eron"
+    ))
+    .unwrap();
+    ron::de::from_bytes(&encrypted.decrypt().unwrap()).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    fn layer(layer_type: LayerType, item_name: &str, variant: LayerVariant) -> CharacterLayer {
+        CharacterLayer {
+            layer_type,
+            item_name: Some(item_name.to_string()),
+            variant: Some(variant),
+        }
+    }
+
+    #[test]
+    fn test_bundled_images_exist() {
+        let catalog = bundled_catalog();
+        for layer in catalog.items.iter().flat_map(WardrobeItem::image_layers) {
+            let path = catalog.texture_path(&layer).unwrap();
+            assert!(
+                Path::new("assets").join(&path).exists(),
+                "{path} is missing"
+            );
+        }
+    }
+
+    #[test]
+    fn test_texture_path() {
+        let catalog = bundled_catalog();
+        assert_eq!(
+            catalog
+                .texture_path(&layer(LayerType::Hair, "1", LayerVariant::HairColour(4)))
+                .as_deref(),
+            Some("images/character/hair/1/black.epng")
+        );
+        // Variants the item doesn't come in have no image
+        assert_eq!(
+            catalog.texture_path(&layer(LayerType::Hair, "1", LayerVariant::Variant(1))),
+            None
+        );
+        assert_eq!(
+            catalog.texture_path(&layer(LayerType::Hair, "1", LayerVariant::HairColour(9))),
+            None
+        );
+    }
+
+    #[test]
+    fn test_tinted_layers_use_tint_image() {
+        let catalog = bundled_catalog();
+        let tint = LayerVariant::Tint(200, 40, 90);
+        assert_eq!(
+            catalog
+                .texture_path(&layer(LayerType::Headwear, "hat", tint))
+                .as_deref(),
+            Some("images/character/headwear/hat/tint.epng")
+        );
+        // Items that aren't tintable only come in their listed variants
+        assert_eq!(
+            catalog.texture_path(&layer(LayerType::Footwear, "thighhighs", tint)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_missing_layers_are_removed() {
+        let mut catalog = bundled_catalog();
+        let socks = |index| {
+            layer(
+                LayerType::Footwear,
+                "socks",
+                LayerVariant::ClothingColour(index),
+            )
+        };
+        catalog.remove_layers(&[socks(1)]);
+        assert!(catalog.texture_path(&socks(0)).is_some());
+        assert!(catalog.texture_path(&socks(1)).is_none());
+
+        // Socks without their tint image keep their colours, but can't be tinted
+        let tinted_socks = layer(LayerType::Footwear, "socks", LayerVariant::Tint(0, 0, 0));
+        assert!(catalog.texture_path(&tinted_socks).is_some());
+        catalog.remove_layers(std::slice::from_ref(&tinted_socks));
+        assert!(catalog.texture_path(&tinted_socks).is_none());
+        assert!(catalog.texture_path(&socks(0)).is_some());
+
+        // An item is dropped once none of its variants are left
+        let all_socks: Vec<_> = (0..5).map(socks).collect();
+        catalog.remove_layers(&all_socks);
+        assert!(catalog.find(&socks(0)).is_none());
+    }
+}
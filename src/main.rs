@@ -15,6 +15,7 @@ mod screens;
 mod theme;
 
 use bevy::{asset::AssetMetaCheck, prelude::*};
+use bevy_pkv::PkvStore;
 
 fn main() -> AppExit {
     App::new().add_plugins(AppPlugin).run()
@@ -46,35 +47,43 @@ impl Plugin for AppPlugin {
                 .set(ImagePlugin::default_nearest()),
         );
 
-        // Add other plugins.
-        app.add_plugins((
-            asset_format::plugin,
-            asset_tracking::plugin,
-            audio::plugin,
-            #[cfg(feature = "dev")]
-            dev_tools::plugin,
-            game::plugin,
-            menus::plugin,
-            pixel_camera::plugin,
-            screens::plugin,
-            theme::plugin,
-        ));
+        // Persistent resources are loaded as soon as their plugins are added, so the store
+        // has to exist before any of ours.
+        app.insert_resource(PkvStore::new("tmstorey", "glass-half-full"));
+        add_game_plugins(app);
+    }
+}
 
-        // Order new `AppSystems` variants by adding them here:
-        app.configure_sets(
-            Update,
-            (
-                AppSystems::TickTimers,
-                AppSystems::RecordInput,
-                AppSystems::Update,
-            )
-                .chain(),
-        );
+/// Adds the game's own plugins, once Bevy's plugins and the [`PkvStore`] are in place
+fn add_game_plugins(app: &mut App) {
+    // Add other plugins.
+    app.add_plugins((
+        asset_format::plugin,
+        asset_tracking::plugin,
+        audio::plugin,
+        #[cfg(feature = "dev")]
+        dev_tools::plugin,
+        game::plugin,
+        menus::plugin,
+        pixel_camera::plugin,
+        screens::plugin,
+        theme::plugin,
+    ));
 
-        // Set up the `Pause` state.
-        app.init_state::<Pause>();
-        app.configure_sets(Update, PausableSystems.run_if(in_state(Pause(false))));
-    }
+    // Order new `AppSystems` variants by adding them here:
+    app.configure_sets(
+        Update,
+        (
+            AppSystems::TickTimers,
+            AppSystems::RecordInput,
+            AppSystems::Update,
+        )
+            .chain(),
+    );
+
+    // Set up the `Pause` state.
+    app.init_state::<Pause>();
+    app.configure_sets(Update, PausableSystems.run_if(in_state(Pause(false))));
 }
 
 /// High-level groupings of systems for the app in the `Update` schedule.
@@ -97,3 +106,36 @@ struct Pause(pub bool);
 /// A system set for systems that shouldn't run while the game is paused.
 #[derive(SystemSet, Copy, Clone, Eq, PartialEq, Hash, Debug)]
 struct PausableSystems;
+
+#[cfg(test)]
+mod tests {
+    use bevy::render::{RenderPlugin, settings::WgpuSettings};
+    use bevy::winit::WinitPlugin;
+
+    use super::*;
+
+    #[test]
+    fn test_plugins_build() {
+        let mut app = App::new();
+        // Run headless, without a window or a GPU
+        app.add_plugins(
+            DefaultPlugins
+                .build()
+                .disable::<WinitPlugin>()
+                .set(RenderPlugin {
+                    render_creation: WgpuSettings {
+                        backends: None,
+                        ..default()
+                    }
+                    .into(),
+                    ..default()
+                }),
+        );
+        app.insert_resource(PkvStore::new_in_dir(
+            std::env::temp_dir().join("glass-half-full-test"),
+        ));
+        add_game_plugins(&mut app);
+        app.finish();
+        app.cleanup();
+    }
+}
@@ -1,8 +1,12 @@
 use crate::{
     game::{
-        PlayerLevel,
         character::{COLUMNS, CharacterLayer, CharacterLayers, LayerType, LayerVariant, ROWS},
+        composite::{AvatarError, Compositor},
         controls::Action,
+        outfits::{MAX_PRESET_NAME_CHARS, OutfitPresets, REQUIRED_LAYER_TYPES, random_outfit},
+        share_code,
+        unlocks::UnlockedItems,
+        wardrobe::Wardrobe,
     },
     menus::Menu,
     screens::Screen,
@@ -10,7 +14,11 @@ use crate::{
 };
 use bevy::{
     ecs::{relationship::RelatedSpawner, spawn::SpawnWith},
-    input::mouse::{MouseScrollUnit, MouseWheel},
+    input::{
+        ButtonState,
+        keyboard::{Key, KeyboardInput},
+        mouse::{MouseScrollUnit, MouseWheel},
+    },
     picking::hover::{HoverMap, Hovered},
     prelude::*,
     ui_widgets::{ControlOrientation, CoreScrollbarDragState, CoreScrollbarThumb, Scrollbar},
@@ -30,17 +38,20 @@ pub static BODY_COLOURS: LazyLock<Vec<Color>> = LazyLock::new(|| {
 
 pub(super) fn plugin(app: &mut App) {
     app.init_resource::<CharacterSelectState>();
+    app.init_resource::<OutfitMenuState>();
 
     app.add_systems(OnEnter(Menu::CharacterSelect), spawn_character_select_menu);
     app.add_systems(
         Update,
         (
-            go_back,
-            handle_layer_type_change,
-            handle_item_selection,
+            go_back.before(type_outfit_name),
+            type_outfit_name,
             update_character_preview,
             send_scroll_events,
             update_scrollbar_thumb,
+            update_colour_picker,
+            update_outfit_grid,
+            update_outfit_status,
         )
             .run_if(in_state(Menu::CharacterSelect)),
     );
@@ -66,10 +77,54 @@ impl Default for CharacterSelectState {
     }
 }
 
+/// Resource holding the outfits panel's status message, the preset waiting to be deleted, and
+/// the preset being renamed
+#[derive(Resource, Debug, Default)]
+struct OutfitMenuState {
+    confirm_delete: Option<String>,
+    renaming: Option<RenamingOutfit>,
+    status: String,
+}
+
+/// A preset being renamed, and the new name typed so far
+#[derive(Debug)]
+struct RenamingOutfit {
+    name: String,
+    draft: String,
+}
+
+impl RenamingOutfit {
+    /// Adds typed text to the new name, up to the longest a name can be
+    fn push(&mut self, text: &str) {
+        for c in text.chars().filter(|c| !c.is_control()) {
+            if self.draft.chars().count() >= MAX_PRESET_NAME_CHARS {
+                return;
+            }
+            self.draft.push(c);
+        }
+    }
+}
+
 /// Marker component for the character preview container
 #[derive(Component)]
 struct CharacterPreview;
 
+/// Marker component for the baked character shown in the preview
+#[derive(Component)]
+struct CharacterPreviewSprite;
+
+/// Where avatars are saved, relative to the working directory
+#[cfg(not(target_family = "wasm"))]
+const AVATAR_PATH: &str = "avatar.png";
+
+/// Marker component for the list of saved outfits
+#[derive(Component)]
+struct OutfitGrid;
+
+/// Marker component for the outfits panel's status message
+#[derive(Component)]
+struct OutfitStatusLabel;
+
 /// Marker component for the items grid
 #[derive(Component)]
 struct ItemsGrid;
@@ -83,6 +138,7 @@ struct LayerTypeButton(LayerType);
 struct ItemButton {
     layer: Option<CharacterLayer>,
     layer_type: LayerType,
+    locked: bool,
 }
 
 fn spawn_character_select_menu(
@@ -90,20 +146,34 @@ fn spawn_character_select_menu(
     asset_server: Res<AssetServer>,
     character_layers: Res<CharacterLayers>,
     mut state: ResMut<CharacterSelectState>,
+    mut outfit_menu_state: ResMut<OutfitMenuState>,
 ) {
     state.current_layers = character_layers.clone();
+    *outfit_menu_state = OutfitMenuState::default();
 
     commands.spawn((
         widget::ui_root("Character Select Menu"),
         GlobalZIndex(2),
         DespawnOnExit(Menu::CharacterSelect),
         children![
-            // Upper section: Character preview
-            character_preview_section(&asset_server),
+            // Upper section: Character preview and saved outfits
+            (
+                Name::new("Upper Section"),
+                Node {
+                    display: Display::Flex,
+                    flex_direction: FlexDirection::Row,
+                    column_gap: px(20),
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                children![character_preview_section(&asset_server), outfits_section(),],
+            ),
             // Middle section: Layer type selector
             layer_type_selector(),
             // Lower section: Items grid
             items_grid_section(),
+            // Colour picker for items that can be tinted
+            colour_picker(),
             // Bottom buttons
             (
                 Name::new("Bottom Buttons"),
@@ -115,6 +185,7 @@ fn spawn_character_select_menu(
                 },
                 children![
                     widget::button("Back", go_back_on_click),
+                    widget::button_medium("Save PNG", export_avatar),
                     widget::button("Confirm", confirm_selection),
                 ],
             ),
@@ -141,6 +212,68 @@ fn character_preview_section(_asset_server: &AssetServer) -> impl Bundle {
     )
 }
 
+fn outfits_section() -> impl Bundle {
+    (
+        Name::new("Outfits Section"),
+        Node {
+            display: Display::Flex,
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            row_gap: px(10),
+            ..default()
+        },
+        children![
+            (
+                Name::new("Outfit Buttons"),
+                Node {
+                    display: Display::Flex,
+                    flex_direction: FlexDirection::Row,
+                    column_gap: px(10),
+                    ..default()
+                },
+                children![
+                    widget::button_medium("Randomise", randomise_outfit),
+                    widget::button_medium("Save outfit", save_outfit),
+                ],
+            ),
+            (
+                Name::new("Share Code Buttons"),
+                Node {
+                    display: Display::Flex,
+                    flex_direction: FlexDirection::Row,
+                    column_gap: px(10),
+                    ..default()
+                },
+                children![
+                    widget::button_medium("Copy code", export_outfit_code),
+                    widget::button_medium("Paste code", import_outfit_code),
+                ],
+            ),
+            (
+                Name::new("Outfit Grid"),
+                OutfitGrid,
+                Node {
+                    display: Display::Grid,
+                    row_gap: px(6),
+                    column_gap: px(10),
+                    grid_template_columns: vec![
+                        GridTrack::px(220.0),
+                        GridTrack::auto(),
+                        GridTrack::auto(),
+                        GridTrack::auto(),
+                        GridTrack::auto(),
+                    ],
+                    align_items: AlignItems::Center,
+                    max_height: px(200),
+                    overflow: Overflow::scroll_y(),
+                    ..default()
+                },
+            ),
+            (widget::label(""), OutfitStatusLabel),
+        ],
+    )
+}
+
 fn layer_type_selector() -> impl Bundle {
     let layer_types = vec![
         LayerType::Body,
@@ -179,31 +312,34 @@ fn layer_type_button(layer_type: LayerType) -> impl Bundle {
         LayerTypeButton(layer_type),
         Node::default(),
         Children::spawn(SpawnWith(move |parent: &mut ChildSpawner| {
-            parent.spawn((
-                Name::new("Button Inner"),
-                Button,
-                BackgroundColor(*BUTTON_BACKGROUND),
-                InteractionPalette {
-                    none: *BUTTON_BACKGROUND,
-                    hovered: *BUTTON_HOVERED_BACKGROUND,
-                    pressed: *BUTTON_PRESSED_BACKGROUND,
-                },
-                Node {
-                    width: px(180),
-                    height: px(50),
-                    align_items: AlignItems::Center,
-                    justify_content: JustifyContent::Center,
-                    ..default()
-                },
-                BorderRadius::all(px(25)),
-                children![(
-                    Name::new("Button Text"),
-                    Text(format!("{:?}", layer_type)),
-                    TextFont::from_font_size(20.0),
-                    TextColor(*BUTTON_TEXT),
-                    Pickable::IGNORE,
-                )],
-            ));
+            parent
+                .spawn((
+                    Name::new("Button Inner"),
+                    Button,
+                    BackgroundColor(*BUTTON_BACKGROUND),
+                    InteractionPalette {
+                        none: *BUTTON_BACKGROUND,
+                        hovered: *BUTTON_HOVERED_BACKGROUND,
+                        pressed: *BUTTON_PRESSED_BACKGROUND,
+                        focused: *BUTTON_FOCUSED_BACKGROUND,
+                    },
+                    Node {
+                        width: px(180),
+                        height: px(50),
+                        align_items: AlignItems::Center,
+                        justify_content: JustifyContent::Center,
+                        ..default()
+                    },
+                    BorderRadius::all(px(25)),
+                    children![(
+                        Name::new("Button Text"),
+                        Text(format!("{:?}", layer_type)),
+                        TextFont::from_font_size(20.0),
+                        TextColor(*BUTTON_TEXT),
+                        Pickable::IGNORE,
+                    )],
+                ))
+                .observe(handle_layer_type_change);
         })),
     )
 }
@@ -273,207 +409,333 @@ fn items_grid_section() -> impl Bundle {
 fn item_button(
     layer: Option<CharacterLayer>,
     layer_type: LayerType,
+    display_name: String,
+    texture_path: Option<String>,
+    requirement: Option<String>,
     asset_server: &AssetServer,
 ) -> impl Bundle {
     let asset_server = asset_server.clone();
     let is_body = layer_type == LayerType::Body;
-    let (path, variant) = if let Some(layer) = layer.clone() {
-        let path = layer
-            .texture_path()
-            .unwrap_or_else(|_| "images/character/empty.epng".to_string());
-        (path, layer.variant)
-    } else {
-        let path = "images/character/empty.epng".to_string();
-        (path, None)
-    };
-    let texture = asset_server.load(path);
+    let variant = layer.as_ref().and_then(|layer| layer.variant);
+    let texture = asset_server
+        .load(texture_path.unwrap_or_else(|| "images/character/empty.epng".to_string()));
     let layout = TextureAtlasLayout::from_grid(UVec2::new(80, 64), COLUMNS, ROWS, None, None);
 
     (
-        Name::new("Item"),
+        Name::new(display_name),
         ItemButton {
             layer: layer.clone(),
             layer_type,
+            locked: requirement.is_some(),
         },
         Node::default(),
         Pickable::IGNORE,
         Children::spawn(SpawnWith(move |parent: &mut ChildSpawner| {
-            parent.spawn((
-                Name::new("Button Inner"),
-                Button,
-                BackgroundColor(*TRANSPARENT),
-                InteractionPalette {
-                    none: *TRANSPARENT,
-                    hovered: *BUTTON_HOVERED_BACKGROUND,
-                    pressed: *BUTTON_PRESSED_BACKGROUND,
-                },
-                Node {
-                    width: px(90),
-                    height: px(90),
-                    align_items: AlignItems::Center,
-                    justify_content: JustifyContent::Center,
-                    ..default()
-                },
-                BorderRadius::all(px(2)),
-                Pickable::IGNORE,
-                // Sprite or color square child
-                Children::spawn(SpawnWith(move |parent: &mut ChildSpawner| {
-                    if is_body {
-                        // For Body type, use a colored square
-                        if let Some(LayerVariant::Variant(index)) = variant {
-                            let color = BODY_COLOURS
-                                .get((index - 1) as usize)
-                                .copied()
-                                .unwrap_or(Color::WHITE);
+            parent
+                .spawn((
+                    Name::new("Button Inner"),
+                    Button,
+                    BackgroundColor(*TRANSPARENT),
+                    InteractionPalette {
+                        none: *TRANSPARENT,
+                        hovered: *BUTTON_HOVERED_BACKGROUND,
+                        pressed: *BUTTON_PRESSED_BACKGROUND,
+                        focused: *BUTTON_FOCUSED_BACKGROUND,
+                    },
+                    Node {
+                        width: px(90),
+                        height: px(90),
+                        align_items: AlignItems::Center,
+                        justify_content: JustifyContent::Center,
+                        ..default()
+                    },
+                    BorderRadius::all(px(2)),
+                    // Sprite or color square child
+                    Children::spawn(SpawnWith(move |parent: &mut ChildSpawner| {
+                        if is_body {
+                            // For Body type, use a colored square
+                            if let Some(LayerVariant::Variant(index)) = variant {
+                                let color = BODY_COLOURS
+                                    .get((index - 1) as usize)
+                                    .copied()
+                                    .unwrap_or(Color::WHITE);
+                                parent.spawn((
+                                    Node {
+                                        width: px(80),
+                                        height: px(80),
+                                        ..default()
+                                    },
+                                    BackgroundColor(color),
+                                    Pickable::IGNORE,
+                                ));
+                            }
+                        } else {
+                            // For other types, show the first sprite from the spritesheet
                             parent.spawn((
+                                Name::new("Item"),
                                 Node {
                                     width: px(80),
                                     height: px(80),
+                                    position_type: PositionType::Absolute,
+                                    ..default()
+                                },
+                                ImageNode {
+                                    image: texture,
+                                    texture_atlas: Some(TextureAtlas {
+                                        layout: asset_server.add(layout),
+                                        index: 0, // Idle pose, first frame
+                                    }),
+                                    // Locked items are drawn as a silhouette
+                                    color: if requirement.is_some() {
+                                        Color::BLACK
+                                    } else {
+                                        Color::WHITE
+                                    },
                                     ..default()
                                 },
-                                BackgroundColor(color),
                                 Pickable::IGNORE,
                             ));
                         }
-                    } else {
-                        // For other types, show the first sprite from the spritesheet
-                        parent.spawn((
-                            Name::new("Item"),
-                            Node {
-                                width: px(80),
-                                height: px(80),
-                                position_type: PositionType::Absolute,
-                                ..default()
-                            },
-                            ImageNode {
-                                image: texture,
-                                texture_atlas: Some(TextureAtlas {
-                                    layout: asset_server.add(layout),
-                                    index: 0, // Idle pose, first frame
-                                }),
-                                ..default()
-                            },
-                            Pickable::IGNORE,
-                        ));
-                    }
-                })),
-            ));
+                        if let Some(requirement) = requirement {
+                            parent.spawn((
+                                Name::new("Requirement"),
+                                Text(requirement),
+                                TextFont::from_font_size(12.0),
+                                TextColor(*LABEL_TEXT),
+                                TextLayout::new_with_justify(Justify::Center),
+                                Node {
+                                    position_type: PositionType::Absolute,
+                                    bottom: px(2),
+                                    ..default()
+                                },
+                                Pickable::IGNORE,
+                            ));
+                        }
+                    })),
+                ))
+                .observe(handle_item_selection);
+        })),
+    )
+}
+
+/// Marker component for the colour picker, shown when the current item can be tinted
+#[derive(Component)]
+struct ColourPicker;
+
+/// Component for a colour picker button, holding its colour as sRGB
+#[derive(Component)]
+struct ColourSwatch([u8; 3]);
+
+/// Returns the colours offered by the colour picker: a row of hues at each lightness
+fn swatch_colours() -> Vec<[u8; 3]> {
+    let mut colours = vec![];
+    for lightness in [0.25, 0.5, 0.75] {
+        for step in 0..12 {
+            let colour = Color::hsl(step as f32 * 30.0, 0.7, lightness);
+            colours.push(colour.to_srgba().to_u8_array_no_alpha());
+        }
+    }
+    for step in 0..12 {
+        let grey = Color::hsl(0.0, 0.0, step as f32 / 11.0);
+        colours.push(grey.to_srgba().to_u8_array_no_alpha());
+    }
+    colours
+}
+
+fn colour_picker() -> impl Bundle {
+    (
+        Name::new("Colour Picker"),
+        ColourPicker,
+        Node {
+            display: Display::None,
+            grid_template_columns: RepeatedGridTrack::px(12, 24.0),
+            row_gap: px(4),
+            column_gap: px(4),
+            ..default()
+        },
+        Children::spawn(SpawnWith(|parent: &mut ChildSpawner| {
+            for colour in swatch_colours() {
+                let [red, green, blue] = colour;
+                parent
+                    .spawn((
+                        Name::new("Colour Swatch"),
+                        ColourSwatch(colour),
+                        Button,
+                        BackgroundColor(Color::srgb_u8(red, green, blue)),
+                        Node {
+                            width: px(24),
+                            height: px(24),
+                            ..default()
+                        },
+                        BorderRadius::all(px(4)),
+                    ))
+                    .observe(handle_colour_selection);
+            }
         })),
     )
 }
 
+/// Tints the current item with the clicked colour
+fn handle_colour_selection(
+    trigger: On<Pointer<Click>>,
+    swatch_query: Query<&ColourSwatch>,
+    wardrobe: Wardrobe,
+    mut state: ResMut<CharacterSelectState>,
+) {
+    let Ok(swatch) = swatch_query.get(trigger.entity) else {
+        return;
+    };
+    let Some(catalog) = wardrobe.catalog() else {
+        return;
+    };
+
+    let layer_type = state.current_layer_type;
+    let Some(layer) = state
+        .current_layers
+        .layers
+        .iter_mut()
+        .find(|layer| layer.layer_type == layer_type)
+    else {
+        return;
+    };
+    if catalog.find(layer).is_some_and(|item| item.tintable) {
+        let [red, green, blue] = swatch.0;
+        layer.variant = Some(LayerVariant::Tint(red, green, blue));
+    }
+}
+
+/// System to show the colour picker only when the current item can be tinted
+fn update_colour_picker(
+    state: Res<CharacterSelectState>,
+    wardrobe: Wardrobe,
+    mut picker: Single<&mut Node, With<ColourPicker>>,
+) {
+    if !state.is_changed() {
+        return;
+    }
+
+    let can_tint = wardrobe.catalog().is_some_and(|catalog| {
+        state
+            .current_layers
+            .layers
+            .iter()
+            .find(|layer| layer.layer_type == state.current_layer_type)
+            .and_then(|layer| catalog.find(layer))
+            .is_some_and(|item| item.tintable)
+    });
+    picker.display = if can_tint {
+        Display::Grid
+    } else {
+        Display::None
+    };
+}
+
 fn handle_layer_type_change(
-    interaction_query: Query<(&Interaction, &ChildOf), (Changed<Interaction>, With<Button>)>,
+    trigger: On<Pointer<Click>>,
+    child_of_query: Query<&ChildOf, With<Button>>,
     layer_button_query: Query<&LayerTypeButton>,
-    player_level: Res<PlayerLevel>,
+    unlocked_items: Res<UnlockedItems>,
+    wardrobe: Wardrobe,
     mut state: ResMut<CharacterSelectState>,
     items_grid_query: Query<(Entity, &Children), With<ItemsGrid>>,
     mut commands: Commands,
     asset_server: Res<AssetServer>,
 ) {
-    for (interaction, child_of) in &interaction_query {
-        if *interaction == Interaction::Pressed
-            && let Ok(layer_button) = layer_button_query.get(child_of.parent())
-        {
-            state.current_layer_type = layer_button.0;
-
-            // Rebuild items grid
-            if let Ok((items_grid_entity, _)) = items_grid_query.single() {
-                // Despawn existing items
-                commands.entity(items_grid_entity).despawn_children();
-
-                // Spawn new items for the selected layer type
-                let available_items = state.current_layer_type.available_items();
-
-                let mut available_items: Vec<_> = available_items.iter().map(Some).collect();
-
-                // Require sufficient player level for certain clothing items
-                let mut allowed_length = available_items.len();
-                if state.current_layer_type == LayerType::Clothes && player_level.0 < 1 {
-                    allowed_length = 6;
-                } else if state.current_layer_type == LayerType::Clothes && player_level.0 < 2 {
-                    allowed_length = 7;
-                } else if state.current_layer_type == LayerType::Clothes && player_level.0 < 3 {
-                    allowed_length = 8;
-                } else if state.current_layer_type == LayerType::Clothes && player_level.0 < 4 {
-                    allowed_length = 9;
-                } else if state.current_layer_type == LayerType::Footwear && player_level.0 < 2 {
-                    allowed_length = 1;
-                } else if state.current_layer_type == LayerType::Footwear && player_level.0 < 3 {
-                    allowed_length = 6;
-                } else if state.current_layer_type == LayerType::Headwear && player_level.0 < 1 {
-                    allowed_length = 10;
-                } else if state.current_layer_type == LayerType::Headwear && player_level.0 < 2 {
-                    allowed_length = 12;
-                } else if state.current_layer_type == LayerType::Headwear && player_level.0 < 3 {
-                    allowed_length = 13;
-                } else if state.current_layer_type == LayerType::Headwear && player_level.0 < 4 {
-                    allowed_length = 14;
-                } else if state.current_layer_type == LayerType::Underclothes && player_level.0 < 4
-                {
-                    allowed_length = 35;
-                } else if state.current_layer_type == LayerType::Underclothes && player_level.0 < 5
-                {
-                    allowed_length = 40;
-                } else if state.current_layer_type == LayerType::Underclothes && player_level.0 == 5
-                {
-                    allowed_length = 45;
-                }
+    let Ok(child_of) = child_of_query.get(trigger.entity) else {
+        return;
+    };
+    let Ok(layer_button) = layer_button_query.get(child_of.parent()) else {
+        return;
+    };
 
-                // Add empty item for all types except Body and Underclothes
-                if state.current_layer_type != LayerType::Body
-                    && state.current_layer_type != LayerType::Underclothes
-                {
-                    available_items.insert(0, None);
-                }
+    let Some(catalog) = wardrobe.catalog() else {
+        return;
+    };
 
-                commands.entity(items_grid_entity).with_children(|parent| {
-                    for index in 0..=allowed_length {
-                        // reference playtime
-                        let layer = available_items.get(index).and_then(|layer| *layer).cloned();
-                        parent.spawn(item_button(layer, state.current_layer_type, &asset_server));
-                    }
-                });
-            }
+    state.current_layer_type = layer_button.0;
+
+    // Rebuild items grid
+    if let Ok((items_grid_entity, _)) = items_grid_query.single() {
+        // Despawn existing items
+        commands.entity(items_grid_entity).despawn_children();
+
+        // Spawn new items for the selected layer type
+        let available_items = catalog.layers_of(state.current_layer_type);
+
+        let mut available_items: Vec<_> = available_items.iter().map(Some).collect();
+
+        // Add empty item for all types except Body and Underclothes
+        if state.current_layer_type != LayerType::Body
+            && state.current_layer_type != LayerType::Underclothes
+        {
+            available_items.insert(0, None);
         }
+
+        commands.entity(items_grid_entity).with_children(|parent| {
+            for layer in available_items {
+                let item = layer.and_then(|layer| catalog.find(layer));
+                let display_name =
+                    item.map_or_else(|| "Nothing".to_string(), |item| item.display_name.clone());
+                // Locked items are shown with what it takes to unlock them
+                let requirement = layer
+                    .filter(|layer| !unlocked_items.is_unlocked(catalog, layer))
+                    .and(item)
+                    .and_then(|item| item.unlock)
+                    .map(|milestone| milestone.requirement());
+                parent.spawn(item_button(
+                    layer.cloned(),
+                    state.current_layer_type,
+                    display_name,
+                    layer.and_then(|layer| catalog.texture_path(layer)),
+                    requirement,
+                    &asset_server,
+                ));
+            }
+        });
     }
 }
 
 fn handle_item_selection(
-    interaction_query: Query<(&Interaction, &ChildOf), (Changed<Interaction>, With<Button>)>,
+    trigger: On<Pointer<Click>>,
+    child_of_query: Query<&ChildOf, With<Button>>,
     item_button_query: Query<&ItemButton>,
     mut state: ResMut<CharacterSelectState>,
 ) {
-    for (interaction, child_of) in &interaction_query {
-        if *interaction == Interaction::Pressed
-            && let Ok(item_button) = item_button_query.get(child_of.parent())
+    let Ok(child_of) = child_of_query.get(trigger.entity) else {
+        return;
+    };
+    let Ok(item_button) = item_button_query.get(child_of.parent()) else {
+        return;
+    };
+    if item_button.locked {
+        return;
+    }
+
+    if let Some(layer) = &item_button.layer {
+        // Update or add the layer to current configuration
+        if let Some(existing) = state
+            .current_layers
+            .layers
+            .iter_mut()
+            .find(|l| l.layer_type == item_button.layer_type)
         {
-            if let Some(layer) = &item_button.layer {
-                // Update or add the layer to current configuration
-                if let Some(existing) = state
-                    .current_layers
-                    .layers
-                    .iter_mut()
-                    .find(|l| l.layer_type == item_button.layer_type)
-                {
-                    *existing = layer.clone();
-                } else {
-                    state.current_layers.layers.push(layer.clone());
-                }
-            } else {
-                // Empty layer selected, remove matching layer
-                state
-                    .current_layers
-                    .layers
-                    .retain(|l| l.layer_type != item_button.layer_type);
-            }
+            *existing = layer.clone();
+        } else {
+            state.current_layers.layers.push(layer.clone());
         }
+    } else {
+        // Empty layer selected, remove matching layer
+        state
+            .current_layers
+            .layers
+            .retain(|l| l.layer_type != item_button.layer_type);
     }
 }
 
 fn update_character_preview(
     state: Res<CharacterSelectState>,
-    preview_query: Query<(Entity, &Children), With<CharacterPreview>>,
+    preview_query: Query<Entity, With<CharacterPreview>>,
+    mut compositor: Compositor,
     mut commands: Commands,
     asset_server: Res<AssetServer>,
 ) {
@@ -482,46 +744,356 @@ fn update_character_preview(
         return;
     }
 
-    if let Ok((preview_entity, _)) = preview_query.single() {
+    if let Ok(preview_entity) = preview_query.single() {
         // Clear existing children
         commands.entity(preview_entity).despawn_children();
 
-        // Spawn all layers
-        let mut sorted_layers = state.current_layers.clone();
-        sorted_layers.layers.sort_by_key(|l| l.layer_type);
-
-        commands.entity(preview_entity).with_children(|parent| {
-            for layer in sorted_layers.layers {
-                if let Ok(path) = layer.texture_path() {
-                    let texture = asset_server.load(path);
-                    let layout = TextureAtlasLayout::from_grid(
-                        UVec2::new(80, 64),
-                        COLUMNS,
-                        ROWS,
-                        None,
-                        None,
-                    );
-
-                    parent.spawn((
-                        Name::new(format!("{:?} Preview Layer", layer.layer_type)),
-                        ImageNode {
-                            image: texture,
-                            texture_atlas: Some(TextureAtlas {
-                                layout: asset_server.add(layout),
-                                index: 0, // Idle pose, first frame
-                            }),
-                            ..default()
-                        },
-                        UiTransform::from_scale(Vec2::splat(4.0)),
-                        Pickable::IGNORE,
-                        Node {
-                            position_type: PositionType::Absolute,
-                            ..default()
-                        },
-                    ));
+        // Draw the layers baked into one sheet
+        let layout = TextureAtlasLayout::from_grid(UVec2::new(80, 64), COLUMNS, ROWS, None, None);
+        commands.entity(preview_entity).with_child((
+            Name::new("Character Preview Sprite"),
+            CharacterPreviewSprite,
+            ImageNode {
+                image: compositor.composite(&state.current_layers),
+                texture_atlas: Some(TextureAtlas {
+                    layout: asset_server.add(layout),
+                    index: 0, // Idle pose, first frame
+                }),
+                ..default()
+            },
+            UiTransform::from_scale(Vec2::splat(4.0)),
+            Pickable::IGNORE,
+            Node {
+                position_type: PositionType::Absolute,
+                ..default()
+            },
+        ));
+    }
+}
+
+/// Saves the character preview as a PNG
+fn export_avatar(
+    _: On<Pointer<Click>>,
+    preview: Single<&ImageNode, With<CharacterPreviewSprite>>,
+    images: Res<Assets<Image>>,
+) {
+    let Some(sheet) = images.get(&preview.image) else {
+        warn!("Failed to save avatar: {}", AvatarError::NotReady);
+        return;
+    };
+
+    #[cfg(not(target_family = "wasm"))]
+    match crate::game::composite::save_avatar(sheet, std::path::Path::new(AVATAR_PATH)) {
+        Ok(()) => info!("Saved avatar to {AVATAR_PATH}"),
+        Err(error) => warn!("Failed to save avatar: {error}"),
+    }
+    #[cfg(target_family = "wasm")]
+    {
+        let _ = sheet;
+        warn!("Saving avatars isn't supported on the web");
+    }
+}
+
+/// System to list the saved outfits again whenever one is saved, changed, deleted or renamed
+fn update_outfit_grid(
+    mut commands: Commands,
+    presets: Res<OutfitPresets>,
+    menu_state: Res<OutfitMenuState>,
+    grid: Single<(Entity, Ref<OutfitGrid>)>,
+) {
+    let (grid, marker) = grid.into_inner();
+    if !presets.is_changed() && !menu_state.is_changed() && !marker.is_added() {
+        return;
+    }
+
+    let rows: Vec<_> = presets
+        .presets
+        .iter()
+        .map(|preset| {
+            let text = match (&menu_state.renaming, preset.season) {
+                (Some(renaming), _) if renaming.name == preset.name => {
+                    format!("{}_", renaming.draft)
                 }
+                (_, Some(season)) => format!("{} ({season})", preset.name),
+                (_, None) => preset.name.clone(),
+            };
+            (preset.name.clone(), text)
+        })
+        .collect();
+
+    commands
+        .entity(grid)
+        .despawn_related::<Children>()
+        .with_children(|parent| {
+            for (name, text) in rows {
+                parent.spawn(widget::label(text));
+                parent.spawn(widget::button_medium("Wear", wear_outfit(name.clone())));
+                parent.spawn(widget::button_medium(
+                    "Season",
+                    cycle_outfit_season(name.clone()),
+                ));
+                parent.spawn(widget::button_medium("Rename", rename_outfit(name.clone())));
+                parent.spawn(widget::button_medium(
+                    "Delete",
+                    delete_outfit_on_confirm(name),
+                ));
             }
         });
+}
+
+fn update_outfit_status(
+    menu_state: Res<OutfitMenuState>,
+    mut label: Single<&mut Text, With<OutfitStatusLabel>>,
+) {
+    if menu_state.is_changed() {
+        label.0 = menu_state.status.clone();
+    }
+}
+
+/// Dresses the character in a random outfit of unlocked items
+fn randomise_outfit(
+    _: On<Pointer<Click>>,
+    wardrobe: Wardrobe,
+    unlocked_items: Res<UnlockedItems>,
+    mut state: ResMut<CharacterSelectState>,
+    mut menu_state: ResMut<OutfitMenuState>,
+) {
+    let Some(catalog) = wardrobe.catalog() else {
+        return;
+    };
+    menu_state.confirm_delete = None;
+    menu_state.status.clear();
+    state.current_layers = random_outfit(catalog, &unlocked_items, &mut rand::rng());
+}
+
+fn save_outfit(
+    _: On<Pointer<Click>>,
+    state: Res<CharacterSelectState>,
+    mut presets: ResMut<OutfitPresets>,
+    mut menu_state: ResMut<OutfitMenuState>,
+) {
+    menu_state.confirm_delete = None;
+    let name = presets.save(&state.current_layers);
+    menu_state.status = format!("Saved {name}");
+}
+
+fn wear_outfit(
+    name: String,
+) -> impl FnMut(
+    On<Pointer<Click>>,
+    Res<OutfitPresets>,
+    Wardrobe,
+    Res<UnlockedItems>,
+    ResMut<CharacterSelectState>,
+    ResMut<OutfitMenuState>,
+) {
+    move |_: On<Pointer<Click>>,
+          presets: Res<OutfitPresets>,
+          wardrobe: Wardrobe,
+          unlocked_items: Res<UnlockedItems>,
+          mut state: ResMut<CharacterSelectState>,
+          mut menu_state: ResMut<OutfitMenuState>| {
+        let (Some(preset), Some(catalog)) = (presets.get(&name), wardrobe.catalog()) else {
+            return;
+        };
+        menu_state.confirm_delete = None;
+        // Presets are shared between saves, so this save may not have unlocked everything
+        if !preset.is_wearable(catalog, &unlocked_items) {
+            menu_state.status = format!("{name} has items you haven't unlocked");
+            return;
+        }
+        menu_state.status.clear();
+        state.current_layers = preset.layers.clone();
+    }
+}
+
+fn cycle_outfit_season(
+    name: String,
+) -> impl FnMut(On<Pointer<Click>>, ResMut<OutfitPresets>, ResMut<OutfitMenuState>) {
+    move |_: On<Pointer<Click>>,
+          mut presets: ResMut<OutfitPresets>,
+          mut menu_state: ResMut<OutfitMenuState>| {
+        menu_state.confirm_delete = None;
+        menu_state.status = match presets.cycle_season(&name) {
+            Some(season) => format!("{name} will be worn in {season}"),
+            None => format!("{name} won't be worn automatically"),
+        };
+    }
+}
+
+/// Starts typing a new name for the preset
+fn rename_outfit(name: String) -> impl FnMut(On<Pointer<Click>>, ResMut<OutfitMenuState>) {
+    move |_: On<Pointer<Click>>, mut menu_state: ResMut<OutfitMenuState>| {
+        menu_state.confirm_delete = None;
+        menu_state.renaming = Some(RenamingOutfit {
+            name: name.clone(),
+            draft: name.clone(),
+        });
+        menu_state.status = format!("Type a new name for {name}, then press Enter");
+    }
+}
+
+/// System to type the new name of the preset being renamed
+///
+/// Enter saves the name, and Escape cancels renaming.
+fn type_outfit_name(
+    mut keyboard_reader: MessageReader<KeyboardInput>,
+    mut presets: ResMut<OutfitPresets>,
+    mut menu_state: ResMut<OutfitMenuState>,
+) {
+    if menu_state.renaming.is_none() {
+        keyboard_reader.clear();
+        return;
+    }
+    let menu_state = &mut *menu_state;
+    let Some(renaming) = &mut menu_state.renaming else {
+        return;
+    };
+
+    for input in keyboard_reader.read() {
+        if input.state != ButtonState::Pressed {
+            continue;
+        }
+        match &input.logical_key {
+            Key::Character(text) => renaming.push(text),
+            Key::Space => renaming.push(" "),
+            Key::Backspace => {
+                renaming.draft.pop();
+            }
+            Key::Enter => {
+                menu_state.status = match presets.rename(&renaming.name, &renaming.draft) {
+                    Ok(new_name) => {
+                        let status = format!("Renamed {} to {new_name}", renaming.name);
+                        menu_state.renaming = None;
+                        status
+                    }
+                    Err(error) => error.to_string(),
+                };
+                return;
+            }
+            Key::Escape => {
+                menu_state.renaming = None;
+                menu_state.status.clear();
+                return;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Copies a share code for the current outfit
+fn export_outfit_code(
+    _: On<Pointer<Click>>,
+    state: Res<CharacterSelectState>,
+    mut menu_state: ResMut<OutfitMenuState>,
+) {
+    menu_state.confirm_delete = None;
+    let code = share_code::encode(&state.current_layers);
+    info!("Outfit code: {code}");
+
+    menu_state.status = match copy_outfit_code(&code) {
+        Ok(()) => "Copied the outfit code".to_string(),
+        Err(()) => format!("Your outfit code is {code}"),
+    };
+}
+
+/// Wears the outfit from a pasted share code, keeping the current items for any it can't
+fn import_outfit_code(
+    _: On<Pointer<Click>>,
+    wardrobe: Wardrobe,
+    unlocked_items: Res<UnlockedItems>,
+    mut state: ResMut<CharacterSelectState>,
+    mut menu_state: ResMut<OutfitMenuState>,
+) {
+    menu_state.confirm_delete = None;
+    let Some(catalog) = wardrobe.catalog() else {
+        return;
+    };
+
+    let code = match paste_outfit_code() {
+        Ok(code) => code,
+        Err(status) => {
+            menu_state.status = status;
+            return;
+        }
+    };
+    let layers = match share_code::decode(&code) {
+        Ok(layers) => layers,
+        Err(error) => {
+            menu_state.status = error.to_string();
+            return;
+        }
+    };
+    let imported_types: Vec<_> = layers.layers.iter().map(|layer| layer.layer_type).collect();
+    let (mut wearable, issues) = share_code::wearable_layers(catalog, &unlocked_items, layers);
+
+    // Keep the current item where the code's couldn't be worn, or the code has none it needs
+    for layer in &state.current_layers.layers {
+        let replaced = wearable
+            .iter()
+            .any(|imported| imported.layer_type == layer.layer_type);
+        if !replaced
+            && (imported_types.contains(&layer.layer_type)
+                || REQUIRED_LAYER_TYPES.contains(&layer.layer_type))
+        {
+            wearable.push(layer.clone());
+        }
+    }
+    wearable.sort_by_key(|layer| layer.layer_type);
+    state.current_layers.layers = wearable;
+
+    menu_state.status = if issues.is_empty() {
+        "Imported the outfit".to_string()
+    } else {
+        let issues: Vec<_> = issues.iter().map(ToString::to_string).collect();
+        format!("Imported the outfit, except: {}", issues.join("; "))
+    };
+}
+
+/// Copies an outfit code to the system clipboard
+#[cfg(not(target_family = "wasm"))]
+fn copy_outfit_code(code: &str) -> Result<(), ()> {
+    arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.set_text(code))
+        .map_err(|error| warn!("Failed to copy the outfit code: {error}"))
+}
+
+#[cfg(target_family = "wasm")]
+fn copy_outfit_code(_code: &str) -> Result<(), ()> {
+    Err(())
+}
+
+/// Reads an outfit code from the system clipboard, or the status to show if it can't
+#[cfg(not(target_family = "wasm"))]
+fn paste_outfit_code() -> Result<String, String> {
+    arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.get_text())
+        .map_err(|error| {
+            warn!("Failed to paste an outfit code: {error}");
+            "Copy an outfit code first".to_string()
+        })
+}
+
+#[cfg(target_family = "wasm")]
+fn paste_outfit_code() -> Result<String, String> {
+    Err("Pasting outfit codes isn't supported on the web".to_string())
+}
+
+/// Deletes the preset on the second click, so an outfit can't be lost by accident
+fn delete_outfit_on_confirm(
+    name: String,
+) -> impl FnMut(On<Pointer<Click>>, ResMut<OutfitPresets>, ResMut<OutfitMenuState>) {
+    move |_: On<Pointer<Click>>,
+          mut presets: ResMut<OutfitPresets>,
+          mut menu_state: ResMut<OutfitMenuState>| {
+        if menu_state.confirm_delete.as_ref() == Some(&name) {
+            presets.delete(&name);
+            menu_state.confirm_delete = None;
+            menu_state.status = format!("Deleted {name}");
+        } else {
+            menu_state.confirm_delete = Some(name.clone());
+            menu_state.status = format!("Press Delete again to delete {name}");
+        }
     }
 }
 
@@ -529,7 +1101,15 @@ fn go_back_on_click(_: On<Pointer<Click>>, mut next_menu: ResMut<NextState<Menu>
     next_menu.set(Menu::Pause);
 }
 
-fn go_back(action_query: Query<&ActionState<Action>>, mut next_menu: ResMut<NextState<Menu>>) {
+fn go_back(
+    action_query: Query<&ActionState<Action>>,
+    menu_state: Res<OutfitMenuState>,
+    mut next_menu: ResMut<NextState<Menu>>,
+) {
+    // Escape cancels renaming instead, and menu keys may be part of the new name
+    if menu_state.renaming.is_some() {
+        return;
+    }
     if let Ok(action_state) = action_query.single()
         && action_state.just_pressed(&Action::Menu)
     {
@@ -557,8 +1137,9 @@ fn confirm_selection(
 
 const LINE_HEIGHT: f32 = 100.;
 
-/// UI scrolling event.
+/// UI scrolling event, bubbling up from the hovered item to the scroll area.
 #[derive(EntityEvent, Debug)]
+#[entity_event(propagate, auto_propagate)]
 struct Scroll {
     entity: Entity,
     delta: Vec2,
@@ -609,6 +1190,10 @@ fn on_scroll_handler(
             delta.y = 0.;
         }
     }
+
+    if *delta == Vec2::ZERO {
+        scroll.propagate(false);
+    }
 }
 
 // Update scrollbar thumb color on hover/drag
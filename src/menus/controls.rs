@@ -0,0 +1,245 @@
+//! The controls menu, for rebinding keys and gamepad buttons.
+
+use bevy::{ecs::spawn::SpawnWith, prelude::*};
+use bevy_pkv::prelude::*;
+use leafwing_input_manager::prelude::*;
+
+use crate::{
+    game::controls::{
+        Action, Binding, InputDevice, REBINDABLE_ACTIONS, binding_label, default_input_map, rebind,
+        save_input_map,
+    },
+    menus::Menu,
+    theme::prelude::*,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<RebindState>();
+    app.add_systems(OnEnter(Menu::Controls), spawn_controls_menu);
+    app.add_systems(OnExit(Menu::Controls), stop_capture);
+    app.add_systems(
+        Update,
+        (
+            go_back,
+            capture_binding,
+            update_binding_labels,
+            update_rebind_status,
+        )
+            .chain()
+            .run_if(in_state(Menu::Controls)),
+    );
+}
+
+/// Resource tracking which binding, if any, is waiting for a new key or button
+#[derive(Resource, Debug, Default)]
+struct RebindState {
+    capturing: Option<(Action, InputDevice)>,
+    status: String,
+}
+
+/// Label showing the current binding of an action on a device
+#[derive(Component, Debug)]
+struct BindingLabel {
+    action: Action,
+    device: InputDevice,
+}
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct RebindStatusLabel;
+
+fn spawn_controls_menu(mut commands: Commands, mut rebind_state: ResMut<RebindState>) {
+    *rebind_state = RebindState::default();
+
+    commands.spawn((
+        widget::ui_root("Controls Menu"),
+        GlobalZIndex(2),
+        DespawnOnExit(Menu::Controls),
+        children![
+            widget::header("Controls"),
+            controls_grid(),
+            (widget::label(""), RebindStatusLabel),
+            widget::button("Reset to defaults", reset_bindings),
+            widget::button("Back", go_back_on_click),
+        ],
+    ));
+}
+
+fn controls_grid() -> impl Bundle {
+    (
+        Name::new("Controls Grid"),
+        Node {
+            display: Display::Grid,
+            row_gap: px(10),
+            column_gap: px(30),
+            grid_template_columns: RepeatedGridTrack::px(3, 250.0),
+            ..default()
+        },
+        Children::spawn(SpawnWith(|parent: &mut ChildSpawner| {
+            for header in ["Action", "Keyboard", "Gamepad"] {
+                parent.spawn(widget::label(header));
+            }
+            parent.spawn(widget::label(Action::Run.to_string()));
+            parent.spawn(widget::label("WASD / Arrows"));
+            parent.spawn(widget::label("Left Stick"));
+
+            for action in REBINDABLE_ACTIONS {
+                parent.spawn(widget::label(action.to_string()));
+                parent.spawn(binding_widget(action, InputDevice::Keyboard));
+                parent.spawn(binding_widget(action, InputDevice::Gamepad));
+            }
+        })),
+    )
+}
+
+fn binding_widget(action: Action, device: InputDevice) -> impl Bundle {
+    (
+        Name::new(format!("{action} {device:?} Binding")),
+        Node {
+            justify_self: JustifySelf::Start,
+            ..default()
+        },
+        children![
+            (
+                Name::new("Current Binding"),
+                Node {
+                    padding: UiRect::right(px(10)),
+                    ..default()
+                },
+                children![(widget::label(""), BindingLabel { action, device })],
+            ),
+            widget::button_small(
+                ">",
+                move |_: On<Pointer<Click>>, mut rebind_state: ResMut<RebindState>| {
+                    rebind_state.capturing = Some((action, device));
+                }
+            ),
+        ],
+    )
+}
+
+fn stop_capture(mut rebind_state: ResMut<RebindState>) {
+    rebind_state.capturing = None;
+}
+
+/// System to bind the next key or button pressed to the action being rebound
+///
+/// Escape cancels, so it can always be used to leave the menu.
+fn capture_binding(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    mut rebind_state: ResMut<RebindState>,
+    mut input_query: Query<&mut InputMap<Action>>,
+    mut pkv: ResMut<PkvStore>,
+) {
+    let Some((action, device)) = rebind_state.capturing else {
+        return;
+    };
+
+    if keyboard.just_pressed(KeyCode::Escape) {
+        rebind_state.capturing = None;
+        rebind_state.status.clear();
+        return;
+    }
+
+    let binding = match device {
+        InputDevice::Keyboard => keyboard
+            .get_just_pressed()
+            .next()
+            .copied()
+            .map(Binding::Key),
+        InputDevice::Gamepad => gamepads
+            .iter()
+            .find_map(|gamepad| gamepad.get_just_pressed().next().copied())
+            .map(Binding::Gamepad),
+        InputDevice::Touch => None,
+    };
+    let Some(binding) = binding else {
+        return;
+    };
+    let Ok(mut input_map) = input_query.single_mut() else {
+        return;
+    };
+
+    rebind_state.capturing = None;
+    rebind_state.status = match rebind(&mut input_map, action, binding) {
+        Ok(()) => {
+            save_input_map(&mut pkv, &input_map);
+            format!("{action} bound to {}", binding.label())
+        }
+        Err(conflict) => format!("{} is already bound to {conflict}", binding.label()),
+    };
+}
+
+fn update_binding_labels(
+    rebind_state: Res<RebindState>,
+    input_query: Query<Ref<InputMap<Action>>>,
+    mut label_query: Query<(&BindingLabel, &mut Text)>,
+) {
+    let Ok(input_map) = input_query.single() else {
+        return;
+    };
+    if !rebind_state.is_changed() && !input_map.is_changed() {
+        return;
+    }
+
+    for (label, mut text) in &mut label_query {
+        text.0 = if rebind_state.capturing == Some((label.action, label.device)) {
+            "...".to_string()
+        } else {
+            binding_label(&input_map, label.action, label.device).unwrap_or_else(|| "-".into())
+        };
+    }
+}
+
+fn update_rebind_status(
+    rebind_state: Res<RebindState>,
+    mut label: Single<&mut Text, With<RebindStatusLabel>>,
+) {
+    if !rebind_state.is_changed() {
+        return;
+    }
+
+    label.0 = match rebind_state.capturing {
+        Some((action, InputDevice::Keyboard)) => {
+            format!("Press a key for {action} (Esc to cancel)")
+        }
+        Some((action, InputDevice::Gamepad)) => {
+            format!("Press a button for {action} (Esc to cancel)")
+        }
+        Some((_, InputDevice::Touch)) | None => rebind_state.status.clone(),
+    };
+}
+
+fn reset_bindings(
+    _: On<Pointer<Click>>,
+    mut rebind_state: ResMut<RebindState>,
+    mut input_query: Query<&mut InputMap<Action>>,
+    mut pkv: ResMut<PkvStore>,
+) {
+    let Ok(mut input_map) = input_query.single_mut() else {
+        return;
+    };
+
+    *input_map = default_input_map();
+    save_input_map(&mut pkv, &input_map);
+    rebind_state.capturing = None;
+    rebind_state.status = "Controls reset to defaults".to_string();
+}
+
+fn go_back_on_click(_: On<Pointer<Click>>, mut next_menu: ResMut<NextState<Menu>>) {
+    next_menu.set(Menu::Settings);
+}
+
+fn go_back(
+    action_query: Query<&ActionState<Action>>,
+    rebind_state: Res<RebindState>,
+    mut next_menu: ResMut<NextState<Menu>>,
+) {
+    if rebind_state.capturing.is_none()
+        && let Ok(action_state) = action_query.single()
+        && action_state.just_pressed(&Action::Menu)
+    {
+        next_menu.set(Menu::Settings);
+    }
+}
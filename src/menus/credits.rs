@@ -1,14 +1,18 @@
 //! The credits menu.
 
-use bevy::{ecs::spawn::SpawnIter, input::common_conditions::input_just_pressed, prelude::*};
+use bevy::{ecs::spawn::SpawnIter, prelude::*};
 
-use crate::{menus::Menu, theme::prelude::*};
+use crate::{
+    game::controls::{Action, action_just_pressed},
+    menus::Menu,
+    theme::prelude::*,
+};
 
 pub(super) fn plugin(app: &mut App) {
     app.add_systems(OnEnter(Menu::Credits), spawn_credits_menu);
     app.add_systems(
         Update,
-        go_back.run_if(in_state(Menu::Credits).and(input_just_pressed(KeyCode::Escape))),
+        go_back.run_if(in_state(Menu::Credits).and(action_just_pressed(Action::Menu))),
     );
 }
 
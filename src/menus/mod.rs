@@ -1,9 +1,12 @@
 //! The game's menus and transitions between them.
 
 mod character_select;
+mod controls;
 mod credits;
 mod main;
 mod pause;
+mod replays;
+mod save_slots;
 mod settings;
 
 use bevy::prelude::*;
@@ -14,9 +17,12 @@ pub(super) fn plugin(app: &mut App) {
     app.add_plugins((
         credits::plugin,
         character_select::plugin,
+        controls::plugin,
         main::plugin,
         settings::plugin,
         pause::plugin,
+        replays::plugin,
+        save_slots::plugin,
     ));
 }
 
@@ -25,8 +31,11 @@ pub enum Menu {
     #[default]
     None,
     Main,
+    SaveSlots,
     Credits,
     CharacterSelect,
     Settings,
+    Controls,
     Pause,
+    Replays,
 }
@@ -24,6 +24,7 @@ fn spawn_pause_menu(mut commands: Commands) {
             widget::button("Continue", close_menu),
             widget::button("Character Select", open_character_select),
             widget::button("Settings", open_settings_menu),
+            widget::button("Replays", open_replays_menu),
             widget::button("Quit to title", quit_to_title),
         ],
         #[cfg(feature = "dev_native")]
@@ -32,6 +33,7 @@ fn spawn_pause_menu(mut commands: Commands) {
             widget::button("Continue", close_menu),
             widget::button("Character Select", open_character_select),
             widget::button("Settings", open_settings_menu),
+            widget::button("Replays", open_replays_menu),
             widget::button("Increment season", increment_season),
             widget::button("Increment level", increment_level),
             widget::button("Quit to title", quit_to_title),
@@ -58,6 +60,10 @@ fn open_settings_menu(_: On<Pointer<Click>>, mut next_menu: ResMut<NextState<Men
     next_menu.set(Menu::Settings);
 }
 
+fn open_replays_menu(_: On<Pointer<Click>>, mut next_menu: ResMut<NextState<Menu>>) {
+    next_menu.set(Menu::Replays);
+}
+
 fn open_character_select(_: On<Pointer<Click>>, mut next_menu: ResMut<NextState<Menu>>) {
     next_menu.set(Menu::CharacterSelect);
 }
@@ -0,0 +1,137 @@
+//! The replays menu, opened from the pause menu to watch and share the level's fastest run.
+//!
+//! Replays are exported to and imported from a shared folder, so teammates can swap files to
+//! watch each other's best runs.
+
+use bevy::prelude::*;
+
+use crate::{
+    game::controls::{Action, action_just_pressed},
+    menus::Menu,
+    theme::prelude::*,
+};
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::game::{
+    level::LevelSeed,
+    replay::{
+        BEST_REPLAY_DIR, PlayedLevel, Replay, SHARED_REPLAY_DIR, export_best_replay,
+        import_replays, play_replay, replay_file_name,
+    },
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<ReplayMenuState>();
+    app.add_systems(OnEnter(Menu::Replays), spawn_replays_menu);
+    app.add_systems(
+        Update,
+        go_back.run_if(in_state(Menu::Replays).and(action_just_pressed(Action::Menu))),
+    );
+    app.add_systems(Update, update_replay_status.run_if(in_state(Menu::Replays)));
+}
+
+/// Resource holding the replays menu's status message
+#[derive(Resource, Debug, Default)]
+struct ReplayMenuState {
+    status: String,
+}
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct ReplayStatusLabel;
+
+fn spawn_replays_menu(mut commands: Commands, mut menu_state: ResMut<ReplayMenuState>) {
+    *menu_state = ReplayMenuState::default();
+
+    commands.spawn((
+        widget::ui_root("Replays Menu"),
+        GlobalZIndex(2),
+        DespawnOnExit(Menu::Replays),
+        #[cfg(not(target_arch = "wasm32"))]
+        children![
+            widget::header("Replays"),
+            (widget::label(""), ReplayStatusLabel),
+            widget::button("Watch best run", watch_best_run),
+            widget::button("Export best run", export_best_run),
+            widget::button("Import runs", import_runs),
+            widget::button("Back", go_back_on_click),
+        ],
+        #[cfg(target_arch = "wasm32")]
+        children![
+            widget::header("Replays"),
+            (
+                widget::label("Replays are only saved by the desktop version"),
+                ReplayStatusLabel
+            ),
+            widget::button("Back", go_back_on_click),
+        ],
+    ));
+}
+
+/// The file name of the current level's best replay
+#[cfg(not(target_arch = "wasm32"))]
+fn best_run_file_name(played: &PlayedLevel, level_seed: &LevelSeed) -> String {
+    replay_file_name(played.season(), played.game_level(), level_seed.0)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn watch_best_run(
+    _: On<Pointer<Click>>,
+    mut commands: Commands,
+    played: PlayedLevel,
+    level_seed: Res<LevelSeed>,
+    mut menu_state: ResMut<ReplayMenuState>,
+) {
+    let file_name = best_run_file_name(&played, &level_seed);
+    match Replay::load(std::path::Path::new(BEST_REPLAY_DIR).join(file_name)) {
+        Ok(replay) => commands.queue(play_replay(replay)),
+        Err(error) => {
+            warn!("Failed to load replay: {error}");
+            menu_state.status = "No run of this level has been saved yet".to_string();
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn export_best_run(
+    _: On<Pointer<Click>>,
+    played: PlayedLevel,
+    level_seed: Res<LevelSeed>,
+    mut menu_state: ResMut<ReplayMenuState>,
+) {
+    let file_name = best_run_file_name(&played, &level_seed);
+    menu_state.status = match export_best_replay(BEST_REPLAY_DIR, SHARED_REPLAY_DIR, &file_name) {
+        Ok(path) => format!("Exported to {}", path.display()),
+        Err(error) => {
+            warn!("Failed to export replay: {error}");
+            "No run of this level has been saved yet".to_string()
+        }
+    };
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn import_runs(_: On<Pointer<Click>>, mut menu_state: ResMut<ReplayMenuState>) {
+    menu_state.status = match import_replays(SHARED_REPLAY_DIR, BEST_REPLAY_DIR) {
+        Ok(0) => format!("No faster runs in {SHARED_REPLAY_DIR}"),
+        Ok(1) => "Imported 1 faster run".to_string(),
+        Ok(count) => format!("Imported {count} faster runs"),
+        Err(error) => format!("Couldn't import runs: {error}"),
+    };
+}
+
+fn update_replay_status(
+    menu_state: Res<ReplayMenuState>,
+    mut label: Single<&mut Text, With<ReplayStatusLabel>>,
+) {
+    if menu_state.is_changed() && !menu_state.status.is_empty() {
+        label.0 = menu_state.status.clone();
+    }
+}
+
+fn go_back_on_click(_: On<Pointer<Click>>, mut next_menu: ResMut<NextState<Menu>>) {
+    next_menu.set(Menu::Pause);
+}
+
+fn go_back(mut next_menu: ResMut<NextState<Menu>>) {
+    next_menu.set(Menu::Pause);
+}
@@ -0,0 +1,228 @@
+//! The save slot picker, opened from the main menu before playing.
+
+use bevy::prelude::*;
+use bevy_pkv::prelude::*;
+
+use crate::{
+    asset_tracking::ResourceHandles,
+    game::{
+        controls::{Action, action_just_pressed},
+        save::{
+            SaveError, SaveSlots, SlotLoadWarning, copy_slot, create_slot, delete_slot, load_slot,
+            read_slot,
+        },
+    },
+    menus::Menu,
+    screens::Screen,
+    theme::prelude::*,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<SlotMenuState>();
+    app.add_systems(OnEnter(Menu::SaveSlots), spawn_save_slots_menu);
+    app.add_systems(
+        Update,
+        go_back.run_if(in_state(Menu::SaveSlots).and(action_just_pressed(Action::Menu))),
+    );
+    app.add_systems(
+        Update,
+        (update_slot_grid, update_slot_status).run_if(in_state(Menu::SaveSlots)),
+    );
+}
+
+/// The most slots that fit on screen
+const MAX_SLOTS: usize = 5;
+
+/// Resource holding the slot menu's status message, and the slot waiting to be deleted
+#[derive(Resource, Debug, Default)]
+struct SlotMenuState {
+    confirm_delete: Option<String>,
+    status: String,
+}
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct SlotGrid;
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct SlotStatusLabel;
+
+fn spawn_save_slots_menu(
+    mut commands: Commands,
+    mut menu_state: ResMut<SlotMenuState>,
+    mut warning: ResMut<SlotLoadWarning>,
+) {
+    // A slot that failed to load is explained here once, then the warning is dismissed
+    *menu_state = SlotMenuState {
+        status: warning.0.take().unwrap_or_default(),
+        ..default()
+    };
+
+    commands.spawn((
+        widget::ui_root("Save Slots Menu"),
+        GlobalZIndex(2),
+        DespawnOnExit(Menu::SaveSlots),
+        children![
+            widget::header("Choose a save"),
+            (
+                Name::new("Slot Grid"),
+                SlotGrid,
+                Node {
+                    display: Display::Grid,
+                    row_gap: px(10),
+                    column_gap: px(20),
+                    grid_template_columns: vec![
+                        GridTrack::px(450.0),
+                        GridTrack::auto(),
+                        GridTrack::auto(),
+                        GridTrack::auto(),
+                    ],
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+            ),
+            (widget::label(""), SlotStatusLabel),
+            widget::button("New save", new_slot),
+            widget::button("Back", go_back_on_click),
+        ],
+    ));
+}
+
+/// System to list the slots again whenever one is added, copied or deleted
+fn update_slot_grid(
+    mut commands: Commands,
+    slots: Res<SaveSlots>,
+    pkv: Res<PkvStore>,
+    grid: Single<(Entity, Ref<SlotGrid>)>,
+) {
+    let (grid, marker) = grid.into_inner();
+    if !slots.is_changed() && !marker.is_added() {
+        return;
+    }
+
+    let rows: Vec<_> = slots
+        .names
+        .iter()
+        .map(|name| {
+            let summary = match read_slot(&pkv, name) {
+                Ok(slot) => slot.summary(),
+                Err(SaveError::UnsupportedVersion(_)) => "Saved by a newer version".to_string(),
+                Err(_) => "Unreadable".to_string(),
+            };
+            let active = if slots.active.as_ref() == Some(name) {
+                "> "
+            } else {
+                ""
+            };
+            (name.clone(), format!("{active}{name}: {summary}"))
+        })
+        .collect();
+
+    commands
+        .entity(grid)
+        .despawn_related::<Children>()
+        .with_children(|parent| {
+            for (name, text) in rows {
+                parent.spawn(widget::label(text));
+                parent.spawn(widget::button_medium("Play", play_slot(name.clone())));
+                parent.spawn(widget::button_medium("Copy", copy_on_click(name.clone())));
+                parent.spawn(widget::button_medium("Delete", delete_on_confirm(name)));
+            }
+        });
+}
+
+fn play_slot(
+    name: String,
+) -> impl FnMut(
+    On<Pointer<Click>>,
+    Commands,
+    Res<PkvStore>,
+    Res<ResourceHandles>,
+    ResMut<SlotMenuState>,
+    ResMut<NextState<Screen>>,
+) {
+    move |_: On<Pointer<Click>>,
+          mut commands: Commands,
+          pkv: Res<PkvStore>,
+          resource_handles: Res<ResourceHandles>,
+          mut menu_state: ResMut<SlotMenuState>,
+          mut next_screen: ResMut<NextState<Screen>>| {
+        // Don't start playing (and saving over) a slot that can't be read
+        if let Err(error) = read_slot(&pkv, &name) {
+            menu_state.status = format!("Can't load {name}: {error}");
+            return;
+        }
+        commands.queue(load_slot(name.clone()));
+        if resource_handles.is_all_done() {
+            next_screen.set(Screen::Gameplay);
+        } else {
+            next_screen.set(Screen::Loading);
+        }
+    }
+}
+
+fn copy_on_click(
+    name: String,
+) -> impl FnMut(On<Pointer<Click>>, Commands, Res<SaveSlots>, ResMut<SlotMenuState>) {
+    move |_: On<Pointer<Click>>,
+          mut commands: Commands,
+          slots: Res<SaveSlots>,
+          mut menu_state: ResMut<SlotMenuState>| {
+        menu_state.confirm_delete = None;
+        if slots.names.len() >= MAX_SLOTS {
+            menu_state.status = "No room for another save".to_string();
+            return;
+        }
+        commands.queue(copy_slot(name.clone()));
+        menu_state.status = format!("Copied {name}");
+    }
+}
+
+/// Deletes the slot on the second click, so a save can't be lost by accident
+fn delete_on_confirm(
+    name: String,
+) -> impl FnMut(On<Pointer<Click>>, Commands, ResMut<SlotMenuState>) {
+    move |_: On<Pointer<Click>>, mut commands: Commands, mut menu_state: ResMut<SlotMenuState>| {
+        if menu_state.confirm_delete.as_ref() == Some(&name) {
+            commands.queue(delete_slot(name.clone()));
+            menu_state.confirm_delete = None;
+            menu_state.status = format!("Deleted {name}");
+        } else {
+            menu_state.confirm_delete = Some(name.clone());
+            menu_state.status = format!("Press Delete again to delete {name}");
+        }
+    }
+}
+
+fn new_slot(
+    _: On<Pointer<Click>>,
+    mut commands: Commands,
+    slots: Res<SaveSlots>,
+    mut menu_state: ResMut<SlotMenuState>,
+) {
+    menu_state.confirm_delete = None;
+    if slots.names.len() >= MAX_SLOTS {
+        menu_state.status = "No room for another save".to_string();
+        return;
+    }
+    commands.queue(create_slot());
+    menu_state.status.clear();
+}
+
+fn update_slot_status(
+    menu_state: Res<SlotMenuState>,
+    mut label: Single<&mut Text, With<SlotStatusLabel>>,
+) {
+    if menu_state.is_changed() {
+        label.0 = menu_state.status.clone();
+    }
+}
+
+fn go_back_on_click(_: On<Pointer<Click>>, mut next_menu: ResMut<NextState<Menu>>) {
+    next_menu.set(Menu::Main);
+}
+
+fn go_back(mut next_menu: ResMut<NextState<Menu>>) {
+    next_menu.set(Menu::Main);
+}
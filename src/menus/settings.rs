@@ -2,20 +2,38 @@
 //!
 //! Additional settings and accessibility options should go here.
 
-use bevy::{audio::Volume, input::common_conditions::input_just_pressed, prelude::*};
+use bevy::prelude::*;
 
-use crate::{menus::Menu, screens::Screen, theme::prelude::*};
+use crate::{
+    audio::{VolumeSetting, VolumeSettings},
+    game::{
+        controls::{Action, action_just_pressed},
+        ghost::GhostSettings,
+        rewind::RewindSettings,
+    },
+    menus::Menu,
+    pixel_camera::PixelScaling,
+    screens::Screen,
+    theme::prelude::*,
+};
 
 pub(super) fn plugin(app: &mut App) {
     app.add_systems(OnEnter(Menu::Settings), spawn_settings_menu);
     app.add_systems(
         Update,
-        go_back.run_if(in_state(Menu::Settings).and(input_just_pressed(KeyCode::Escape))),
+        go_back.run_if(in_state(Menu::Settings).and(action_just_pressed(Action::Menu))),
     );
 
     app.add_systems(
         Update,
-        update_global_volume_label.run_if(in_state(Menu::Settings)),
+        (
+            update_volume_labels,
+            update_mute_label,
+            update_pixel_scaling_label,
+            update_ghost_label,
+            update_rewind_label,
+        )
+            .run_if(in_state(Menu::Settings)),
     );
 }
 
@@ -28,12 +46,14 @@ fn spawn_settings_menu(mut commands: Commands) {
         children![
             widget::header("Settings"),
             settings_grid(),
+            widget::button("Controls", open_controls_menu),
             widget::button("Back", go_back_on_click),
         ],
         #[cfg(feature = "dev_native")]
         children![
             widget::header("Settings"),
             settings_grid(),
+            widget::button("Controls", open_controls_menu),
             widget::button("Encrypt assets", crate::asset_format::encrypt_raw_assets),
             widget::button("Back", go_back_on_click),
         ],
@@ -58,20 +78,68 @@ fn settings_grid() -> impl Bundle {
                     ..default()
                 }
             ),
-            global_volume_widget(),
+            volume_widget(VolumeSetting::Master),
+            (
+                widget::label("Music Volume"),
+                Node {
+                    justify_self: JustifySelf::End,
+                    ..default()
+                }
+            ),
+            volume_widget(VolumeSetting::Music),
+            (
+                widget::label("Effects Volume"),
+                Node {
+                    justify_self: JustifySelf::End,
+                    ..default()
+                }
+            ),
+            volume_widget(VolumeSetting::SoundEffects),
+            (
+                widget::label("Mute"),
+                Node {
+                    justify_self: JustifySelf::End,
+                    ..default()
+                }
+            ),
+            mute_widget(),
+            (
+                widget::label("Pixel Scaling"),
+                Node {
+                    justify_self: JustifySelf::End,
+                    ..default()
+                }
+            ),
+            pixel_scaling_widget(),
+            (
+                widget::label("Ghost"),
+                Node {
+                    justify_self: JustifySelf::End,
+                    ..default()
+                }
+            ),
+            ghost_widget(),
+            (
+                widget::label("Rewind"),
+                Node {
+                    justify_self: JustifySelf::End,
+                    ..default()
+                }
+            ),
+            rewind_widget(),
         ],
     )
 }
 
-fn global_volume_widget() -> impl Bundle {
+fn volume_widget(setting: VolumeSetting) -> impl Bundle {
     (
-        Name::new("Global Volume Widget"),
+        Name::new(format!("{setting:?} Volume Widget")),
         Node {
             justify_self: JustifySelf::Start,
             ..default()
         },
         children![
-            widget::button_small("-", lower_global_volume),
+            widget::button_small("-", adjust_volume(setting, -VOLUME_STEP)),
             (
                 Name::new("Current Volume"),
                 Node {
@@ -79,36 +147,201 @@ fn global_volume_widget() -> impl Bundle {
                     justify_content: JustifyContent::Center,
                     ..default()
                 },
-                children![(widget::label(""), GlobalVolumeLabel)],
+                children![(widget::label(""), VolumeLabel(setting))],
+            ),
+            widget::button_small("+", adjust_volume(setting, VOLUME_STEP)),
+        ],
+    )
+}
+
+/// How much each click of a volume button changes the volume by
+const VOLUME_STEP: f32 = 0.05;
+
+fn adjust_volume(
+    setting: VolumeSetting,
+    step: f32,
+) -> impl FnMut(On<Pointer<Click>>, ResMut<VolumeSettings>) {
+    move |_: On<Pointer<Click>>, mut settings: ResMut<VolumeSettings>| {
+        settings.adjust(setting, step);
+    }
+}
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct VolumeLabel(VolumeSetting);
+
+fn update_volume_labels(
+    settings: Res<VolumeSettings>,
+    mut label_query: Query<(&mut Text, &VolumeLabel)>,
+) {
+    for (mut text, label) in &mut label_query {
+        let percent = 100.0 * settings.get(label.0);
+        text.0 = format!("{percent:3.0}%");
+    }
+}
+
+fn mute_widget() -> impl Bundle {
+    (
+        Name::new("Mute Widget"),
+        Node {
+            justify_self: JustifySelf::Start,
+            ..default()
+        },
+        children![
+            widget::button_small("<", toggle_mute),
+            (
+                Name::new("Current Mute Setting"),
+                Node {
+                    padding: UiRect::horizontal(px(10)),
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                children![(widget::label(""), MuteLabel)],
+            ),
+            widget::button_small(">", toggle_mute),
+        ],
+    )
+}
+
+fn toggle_mute(_: On<Pointer<Click>>, mut settings: ResMut<VolumeSettings>) {
+    settings.muted = !settings.muted;
+}
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct MuteLabel;
+
+fn update_mute_label(settings: Res<VolumeSettings>, mut label: Single<&mut Text, With<MuteLabel>>) {
+    label.0 = if settings.muted { "On" } else { "Off" }.to_string();
+}
+
+fn pixel_scaling_widget() -> impl Bundle {
+    (
+        Name::new("Pixel Scaling Widget"),
+        Node {
+            justify_self: JustifySelf::Start,
+            ..default()
+        },
+        children![
+            widget::button_small("<", toggle_pixel_scaling),
+            (
+                Name::new("Current Scaling"),
+                Node {
+                    padding: UiRect::horizontal(px(10)),
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                children![(widget::label(""), PixelScalingLabel)],
+            ),
+            widget::button_small(">", toggle_pixel_scaling),
+        ],
+    )
+}
+
+fn toggle_pixel_scaling(_: On<Pointer<Click>>, mut scaling: ResMut<PixelScaling>) {
+    *scaling = match *scaling {
+        PixelScaling::Fit => PixelScaling::Integer,
+        PixelScaling::Integer => PixelScaling::Fit,
+    };
+}
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct PixelScalingLabel;
+
+fn update_pixel_scaling_label(
+    scaling: Res<PixelScaling>,
+    mut label: Single<&mut Text, With<PixelScalingLabel>>,
+) {
+    label.0 = match *scaling {
+        PixelScaling::Fit => "Fit".to_string(),
+        PixelScaling::Integer => "Integer".to_string(),
+    };
+}
+
+fn ghost_widget() -> impl Bundle {
+    (
+        Name::new("Ghost Widget"),
+        Node {
+            justify_self: JustifySelf::Start,
+            ..default()
+        },
+        children![
+            widget::button_small("<", toggle_ghost),
+            (
+                Name::new("Current Ghost Setting"),
+                Node {
+                    padding: UiRect::horizontal(px(10)),
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                children![(widget::label(""), GhostLabel)],
             ),
-            widget::button_small("+", raise_global_volume),
+            widget::button_small(">", toggle_ghost),
         ],
     )
 }
 
-const MIN_VOLUME: f32 = 0.0;
-const MAX_VOLUME: f32 = 3.0;
+fn toggle_ghost(_: On<Pointer<Click>>, mut settings: ResMut<GhostSettings>) {
+    settings.enabled = !settings.enabled;
+}
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct GhostLabel;
 
-fn lower_global_volume(_: On<Pointer<Click>>, mut global_volume: ResMut<GlobalVolume>) {
-    let linear = (global_volume.volume.to_linear() - 0.1).max(MIN_VOLUME);
-    global_volume.volume = Volume::Linear(linear);
+fn update_ghost_label(
+    settings: Res<GhostSettings>,
+    mut label: Single<&mut Text, With<GhostLabel>>,
+) {
+    label.0 = if settings.enabled { "On" } else { "Off" }.to_string();
+}
+
+fn rewind_widget() -> impl Bundle {
+    (
+        Name::new("Rewind Widget"),
+        Node {
+            justify_self: JustifySelf::Start,
+            ..default()
+        },
+        children![
+            widget::button_small("-", adjust_rewind(-REWIND_STEP)),
+            (
+                Name::new("Current Rewind Setting"),
+                Node {
+                    padding: UiRect::horizontal(px(10)),
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                children![(widget::label(""), RewindLabel)],
+            ),
+            widget::button_small("+", adjust_rewind(REWIND_STEP)),
+        ],
+    )
 }
 
-fn raise_global_volume(_: On<Pointer<Click>>, mut global_volume: ResMut<GlobalVolume>) {
-    let linear = (global_volume.volume.to_linear() + 0.1).min(MAX_VOLUME);
-    global_volume.volume = Volume::Linear(linear);
+/// How much each click of a rewind button changes the history by, in seconds
+const REWIND_STEP: f32 = 5.0;
+
+fn adjust_rewind(step: f32) -> impl FnMut(On<Pointer<Click>>, ResMut<RewindSettings>) {
+    move |_: On<Pointer<Click>>, mut settings: ResMut<RewindSettings>| {
+        settings.adjust(step);
+    }
 }
 
 #[derive(Component, Reflect)]
 #[reflect(Component)]
-struct GlobalVolumeLabel;
+struct RewindLabel;
 
-fn update_global_volume_label(
-    global_volume: Res<GlobalVolume>,
-    mut label: Single<&mut Text, With<GlobalVolumeLabel>>,
+fn update_rewind_label(
+    settings: Res<RewindSettings>,
+    mut label: Single<&mut Text, With<RewindLabel>>,
 ) {
-    let percent = 100.0 * global_volume.volume.to_linear();
-    label.0 = format!("{percent:3.0}%");
+    label.0 = format!("{:.0}s", settings.history_secs);
+}
+
+fn open_controls_menu(_: On<Pointer<Click>>, mut next_menu: ResMut<NextState<Menu>>) {
+    next_menu.set(Menu::Controls);
 }
 
 fn go_back_on_click(
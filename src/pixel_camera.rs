@@ -4,10 +4,14 @@ use bevy::prelude::*;
 use bevy::render::render_resource::{
     Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
 };
-use bevy::window::WindowResized;
+use bevy::window::{PrimaryWindow, WindowResized};
+use bevy_pkv::prelude::*;
+use serde::{Deserialize, Serialize};
 
 pub const GAME_HEIGHT: u32 = 320;
-pub const MAX_WIDTH: u32 = 16_384;
+
+/// Distance between the pixel camera's ears for spatial audio, in world pixels
+const LISTENER_EAR_GAP: f32 = 200.0;
 
 #[derive(Component)]
 pub struct PixelCamera;
@@ -15,15 +19,48 @@ pub struct PixelCamera;
 #[derive(Component)]
 pub struct MainCamera;
 
+/// Marker component for the sprite that displays the pixel camera's render target
+#[derive(Component)]
+struct Canvas;
+
+/// Resource describing the image the pixel camera renders into
+#[derive(Resource, Debug, Clone)]
+pub struct PixelCanvas {
+    pub image: Handle<Image>,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// How the pixel canvas is scaled up to fill the window
+#[derive(
+    Clone, Copy, Debug, Default, Eq, PartialEq, Hash, Reflect, Resource, Serialize, Deserialize,
+)]
+pub enum PixelScaling {
+    /// Scale by any factor that fits the window
+    #[default]
+    Fit,
+    /// Only scale by whole numbers, so every pixel stays crisp
+    Integer,
+}
+
 pub fn plugin(app: &mut App) {
+    app.init_persistent_resource::<PixelScaling>();
     app.add_systems(Startup, setup_pixel_camera);
-    app.add_systems(Update, fit_canvas);
+    app.add_systems(Update, (fit_canvas, apply_pixel_scaling));
 }
 
-fn setup_pixel_camera(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
+fn setup_pixel_camera(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    window: Query<&Window, With<PrimaryWindow>>,
+) {
+    let (width, height) = window
+        .single()
+        .map(|window| calculate_render_size(window.width(), window.height()))
+        .unwrap_or((GAME_HEIGHT * 16 / 9, GAME_HEIGHT));
     let canvas_size = Extent3d {
-        width: MAX_WIDTH,
-        height: GAME_HEIGHT,
+        width,
+        height,
         depth_or_array_layers: 1,
     };
 
@@ -47,6 +84,12 @@ fn setup_pixel_camera(mut commands: Commands, mut images: ResMut<Assets<Image>>)
 
     let image_handle = images.add(image);
 
+    commands.insert_resource(PixelCanvas {
+        image: image_handle.clone(),
+        width,
+        height,
+    });
+
     // Pixel camera that renders to the texture
     commands.spawn((
         Name::new("Pixel Camera"),
@@ -58,13 +101,16 @@ fn setup_pixel_camera(mut commands: Commands, mut images: ResMut<Assets<Image>>)
         },
         PixelCamera,
         RenderLayers::layer(0), // Render only entities on layer 0
+        // Ambient sounds pan and fade with their distance from the camera
+        SpatialListener::new(LISTENER_EAR_GAP),
     ));
 
     commands.spawn((
         Name::new("Canvas"),
+        Canvas,
         Sprite {
             image: image_handle.clone(),
-            custom_size: Some(Vec2::new(MAX_WIDTH as f32, GAME_HEIGHT as f32)),
+            custom_size: Some(Vec2::new(width as f32, height as f32)),
             ..default()
         },
         Transform::from_xyz(0.0, 0.0, 0.0),
@@ -85,30 +131,94 @@ fn setup_pixel_camera(mut commands: Commands, mut images: ResMut<Assets<Image>>)
     ));
 }
 
-/// Fit canvas to window on resize
+/// Fit canvas to window on resize, reallocating the render target to match
 fn fit_canvas(
     mut resize_messages: MessageReader<WindowResized>,
+    scaling: Res<PixelScaling>,
+    canvas: ResMut<PixelCanvas>,
+    images: ResMut<Assets<Image>>,
+    canvas_sprite: Single<&mut Sprite, With<Canvas>>,
+    projection: Single<&mut Projection, With<MainCamera>>,
+) {
+    let Some(window_resized) = resize_messages.read().last() else {
+        return;
+    };
+    resize_canvas(
+        window_resized.width,
+        window_resized.height,
+        *scaling,
+        canvas,
+        images,
+        canvas_sprite,
+        projection,
+    );
+}
+
+/// Refit the canvas when the scaling mode changes
+fn apply_pixel_scaling(
+    scaling: Res<PixelScaling>,
+    window: Single<&Window, With<PrimaryWindow>>,
+    canvas: ResMut<PixelCanvas>,
+    images: ResMut<Assets<Image>>,
+    canvas_sprite: Single<&mut Sprite, With<Canvas>>,
+    projection: Single<&mut Projection, With<MainCamera>>,
+) {
+    if !scaling.is_changed() {
+        return;
+    }
+    resize_canvas(
+        window.width(),
+        window.height(),
+        *scaling,
+        canvas,
+        images,
+        canvas_sprite,
+        projection,
+    );
+}
+
+fn resize_canvas(
+    window_width: f32,
+    window_height: f32,
+    scaling: PixelScaling,
+    mut canvas: ResMut<PixelCanvas>,
+    mut images: ResMut<Assets<Image>>,
+    mut canvas_sprite: Single<&mut Sprite, With<Canvas>>,
     mut projection: Single<&mut Projection, With<MainCamera>>,
 ) {
     let Projection::Orthographic(projection) = &mut **projection else {
         return;
     };
-    for window_resized in resize_messages.read() {
-        let (render_width, render_height) =
-            calculate_render_size(window_resized.width, window_resized.height);
-        let scale = calculate_scale(
-            window_resized.width,
-            window_resized.height,
-            render_width,
-            render_height,
-        );
-        projection.scale = 1. / scale;
+
+    // Minimised windows report a zero size
+    if window_width <= 0.0 || window_height <= 0.0 {
+        return;
+    }
+
+    let (render_width, render_height) = calculate_render_size(window_width, window_height);
+    if render_width != canvas.width || render_height != canvas.height {
+        if let Some(image) = images.get_mut(&canvas.image) {
+            image.resize(Extent3d {
+                width: render_width,
+                height: render_height,
+                depth_or_array_layers: 1,
+            });
+        }
+        canvas_sprite.custom_size = Some(Vec2::new(render_width as f32, render_height as f32));
+        canvas.width = render_width;
+        canvas.height = render_height;
+    }
+
+    let mut scale = calculate_scale(window_width, window_height, render_width, render_height);
+    if scaling == PixelScaling::Integer {
+        scale = scale.floor().max(1.0);
     }
+    projection.scale = 1. / scale;
 }
 
 /// Calculate the render target size based on window dimensions
 /// Maintains 320 pixels height and adjusts width based on aspect ratio
-fn calculate_render_size(window_width: f32, window_height: f32) -> (u32, u32) {
+pub fn calculate_render_size(window_width: f32, window_height: f32) -> (u32, u32) {
     let aspect_ratio = window_width / window_height;
     let height = GAME_HEIGHT;
     let width = (height as f32 * aspect_ratio).round() as u32;
@@ -1,24 +1,22 @@
 //! The screen state for the main gameplay.
 
-use bevy::{input::common_conditions::input_just_pressed, prelude::*};
+use bevy::prelude::*;
 
-use crate::{Pause, menus::Menu, screens::Screen};
+use crate::{
+    Pause,
+    game::controls::{Action, action_just_pressed},
+    menus::Menu,
+    screens::Screen,
+};
 
 pub(super) fn plugin(app: &mut App) {
-    // Toggle pause on key press.
+    // Pause when the menu action is pressed. Each menu handles going back itself.
     app.add_systems(
         Update,
-        (
-            (pause, spawn_pause_overlay, open_pause_menu).run_if(
-                in_state(Screen::Gameplay)
-                    .and(in_state(Menu::None))
-                    .and(input_just_pressed(KeyCode::KeyP).or(input_just_pressed(KeyCode::Escape))),
-            ),
-            close_menu.run_if(
-                in_state(Screen::Gameplay)
-                    .and(not(in_state(Menu::None)))
-                    .and(input_just_pressed(KeyCode::KeyP)),
-            ),
+        (pause, spawn_pause_overlay, open_pause_menu).run_if(
+            in_state(Screen::Gameplay)
+                .and(in_state(Menu::None))
+                .and(action_just_pressed(Action::Menu)),
         ),
     );
     app.add_systems(OnExit(Screen::Gameplay), (close_menu, unpause));
@@ -1,11 +1,15 @@
 //! The victory screen that appears when a level is completed.
 
 use bevy::prelude::*;
+use leafwing_input_manager::prelude::*;
 
 use crate::{
     game::{
         CompletedYear, GameLevel, PlayerLevel, Season,
         character::{COLUMNS, ROWS},
+        controls::{Action, InputDevice, binding_label},
+        unlocks::{Milestone, unlocks_at},
+        wardrobe::{Wardrobe, WardrobeCatalog},
     },
     screens::Screen,
 };
@@ -21,7 +25,16 @@ fn spawn_victory_screen(
     game_level: Res<GameLevel>,
     completed_year: Res<CompletedYear>,
     asset_server: Res<AssetServer>,
+    wardrobe: Wardrobe,
+    input_query: Query<&InputMap<Action>>,
+    device: Res<InputDevice>,
 ) {
+    let continue_label = input_query
+        .single()
+        .ok()
+        .and_then(|input_map| binding_label(input_map, Action::Select, *device))
+        .unwrap_or_else(|| "Enter".to_string());
+
     let complete_message = if game_level.0 >= 3 {
         if *season == Season::Spring {
             "You completed a whole year!".to_string()
@@ -70,7 +83,11 @@ fn spawn_victory_screen(
                 TextColor(Color::WHITE),
             ));
 
-            if game_level.0 == 3 {
+            let milestone = Milestone::completed(*season, completed_year.0);
+            if game_level.0 == 3
+                && let Some(catalog) = wardrobe.catalog()
+                && unlocks_at(catalog, milestone).next().is_some()
+            {
                 parent.spawn((
                     Name::new("Unlocked Item Title"),
                     Text::new("Unlocked clothing items:"),
@@ -80,17 +97,17 @@ fn spawn_victory_screen(
                     },
                     TextColor(Color::WHITE),
                 ));
-                parent.spawn(unlocked_items(
-                    *season,
-                    completed_year.0,
-                    asset_server.clone(),
-                ));
+                parent.spawn(unlocked_items(catalog, milestone, asset_server.clone()));
             }
 
             // Continue instruction
             parent.spawn((
                 Name::new("Continue Instruction"),
-                Text::new("Press SPACE or ENTER to continue"),
+                Text::new(if *device == InputDevice::Touch {
+                    "Tap to continue".to_string()
+                } else {
+                    format!("Press {continue_label} to continue")
+                }),
                 TextFont {
                     font_size: 24.0,
                     ..default()
@@ -100,43 +117,17 @@ fn spawn_victory_screen(
         });
 }
 
-fn unlocked_items(season: Season, completed_year: bool, asset_server: AssetServer) -> impl Bundle {
-    let items = if !completed_year {
-        match season {
-            Season::Summer => {
-                vec![
-                    "clothes/fancy-dress",
-                    "headwear/farming-hat",
-                    "headwear/mining-helmet",
-                ]
-            }
-            Season::Autumn => {
-                vec![
-                    "footwear/socks/blue",
-                    "clothes/queen-dress",
-                    "headwear/witch-hat",
-                ]
-            }
-            Season::Winter => {
-                vec![
-                    "footwear/thighhighs/1",
-                    "clothes/skirt",
-                    "headwear/santa-hat",
-                ]
-            }
-            Season::Spring => {
-                vec![
-                    "underclothes/bikini/blue",
-                    "clothes/short-skirt",
-                    "headwear/bunnyears/1",
-                ]
-            }
-        }
-    } else if season == Season::Summer {
-        vec!["underclothes/underwear/blue"]
-    } else {
-        vec![]
-    };
+fn unlocked_items(
+    catalog: &WardrobeCatalog,
+    milestone: Milestone,
+    asset_server: AssetServer,
+) -> impl Bundle {
+    let previews: Vec<_> = unlocks_at(catalog, milestone)
+        .filter_map(|item| {
+            let path = catalog.texture_path(&item.preview())?;
+            Some((item.display_name.clone(), path))
+        })
+        .collect();
     let layout = asset_server.add(TextureAtlasLayout::from_grid(
         UVec2::new(80, 64),
         COLUMNS,
@@ -157,10 +148,10 @@ fn unlocked_items(season: Season, completed_year: bool, asset_server: AssetServe
             ..default()
         },
         Children::spawn(SpawnWith(move |parent: &mut ChildSpawner| {
-            for item in items {
-                let path = format!("images/character/{}.epng", item);
+            for (display_name, path) in previews {
                 let texture = asset_server.load(path);
                 parent.spawn((
+                    Name::new(display_name),
                     ImageNode {
                         image: texture,
                         texture_atlas: Some(TextureAtlas {
@@ -177,14 +168,19 @@ fn unlocked_items(season: Season, completed_year: bool, asset_server: AssetServe
 }
 
 fn handle_continue(
-    keyboard: Res<ButtonInput<KeyCode>>,
+    action_query: Query<&ActionState<Action>>,
+    touches: Res<Touches>,
     mut season: ResMut<Season>,
     mut game_level: ResMut<GameLevel>,
     mut player_level: ResMut<PlayerLevel>,
     mut completed_year: ResMut<CompletedYear>,
     mut next_state: ResMut<NextState<Screen>>,
 ) {
-    if keyboard.just_pressed(KeyCode::Space) || keyboard.just_pressed(KeyCode::Enter) {
+    if touches.any_just_pressed()
+        || action_query
+            .single()
+            .is_ok_and(|action_state| action_state.just_pressed(&Action::Select))
+    {
         // Increment level
         game_level.0 = game_level.0.saturating_add(1);
 
@@ -1,6 +1,6 @@
 use bevy::prelude::*;
 
-use crate::{asset_tracking::LoadResource, audio::sound_effect};
+use crate::{asset_tracking::LoadResource, audio::sound_effect, theme::navigation::FocusedButton};
 
 pub(super) fn plugin(app: &mut App) {
     app.add_systems(Update, apply_interaction_palette);
@@ -12,26 +12,35 @@ pub(super) fn plugin(app: &mut App) {
 
 /// Palette for widget interactions. Add this to an entity that supports
 /// [`Interaction`]s, such as a button, to change its [`BackgroundColor`] based
-/// on the current interaction state.
+/// on the current interaction state and whether it has keyboard/gamepad focus.
 #[derive(Component, Debug, Reflect)]
 #[reflect(Component)]
 pub struct InteractionPalette {
     pub none: Color,
     pub hovered: Color,
     pub pressed: Color,
+    pub focused: Color,
 }
 
 fn apply_interaction_palette(
-    mut palette_query: Query<
-        (&Interaction, &InteractionPalette, &mut BackgroundColor),
-        Changed<Interaction>,
-    >,
+    focus: Res<FocusedButton>,
+    mut palette_query: Query<(
+        Entity,
+        Ref<Interaction>,
+        &InteractionPalette,
+        &mut BackgroundColor,
+    )>,
 ) {
-    for (interaction, palette, mut background) in &mut palette_query {
-        *background = match interaction {
-            Interaction::None => palette.none,
-            Interaction::Hovered => palette.hovered,
+    for (entity, interaction, palette, mut background) in &mut palette_query {
+        if !interaction.is_changed() && !focus.is_changed() {
+            continue;
+        }
+
+        *background = match *interaction {
             Interaction::Pressed => palette.pressed,
+            Interaction::Hovered => palette.hovered,
+            Interaction::None if focus.0 == Some(entity) => palette.focused,
+            Interaction::None => palette.none,
         }
         .into();
     }
@@ -50,8 +59,8 @@ impl FromWorld for InteractionAssets {
     fn from_world(world: &mut World) -> Self {
         let assets = world.resource::<AssetServer>();
         Self {
-            hover: assets.load("audio/sound_effects/button_hover.ogg"),
-            click: assets.load("audio/sound_effects/button_click.ogg"),
+            hover: assets.load("audio/sound_effects/button_hover.eogg"),
+            click: assets.load("audio/sound_effects/button_click.eogg"),
         }
     }
 }
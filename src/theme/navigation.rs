@@ -0,0 +1,193 @@
+//! Keyboard and gamepad focus navigation between buttons.
+//!
+//! Buttons are activated by triggering the same [`Pointer<Click>`] a mouse click would, so
+//! every button works with focus navigation without any extra wiring.
+
+use std::time::Duration;
+
+use bevy::{
+    camera::NormalizedRenderTarget,
+    picking::{
+        backend::HitData,
+        pointer::{Location, PointerButton, PointerId},
+    },
+    prelude::*,
+    ui::UiGlobalTransform,
+    window::{PrimaryWindow, WindowRef},
+};
+use leafwing_input_manager::prelude::*;
+
+use crate::{game::controls::Action, menus::Menu};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<FocusedButton>();
+    app.add_systems(OnExit(Menu::None), clear_focus);
+    app.add_systems(
+        Update,
+        (
+            clear_lost_focus,
+            focus_on_hover,
+            navigate_focus,
+            activate_focus,
+        )
+            .chain()
+            .run_if(not(in_state(Menu::None))),
+    );
+}
+
+/// How far the navigation stick needs to be pushed to move focus
+const NAVIGATE_THRESHOLD: f32 = 0.5;
+
+/// Resource holding the button that has keyboard/gamepad focus, if any
+#[derive(Resource, Debug, Default)]
+pub struct FocusedButton(pub Option<Entity>);
+
+fn clear_focus(mut focus: ResMut<FocusedButton>) {
+    focus.0 = None;
+}
+
+/// System to drop focus from buttons that were despawned, such as when changing menus
+fn clear_lost_focus(mut focus: ResMut<FocusedButton>, button_query: Query<(), With<Button>>) {
+    if focus.0.is_some_and(|entity| !button_query.contains(entity)) {
+        focus.0 = None;
+    }
+}
+
+/// System to move focus to whichever button the mouse hovers, so the two never disagree
+fn focus_on_hover(
+    mut focus: ResMut<FocusedButton>,
+    button_query: Query<(Entity, &Interaction), (Changed<Interaction>, With<Button>)>,
+) {
+    for (entity, interaction) in &button_query {
+        if *interaction == Interaction::Hovered && focus.0 != Some(entity) {
+            focus.0 = Some(entity);
+        }
+    }
+}
+
+/// System to move focus to the nearest button in the direction pressed
+fn navigate_focus(
+    action_query: Query<&ActionState<Action>>,
+    mut held: Local<bool>,
+    mut focus: ResMut<FocusedButton>,
+    button_query: Query<(Entity, &UiGlobalTransform, &InheritedVisibility), With<Button>>,
+) {
+    let Ok(action_state) = action_query.single() else {
+        return;
+    };
+
+    let axis = action_state.axis_pair(&Action::Navigate);
+    // Only move once per push, rather than every frame the direction is held
+    let pressed = axis.length() > NAVIGATE_THRESHOLD;
+    let just_pressed = pressed && !*held;
+    *held = pressed;
+    if !just_pressed {
+        return;
+    }
+
+    // UI coordinates grow downwards
+    let direction = if axis.x.abs() > axis.y.abs() {
+        Vec2::new(axis.x.signum(), 0.0)
+    } else {
+        Vec2::new(0.0, -axis.y.signum())
+    };
+
+    let buttons: Vec<_> = button_query
+        .iter()
+        .filter(|(_, _, visibility)| visibility.get())
+        .map(|(entity, transform, _)| (entity, transform.translation))
+        .collect();
+
+    let current = focus
+        .0
+        .and_then(|focused| buttons.iter().find(|(entity, _)| *entity == focused));
+
+    focus.0 = match current {
+        Some((_, position)) => next_focus(*position, direction, &buttons).or(focus.0),
+        // Start from the top left button
+        None => buttons
+            .iter()
+            .min_by(|(_, a), (_, b)| a.y.total_cmp(&b.y).then(a.x.total_cmp(&b.x)))
+            .map(|(entity, _)| *entity),
+    };
+}
+
+/// Returns the closest button in `direction` from `from`, preferring buttons in line with it
+pub fn next_focus(from: Vec2, direction: Vec2, buttons: &[(Entity, Vec2)]) -> Option<Entity> {
+    buttons
+        .iter()
+        .filter_map(|(entity, position)| {
+            let offset = *position - from;
+            let along = offset.dot(direction);
+            if along <= 1.0 {
+                return None;
+            }
+            let across = offset.perp_dot(direction).abs();
+            Some((*entity, along + across * 2.0))
+        })
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(entity, _)| entity)
+}
+
+/// System to click the focused button when select is pressed
+fn activate_focus(
+    mut commands: Commands,
+    action_query: Query<&ActionState<Action>>,
+    focus: Res<FocusedButton>,
+    button_query: Query<&UiGlobalTransform, With<Button>>,
+    window: Single<Entity, With<PrimaryWindow>>,
+) {
+    let Ok(action_state) = action_query.single() else {
+        return;
+    };
+    if !action_state.just_pressed(&Action::Select) {
+        return;
+    }
+    let Some(entity) = focus.0 else {
+        return;
+    };
+    let Ok(transform) = button_query.get(entity) else {
+        return;
+    };
+    let Some(window_ref) = WindowRef::Primary.normalize(Some(*window)) else {
+        return;
+    };
+
+    let location = Location {
+        target: NormalizedRenderTarget::Window(window_ref),
+        position: transform.translation,
+    };
+    let click = Click {
+        button: PointerButton::Primary,
+        hit: HitData::new(Entity::PLACEHOLDER, 0.0, None, None),
+        duration: Duration::ZERO,
+    };
+    commands.trigger(Pointer::new(PointerId::Mouse, location, click, entity));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_focus_prefers_aligned_buttons() {
+        let a = Entity::from_raw_u32(1).unwrap();
+        let b = Entity::from_raw_u32(2).unwrap();
+        let c = Entity::from_raw_u32(3).unwrap();
+        let buttons = [
+            (a, Vec2::new(0.0, 0.0)),
+            (b, Vec2::new(0.0, 100.0)),
+            (c, Vec2::new(60.0, 80.0)),
+        ];
+
+        // Down from the top button picks the one directly below
+        assert_eq!(next_focus(Vec2::ZERO, Vec2::Y, &buttons), Some(b));
+        // Nothing above the top button
+        assert_eq!(next_focus(Vec2::ZERO, -Vec2::Y, &buttons), None);
+        // Right from the bottom button picks the one to its right
+        assert_eq!(
+            next_focus(Vec2::new(0.0, 100.0), Vec2::X, &buttons),
+            Some(c)
+        );
+    }
+}
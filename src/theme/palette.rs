@@ -13,7 +13,13 @@ pub static BUTTON_BACKGROUND: LazyLock<Color> =
 pub static BUTTON_HOVERED_BACKGROUND: LazyLock<Color> =
     LazyLock::new(|| Srgba::hex("#6299d1").unwrap().into());
 
+pub static BUTTON_FOCUSED_BACKGROUND: LazyLock<Color> =
+    LazyLock::new(|| Srgba::hex("#5a80c8").unwrap().into());
+
 pub static BUTTON_PRESSED_BACKGROUND: LazyLock<Color> =
     LazyLock::new(|| Srgba::hex("#3d4999").unwrap().into());
 
+pub static TOUCH_BACKGROUND: LazyLock<Color> =
+    LazyLock::new(|| Srgba::hex("#ececec").unwrap().into());
+
 pub static TRANSPARENT: LazyLock<Color> = LazyLock::new(|| Srgba::hex("#00000000").unwrap().into());
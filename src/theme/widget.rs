@@ -71,6 +71,29 @@ where
     )
 }
 
+/// A medium rounded button with text and an action defined as an [`Observer`], for rows of options.
+pub fn button_medium<E, B, M, I>(text: impl Into<String>, action: I) -> impl Bundle
+where
+    E: EntityEvent,
+    B: Bundle,
+    I: IntoObserverSystem<E, B, M>,
+{
+    button_base(
+        text,
+        action,
+        (
+            Node {
+                width: px(160),
+                height: px(60),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            BorderRadius::MAX,
+        ),
+    )
+}
+
 /// A small square button with text and an action defined as an [`Observer`].
 pub fn button_small<E, B, M, I>(text: impl Into<String>, action: I) -> impl Bundle
 where
@@ -117,6 +140,7 @@ where
                         none: *BUTTON_BACKGROUND,
                         hovered: *BUTTON_HOVERED_BACKGROUND,
                         pressed: *BUTTON_PRESSED_BACKGROUND,
+                        focused: *BUTTON_FOCUSED_BACKGROUND,
                     },
                     children![(
                         Name::new("Button Text"),